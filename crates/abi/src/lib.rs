@@ -0,0 +1,817 @@
+use std::fmt::Write;
+use std::mem;
+use wit_bindgen_core::wit_parser::abi::{
+    AbiVariant, Bindgen, Instruction, LiftLower, WasmSignature, WasmType,
+};
+use wit_bindgen_core::{
+    uwriteln, wit_parser, Diagnostic, FileInfo, Files, Source, Style, Types, WorldGenerator,
+};
+use wit_parser::*;
+
+const PRODUCER: &str = "wit-bindgen-abi";
+
+#[derive(Default)]
+struct Abi {
+    src: Source,
+    opts: Opts,
+    sizes: SizeAlign,
+    types: Types,
+}
+
+#[derive(Default, Debug, Clone)]
+#[cfg_attr(feature = "clap", derive(clap::Args))]
+pub struct Opts {
+    /// Don't print the lift/lower instruction sequence of each function,
+    /// only its signatures and memory layouts.
+    #[cfg_attr(feature = "clap", arg(long))]
+    pub no_instructions: bool,
+}
+
+impl Opts {
+    pub fn build(&self) -> Box<dyn WorldGenerator> {
+        let mut r = Abi::default();
+        r.src = Source::new(Style::MARKDOWN);
+        r.opts = self.clone();
+        Box::new(r)
+    }
+}
+
+impl WorldGenerator for Abi {
+    fn preprocess(&mut self, resolve: &Resolve, world: WorldId) -> Result<(), Diagnostic> {
+        self.sizes.fill(resolve);
+        self.types.analyze(resolve);
+        uwriteln!(
+            self.src,
+            "# Canonical ABI of world `{}`\n",
+            resolve.worlds[world].name
+        );
+        Ok(())
+    }
+
+    fn import_interface(
+        &mut self,
+        resolve: &Resolve,
+        name: &WorldKey,
+        iface: InterfaceId,
+        _files: &mut Files,
+    ) -> Result<(), Diagnostic> {
+        let name = resolve.name_world_key(name);
+        for func in resolve.interfaces[iface].functions.values() {
+            self.function(resolve, Some(&name), func, true);
+        }
+        Ok(())
+    }
+
+    fn export_interface(
+        &mut self,
+        resolve: &Resolve,
+        name: &WorldKey,
+        iface: InterfaceId,
+        _files: &mut Files,
+    ) -> Result<(), Diagnostic> {
+        let name = resolve.name_world_key(name);
+        for func in resolve.interfaces[iface].functions.values() {
+            self.function(resolve, Some(&name), func, false);
+        }
+        Ok(())
+    }
+
+    fn import_funcs(
+        &mut self,
+        resolve: &Resolve,
+        _world: WorldId,
+        funcs: &[(&str, &Function)],
+        _files: &mut Files,
+    ) -> Result<(), Diagnostic> {
+        for (_, func) in funcs {
+            self.function(resolve, None, func, true);
+        }
+        Ok(())
+    }
+
+    fn export_funcs(
+        &mut self,
+        resolve: &Resolve,
+        _world: WorldId,
+        funcs: &[(&str, &Function)],
+        _files: &mut Files,
+    ) -> Result<(), Diagnostic> {
+        for (_, func) in funcs {
+            self.function(resolve, None, func, false);
+        }
+        Ok(())
+    }
+
+    fn export_types(
+        &mut self,
+        _resolve: &Resolve,
+        _world: WorldId,
+        _types: &[(&str, TypeId)],
+        _files: &mut Files,
+    ) -> Result<(), Diagnostic> {
+        // Types are described where they're used by functions.
+        Ok(())
+    }
+
+    fn finish(
+        &mut self,
+        resolve: &Resolve,
+        world: WorldId,
+        files: &mut Files,
+    ) -> Result<(), Diagnostic> {
+        files.create(
+            &format!("{}-abi.md", resolve.worlds[world].name),
+            FileInfo::text(PRODUCER, "markdown"),
+            self.src.as_bytes(),
+        )
+    }
+}
+
+impl Abi {
+    fn function(
+        &mut self,
+        resolve: &Resolve,
+        interface: Option<&str>,
+        func: &Function,
+        import: bool,
+    ) {
+        let (kind, variant, lift_lower) = if import {
+            (
+                "Import",
+                AbiVariant::GuestImport,
+                LiftLower::LowerArgsLiftResults,
+            )
+        } else {
+            (
+                "Export",
+                AbiVariant::GuestExport,
+                LiftLower::LiftArgsLowerResults,
+            )
+        };
+        match interface {
+            Some(iface) => uwriteln!(self.src, "## {kind} `{iface}#{}`\n", func.name),
+            None => uwriteln!(self.src, "## {kind} `{}`\n", func.name),
+        }
+        let export_name = func.core_export_name(interface);
+        if import {
+            uwriteln!(
+                self.src,
+                "Imported from module `{}` as `{}`.\n",
+                interface.unwrap_or("$root"),
+                func.name
+            );
+        } else {
+            uwriteln!(self.src, "Exported as `{export_name}`.\n");
+        }
+
+        uwriteln!(self.src, "### Core signatures\n");
+        for (label, variant) in [
+            ("import", AbiVariant::GuestImport),
+            ("export", AbiVariant::GuestExport),
+        ] {
+            let sig = resolve.wasm_signature(variant, func);
+            uwriteln!(self.src, "- {label}: {}", signature(&sig));
+        }
+        self.src.push_str("\n");
+
+        uwriteln!(self.src, "### Memory layout\n");
+        let mut any = false;
+        for (name, ty) in func.params.iter() {
+            self.layout(resolve, &format!("Parameter `{name}`"), name, ty);
+            any = true;
+        }
+        match &func.results {
+            Results::Named(results) => {
+                for (name, ty) in results.iter() {
+                    self.layout(resolve, &format!("Result `{name}`"), name, ty);
+                    any = true;
+                }
+            }
+            Results::Anon(ty) => {
+                self.layout(resolve, "Result", "ret", ty);
+                any = true;
+            }
+        }
+        if !any {
+            self.src
+                .push_str("This function has no parameters or results.\n\n");
+        }
+
+        if !self.opts.no_instructions {
+            uwriteln!(self.src, "### Instructions\n");
+            let mut f = FunctionBindgen::new(&self.sizes, &self.types);
+            resolve.call(variant, lift_lower, func, &mut f);
+            let lines = f.lines;
+            self.code_block(&lines);
+        }
+
+        uwriteln!(self.src, "### Post-return\n");
+        if import {
+            self.src
+                .push_str("Post-return functions only apply to exports.\n\n");
+        } else if resolve.guest_export_needs_post_return(func) {
+            uwriteln!(
+                self.src,
+                "Required, exported as `cabi_post_{export_name}`.\n"
+            );
+            if !self.opts.no_instructions {
+                let mut f = FunctionBindgen::new(&self.sizes, &self.types);
+                resolve.post_return(func, &mut f);
+                let lines = f.lines;
+                self.code_block(&lines);
+            }
+        } else {
+            self.src.push_str("Not required.\n\n");
+        }
+    }
+
+    fn code_block(&mut self, lines: &[String]) {
+        self.src.push_str("```text\n");
+        for line in lines {
+            self.src.push_str(line);
+            self.src.push_str("\n");
+        }
+        self.src.push_str("```\n\n");
+    }
+
+    fn layout(&mut self, resolve: &Resolve, label: &str, name: &str, ty: &Type) {
+        uwriteln!(
+            self.src,
+            "#### {label}: `{}` (size {}, align {})\n",
+            type_name(resolve, ty),
+            self.sizes.size(ty),
+            self.sizes.align(ty),
+        );
+        let mut rows = Vec::new();
+        self.rows(resolve, name, ty, 0, &mut rows);
+        self.src.push_str("| offset | size | field | type |\n");
+        self.src.push_str("|---:|---:|---|---|\n");
+        for row in rows {
+            match row.ty {
+                Some(ty) => uwriteln!(
+                    self.src,
+                    "| {} | {} | `{}` | {ty} |",
+                    row.offset,
+                    row.size,
+                    row.path
+                ),
+                None => uwriteln!(self.src, "| {} | {} | (padding) | |", row.offset, row.size),
+            }
+        }
+        self.src.push_str("\n");
+    }
+
+    /// Appends the rows describing a value of type `ty` stored at `offset`.
+    fn rows(&self, resolve: &Resolve, path: &str, ty: &Type, offset: usize, rows: &mut Vec<Row>) {
+        let size = self.sizes.size(ty);
+        let id = match ty {
+            Type::Id(id) => *id,
+            Type::String => return pointer_and_length(path, offset, rows),
+            _ => return rows.push(Row::leaf(path, offset, size, type_name(resolve, ty))),
+        };
+        match &resolve.types[id].kind {
+            TypeDefKind::Record(r) => {
+                let fields = r
+                    .fields
+                    .iter()
+                    .map(|f| (format!("{path}.{}", f.name), &f.ty));
+                self.fields(resolve, fields, offset, size, rows)
+            }
+            TypeDefKind::Tuple(t) => {
+                let fields = t
+                    .types
+                    .iter()
+                    .enumerate()
+                    .map(|(i, ty)| (format!("{path}.{i}"), ty));
+                self.fields(resolve, fields, offset, size, rows)
+            }
+            TypeDefKind::Variant(v) => {
+                let cases = v.cases.iter().map(|c| (c.name.clone(), c.ty.as_ref()));
+                self.variant(resolve, path, v.tag(), cases, offset, size, rows)
+            }
+            TypeDefKind::Union(u) => {
+                let cases = u
+                    .cases
+                    .iter()
+                    .enumerate()
+                    .map(|(i, c)| (i.to_string(), Some(&c.ty)));
+                self.variant(resolve, path, u.tag(), cases, offset, size, rows)
+            }
+            TypeDefKind::Option(t) => {
+                let cases = [("none".to_string(), None), ("some".to_string(), Some(t))];
+                self.variant(resolve, path, Int::U8, cases, offset, size, rows)
+            }
+            TypeDefKind::Result(r) => {
+                let cases = [
+                    ("ok".to_string(), r.ok.as_ref()),
+                    ("err".to_string(), r.err.as_ref()),
+                ];
+                self.variant(resolve, path, Int::U8, cases, offset, size, rows)
+            }
+            TypeDefKind::Enum(e) => rows.push(Row::leaf(
+                path,
+                offset,
+                size,
+                format!("discriminant ({})", int_name(e.tag())),
+            )),
+            TypeDefKind::List(_) => pointer_and_length(path, offset, rows),
+            TypeDefKind::Type(t) => self.rows(resolve, path, t, offset, rows),
+            TypeDefKind::Flags(_)
+            | TypeDefKind::Future(_)
+            | TypeDefKind::Stream(_)
+            | TypeDefKind::Unknown => {
+                rows.push(Row::leaf(path, offset, size, type_name(resolve, ty)))
+            }
+        }
+    }
+
+    /// Appends rows for fields laid out one after another, as in a record or
+    /// tuple, padding the whole to `size` bytes.
+    fn fields<'a>(
+        &self,
+        resolve: &Resolve,
+        fields: impl Iterator<Item = (String, &'a Type)>,
+        offset: usize,
+        size: usize,
+        rows: &mut Vec<Row>,
+    ) {
+        let mut cur = 0;
+        for (path, ty) in fields {
+            let at = align_to(cur, self.sizes.align(ty));
+            Row::padding(offset + cur, at - cur, rows);
+            self.rows(resolve, &path, ty, offset + at, rows);
+            cur = at + self.sizes.size(ty);
+        }
+        Row::padding(offset + cur, size - cur, rows);
+    }
+
+    /// Appends rows for a discriminant followed by the payload of each case,
+    /// all of which start at the same offset.
+    fn variant<'a>(
+        &self,
+        resolve: &Resolve,
+        path: &str,
+        tag: Int,
+        cases: impl IntoIterator<Item = (String, Option<&'a Type>)>,
+        offset: usize,
+        size: usize,
+        rows: &mut Vec<Row>,
+    ) {
+        let cases = cases.into_iter().collect::<Vec<_>>();
+        let tag_size = int_size(tag);
+        rows.push(Row::leaf(
+            &format!("{path}.tag"),
+            offset,
+            tag_size,
+            format!("discriminant ({})", int_name(tag)),
+        ));
+        let payloads = cases
+            .iter()
+            .filter_map(|(name, ty)| ty.map(|ty| (name, ty)))
+            .collect::<Vec<_>>();
+        let align = payloads
+            .iter()
+            .map(|(_, ty)| self.sizes.align(ty))
+            .max()
+            .unwrap_or(1);
+        let payload_offset = align_to(tag_size, align);
+        let mut end = tag_size;
+        if !payloads.is_empty() {
+            Row::padding(offset + tag_size, payload_offset - tag_size, rows);
+            for (name, ty) in payloads {
+                self.rows(
+                    resolve,
+                    &format!("{path}::{name}"),
+                    ty,
+                    offset + payload_offset,
+                    rows,
+                );
+                end = end.max(payload_offset + self.sizes.size(ty));
+            }
+        }
+        Row::padding(offset + end, size - end, rows);
+    }
+}
+
+/// A row in the memory layout table of a type.
+struct Row {
+    offset: usize,
+    size: usize,
+    path: String,
+    /// A description of the value, or `None` for padding.
+    ty: Option<String>,
+}
+
+impl Row {
+    fn leaf(path: &str, offset: usize, size: usize, ty: String) -> Row {
+        Row {
+            offset,
+            size,
+            path: path.to_string(),
+            ty: Some(ty),
+        }
+    }
+
+    fn padding(offset: usize, size: usize, rows: &mut Vec<Row>) {
+        if size > 0 {
+            rows.push(Row {
+                offset,
+                size,
+                path: String::new(),
+                ty: None,
+            });
+        }
+    }
+}
+
+fn pointer_and_length(path: &str, offset: usize, rows: &mut Vec<Row>) {
+    rows.push(Row::leaf(
+        &format!("{path}.ptr"),
+        offset,
+        4,
+        "pointer (u32)".to_string(),
+    ));
+    rows.push(Row::leaf(
+        &format!("{path}.len"),
+        offset + 4,
+        4,
+        "length (u32)".to_string(),
+    ));
+}
+
+/// Records the instructions `Resolve::call` and `Resolve::post_return` emit
+/// for a function as lines of text.
+struct FunctionBindgen<'a> {
+    sizes: &'a SizeAlign,
+    types: &'a Types,
+    lines: Vec<String>,
+    block_storage: Vec<Vec<String>>,
+    blocks: Vec<(Vec<String>, Vec<String>)>,
+    next_value: usize,
+}
+
+impl<'a> FunctionBindgen<'a> {
+    fn new(sizes: &'a SizeAlign, types: &'a Types) -> FunctionBindgen<'a> {
+        FunctionBindgen {
+            sizes,
+            types,
+            lines: Vec::new(),
+            block_storage: Vec::new(),
+            blocks: Vec::new(),
+            next_value: 0,
+        }
+    }
+
+    fn value(&mut self) -> String {
+        let value = format!("v{}", self.next_value);
+        self.next_value += 1;
+        value
+    }
+}
+
+impl Bindgen for FunctionBindgen<'_> {
+    type Operand = String;
+
+    fn emit(
+        &mut self,
+        _resolve: &Resolve,
+        inst: &Instruction<'_>,
+        operands: &mut Vec<String>,
+        results: &mut Vec<String>,
+    ) {
+        let mut line = String::new();
+        for i in 0..inst.results_len() {
+            let value = self.value();
+            if i > 0 {
+                line.push_str(", ");
+            }
+            line.push_str(&value);
+            results.push(value);
+        }
+        if !line.is_empty() {
+            line.push_str(" = ");
+        }
+        line.push_str(&describe(inst));
+        if !operands.is_empty() {
+            line.push_str(" ");
+            line.push_str(&operands.join(", "));
+        }
+        self.lines.push(line);
+
+        // An instruction's blocks are the last ones finished, for example one
+        // per case of a variant. Earlier ones belong to an instruction which
+        // hasn't been emitted yet, e.g. the enclosing variant of a payload.
+        let blocks = self.blocks.len() - block_count(inst);
+        let blocks = self.blocks.drain(blocks..).collect::<Vec<_>>();
+        for (i, (block, results)) in blocks.into_iter().enumerate() {
+            self.lines.push(format!("  block {i}:"));
+            for line in block {
+                self.lines.push(format!("    {line}"));
+            }
+            if !results.is_empty() {
+                self.lines.push(format!("    yield {}", results.join(", ")));
+            }
+        }
+    }
+
+    fn return_pointer(&mut self, size: usize, align: usize) -> String {
+        let value = self.value();
+        self.lines.push(format!(
+            "{value} = return area (size {size}, align {align})"
+        ));
+        value
+    }
+
+    fn push_block(&mut self) {
+        let prev = mem::take(&mut self.lines);
+        self.block_storage.push(prev);
+    }
+
+    fn finish_block(&mut self, operands: &mut Vec<String>) {
+        let to_restore = self.block_storage.pop().unwrap();
+        let lines = mem::replace(&mut self.lines, to_restore);
+        self.blocks.push((lines, mem::take(operands)));
+    }
+
+    fn sizes(&self) -> &SizeAlign {
+        self.sizes
+    }
+
+    fn is_list_canonical(&self, _resolve: &Resolve, ty: &Type) -> bool {
+        self.types.is_plain_old_data(ty)
+    }
+}
+
+/// Returns how many blocks `inst` consumes.
+fn block_count(inst: &Instruction<'_>) -> usize {
+    match inst {
+        Instruction::VariantLower { variant, .. } | Instruction::VariantLift { variant, .. } => {
+            variant.cases.len()
+        }
+        Instruction::UnionLower { union, .. } | Instruction::UnionLift { union, .. } => {
+            union.cases.len()
+        }
+        Instruction::OptionLower { .. }
+        | Instruction::OptionLift { .. }
+        | Instruction::ResultLower { .. }
+        | Instruction::ResultLift { .. } => 2,
+        Instruction::ListLower { .. }
+        | Instruction::ListLift { .. }
+        | Instruction::GuestDeallocateList { .. } => 1,
+        Instruction::GuestDeallocateVariant { blocks } => *blocks,
+        _ => 0,
+    }
+}
+
+/// Returns the name of `inst` along with any immediates which aren't
+/// described by its operands.
+fn describe(inst: &Instruction<'_>) -> String {
+    let debug = format!("{inst:?}");
+    let name = debug
+        .split(|c: char| !c.is_alphanumeric() && c != '_')
+        .next()
+        .unwrap();
+    let immediates = match inst {
+        Instruction::GetArg { nth } => format!("nth={nth}"),
+        Instruction::I32Const { val } => format!("val={val}"),
+        Instruction::I32Load { offset }
+        | Instruction::I32Load8U { offset }
+        | Instruction::I32Load8S { offset }
+        | Instruction::I32Load16U { offset }
+        | Instruction::I32Load16S { offset }
+        | Instruction::I64Load { offset }
+        | Instruction::F32Load { offset }
+        | Instruction::F64Load { offset }
+        | Instruction::I32Store { offset }
+        | Instruction::I32Store8 { offset }
+        | Instruction::I32Store16 { offset }
+        | Instruction::I64Store { offset }
+        | Instruction::F32Store { offset }
+        | Instruction::F64Store { offset } => format!("offset={offset}"),
+        Instruction::Bitcasts { casts } => format!("{casts:?}"),
+        Instruction::ConstZero { tys } => format!("{tys:?}"),
+        Instruction::CallWasm { name, .. } => format!("`{name}`"),
+        Instruction::CallInterface { func, .. } => format!("`{}`", func.name),
+        Instruction::GuestDeallocate { size, align } => format!("size={size} align={align}"),
+        _ => return name.to_string(),
+    };
+    format!("{name} {immediates}")
+}
+
+fn signature(sig: &WasmSignature) -> String {
+    let types = |tys: &[WasmType]| {
+        tys.iter()
+            .map(|t| wasm_type(*t))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    let mut s = format!("`({}) -> ({})`", types(&sig.params), types(&sig.results));
+    if sig.indirect_params {
+        s.push_str(", parameters are passed indirectly through memory");
+    }
+    if sig.retptr {
+        s.push_str(", results are returned through a pointer");
+    }
+    s
+}
+
+fn wasm_type(ty: WasmType) -> &'static str {
+    match ty {
+        WasmType::I32 => "i32",
+        WasmType::I64 => "i64",
+        WasmType::F32 => "f32",
+        WasmType::F64 => "f64",
+    }
+}
+
+fn type_name(resolve: &Resolve, ty: &Type) -> String {
+    let id = match ty {
+        Type::Bool => return "bool".to_string(),
+        Type::U8 => return "u8".to_string(),
+        Type::U16 => return "u16".to_string(),
+        Type::U32 => return "u32".to_string(),
+        Type::U64 => return "u64".to_string(),
+        Type::S8 => return "s8".to_string(),
+        Type::S16 => return "s16".to_string(),
+        Type::S32 => return "s32".to_string(),
+        Type::S64 => return "s64".to_string(),
+        Type::Float32 => return "float32".to_string(),
+        Type::Float64 => return "float64".to_string(),
+        Type::Char => return "char".to_string(),
+        Type::String => return "string".to_string(),
+        Type::Id(id) => *id,
+    };
+    let ty = &resolve.types[id];
+    if let Some(name) = &ty.name {
+        return name.clone();
+    }
+    let opt = |ty: Option<&Type>| match ty {
+        Some(ty) => type_name(resolve, ty),
+        None => "_".to_string(),
+    };
+    match &ty.kind {
+        TypeDefKind::List(t) => format!("list<{}>", type_name(resolve, t)),
+        TypeDefKind::Option(t) => format!("option<{}>", type_name(resolve, t)),
+        TypeDefKind::Result(r) => {
+            format!("result<{}, {}>", opt(r.ok.as_ref()), opt(r.err.as_ref()))
+        }
+        TypeDefKind::Tuple(t) => format!(
+            "tuple<{}>",
+            t.types
+                .iter()
+                .map(|t| type_name(resolve, t))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        TypeDefKind::Union(u) => format!(
+            "union {{ {} }}",
+            u.cases
+                .iter()
+                .map(|c| type_name(resolve, &c.ty))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        TypeDefKind::Future(t) => format!("future<{}>", opt(t.as_ref())),
+        TypeDefKind::Stream(s) => format!(
+            "stream<{}, {}>",
+            opt(s.element.as_ref()),
+            opt(s.end.as_ref())
+        ),
+        TypeDefKind::Type(t) => type_name(resolve, t),
+        _ => "<anonymous>".to_string(),
+    }
+}
+
+fn int_size(int: Int) -> usize {
+    match int {
+        Int::U8 => 1,
+        Int::U16 => 2,
+        Int::U32 => 4,
+        Int::U64 => 8,
+    }
+}
+
+fn int_name(int: Int) -> &'static str {
+    match int {
+        Int::U8 => "u8",
+        Int::U16 => "u16",
+        Int::U32 => "u32",
+        Int::U64 => "u64",
+    }
+}
+
+fn align_to(val: usize, align: usize) -> usize {
+    (val + align - 1) & !(align - 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn generate(src: &str) -> String {
+        let mut resolve = Resolve::default();
+        let pkg = UnresolvedPackage::parse("test.wit".as_ref(), src).unwrap();
+        let pkg = resolve.push(pkg).unwrap();
+        let world = resolve.select_world(pkg, Some("w")).unwrap();
+        let mut files = Files::default();
+        Opts::default()
+            .build()
+            .generate(&resolve, world, &Default::default(), &mut files)
+            .unwrap();
+        let (_, contents) = files.iter().next().unwrap();
+        String::from_utf8(contents.to_vec()).unwrap()
+    }
+
+    #[test]
+    fn layout() {
+        let contents = generate(
+            "package a:b
+
+            interface i {
+                record r { a: u8, b: u32, c: option<u64> }
+                f: func(x: r) -> string
+            }
+
+            world w {
+                export i
+            }",
+        );
+
+        let expected = "\
+#### Parameter `x`: `r` (size 24, align 8)
+
+| offset | size | field | type |
+|---:|---:|---|---|
+| 0 | 1 | `x.a` | u8 |
+| 1 | 3 | (padding) | |
+| 4 | 4 | `x.b` | u32 |
+| 8 | 1 | `x.c.tag` | discriminant (u8) |
+| 9 | 7 | (padding) | |
+| 16 | 8 | `x.c::some` | u64 |
+";
+        assert!(contents.contains(expected), "{contents}");
+        assert!(
+            contents.contains(
+                "- export: `(i32, i32, i32, i64) -> (i32)`, results are returned through a pointer"
+            ),
+            "{contents}"
+        );
+        assert!(
+            contents.contains("Required, exported as `cabi_post_a:b/i#f`."),
+            "{contents}"
+        );
+    }
+
+    #[test]
+    fn variant_blocks() {
+        let contents = generate(
+            "package a:b
+
+            interface i {
+                variant v { a(u32), b(string) }
+                f: func(x: v)
+            }
+
+            world w {
+                import i
+            }",
+        );
+        let instructions = contents.split("### Instructions\n").nth(1).unwrap();
+        let instructions = instructions.split("```\n").next().unwrap();
+        let lines = instructions.lines().collect::<Vec<_>>();
+
+        // Each case's block is listed under the `VariantLower` which consumes
+        // it, not under the first instruction of the next case.
+        let lower = lines
+            .iter()
+            .position(|l| l.contains("= VariantLower "))
+            .unwrap_or_else(|| panic!("{contents}"));
+        assert_eq!(lines[lower + 1], "  block 0:", "{contents}");
+        assert!(
+            lines[lower + 2].ends_with("= VariantPayloadName"),
+            "{contents}"
+        );
+        let block1 = lines
+            .iter()
+            .position(|l| *l == "  block 1:")
+            .unwrap_or_else(|| panic!("{contents}"));
+        assert!(
+            lines[lower + 2..block1]
+                .iter()
+                .all(|l| l.starts_with("    ")),
+            "{contents}"
+        );
+        assert!(
+            lines[block1 + 1].ends_with("= VariantPayloadName"),
+            "{contents}"
+        );
+        assert_eq!(
+            lines
+                .iter()
+                .filter(|l| l.trim_start().starts_with("block "))
+                .count(),
+            2,
+            "{contents}"
+        );
+    }
+}
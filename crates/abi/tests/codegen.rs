@@ -0,0 +1,29 @@
+use std::path::Path;
+
+macro_rules! codegen_test {
+    ($id:ident $name:tt $test:tt) => {
+        #[test]
+        fn $id() {
+            test_helpers::run_world_codegen_test(
+                "abi",
+                $test.as_ref(),
+                |resolve, world, files| {
+                    wit_bindgen_abi::Opts::default().build().generate(
+                        resolve,
+                        world,
+                        &Default::default(),
+                        files,
+                    )
+                },
+                verify,
+            );
+        }
+    };
+}
+
+test_helpers::codegen_tests!();
+
+fn verify(dir: &Path, name: &str) {
+    let contents = std::fs::read_to_string(dir.join(format!("{name}-abi.md"))).unwrap();
+    assert!(contents.starts_with(&format!("# Canonical ABI of world `{name}`")));
+}
@@ -8,10 +8,13 @@ use wit_bindgen_core::wit_parser::abi::{
     AbiVariant, Bindgen, Bitcast, Instruction, LiftLower, WasmType,
 };
 use wit_bindgen_core::{
-    uwrite, uwriteln, wit_parser::*, Files, InterfaceGenerator as _, Ns, WorldGenerator,
+    check_types, reject_async_types, uwrite, uwriteln, wit_parser::*, Casing, Diagnostic, FileInfo,
+    Files, InterfaceGenerator as _, Names, Ns, Profile, Renames, Style, Types, WorldGenerator,
 };
 use wit_component::StringEncoding;
 
+const PRODUCER: &str = "wit-bindgen-c";
+
 #[derive(Default)]
 struct C {
     src: Source,
@@ -20,9 +23,13 @@ struct C {
     return_pointer_area_size: usize,
     return_pointer_area_align: usize,
     names: Ns,
+    // The identifiers of types, functions, fields and cases, which are
+    // qualified with `owner_namespace` where they're used.
+    idents: Names,
     needs_string: bool,
     world: String,
     sizes: SizeAlign,
+    type_info: Types,
 
     // Known names for interfaces as they're seen in imports and exports.
     //
@@ -53,6 +60,9 @@ struct C {
     // implementation of functions. These types go in the implementation file,
     // not the header file.
     private_anonymous_types: BTreeSet<TypeId>,
+
+    // Definitions of the exported functions for the stub, if requested.
+    stub: wit_bindgen_core::Source,
 }
 
 #[derive(Default, Debug, Clone)]
@@ -67,6 +77,16 @@ pub struct Opts {
     // Skip optional null pointer and boolean result argument signature flattening
     #[cfg_attr(feature = "clap", arg(long, default_value_t = false))]
     pub no_sig_flattening: bool,
+    /// Whether or not to generate a stub implementation of the exported
+    /// functions, `{world}_stub.c`
+    #[cfg_attr(feature = "clap", arg(long))]
+    pub generate_stub: bool,
+    /// Overrides the C identifier used for an item, in the form `PATH=NAME`,
+    /// for example `a:b/iface#ty.field=renamed`. Types and functions are
+    /// still qualified with the name of their interface. May be specified
+    /// multiple times.
+    #[cfg_attr(feature = "clap", arg(long = "rename", value_name = "PATH=NAME"))]
+    pub rename: Vec<String>,
 }
 
 impl Opts {
@@ -100,10 +120,21 @@ enum Scalar {
 }
 
 impl WorldGenerator for C {
-    fn preprocess(&mut self, resolve: &Resolve, world: WorldId) {
+    fn preprocess(&mut self, resolve: &Resolve, world: WorldId) -> Result<(), Diagnostic> {
+        if let StringEncoding::CompactUTF16 = self.opts.string_encoding {
+            return Err(Diagnostic::unsupported(
+                "C",
+                "the `compact-utf16` string encoding",
+            ));
+        }
+        check_types(resolve, world, reject_async_types("C"))?;
+        self.idents = Names::new(Profile::C, Renames::parse(&self.opts.rename)?);
+        self.idents.assign(resolve, world)?;
         let name = &resolve.worlds[world].name;
         self.world = name.to_string();
         self.sizes.fill(resolve);
+        self.type_info.analyze(resolve);
+        Ok(())
     }
 
     fn import_interface(
@@ -112,13 +143,13 @@ impl WorldGenerator for C {
         name: &WorldKey,
         id: InterfaceId,
         _files: &mut Files,
-    ) {
+    ) -> Result<(), Diagnostic> {
         let prev = self.interface_names.insert(id, name.clone());
         assert!(prev.is_none());
         let mut gen = self.interface(resolve, true);
         gen.interface = Some(id);
         if gen.gen.interfaces_with_types_printed.insert(id) {
-            gen.types(id);
+            gen.types(id)?;
         }
 
         for (i, (_name, func)) in resolve.interfaces[id].functions.iter().enumerate() {
@@ -130,6 +161,7 @@ impl WorldGenerator for C {
         }
 
         gen.gen.src.append(&gen.src);
+        Ok(())
     }
 
     fn import_funcs(
@@ -138,7 +170,7 @@ impl WorldGenerator for C {
         world: WorldId,
         funcs: &[(&str, &Function)],
         _files: &mut Files,
-    ) {
+    ) -> Result<(), Diagnostic> {
         let name = &resolve.worlds[world].name;
         let mut gen = self.interface(resolve, true);
 
@@ -150,6 +182,7 @@ impl WorldGenerator for C {
         }
 
         gen.gen.src.append(&gen.src);
+        Ok(())
     }
 
     fn export_interface(
@@ -158,12 +191,12 @@ impl WorldGenerator for C {
         name: &WorldKey,
         id: InterfaceId,
         _files: &mut Files,
-    ) {
+    ) -> Result<(), Diagnostic> {
         self.interface_names.insert(id, name.clone());
         let mut gen = self.interface(resolve, false);
         gen.interface = Some(id);
         if gen.gen.interfaces_with_types_printed.insert(id) {
-            gen.types(id);
+            gen.types(id)?;
         }
 
         for (i, (_name, func)) in resolve.interfaces[id].functions.iter().enumerate() {
@@ -175,6 +208,7 @@ impl WorldGenerator for C {
         }
 
         gen.gen.src.append(&gen.src);
+        Ok(())
     }
 
     fn export_funcs(
@@ -183,7 +217,7 @@ impl WorldGenerator for C {
         world: WorldId,
         funcs: &[(&str, &Function)],
         _files: &mut Files,
-    ) {
+    ) -> Result<(), Diagnostic> {
         let name = &resolve.worlds[world].name;
         let mut gen = self.interface(resolve, false);
 
@@ -195,6 +229,7 @@ impl WorldGenerator for C {
         }
 
         gen.gen.src.append(&gen.src);
+        Ok(())
     }
 
     fn export_types(
@@ -203,15 +238,21 @@ impl WorldGenerator for C {
         _world: WorldId,
         types: &[(&str, TypeId)],
         _files: &mut Files,
-    ) {
+    ) -> Result<(), Diagnostic> {
         let mut gen = self.interface(resolve, false);
         for (name, id) in types {
-            gen.define_type(name, *id);
+            gen.define_type(name, *id)?;
         }
         gen.gen.src.append(&gen.src);
+        Ok(())
     }
 
-    fn finish(&mut self, resolve: &Resolve, id: WorldId, files: &mut Files) {
+    fn finish(
+        &mut self,
+        resolve: &Resolve,
+        id: WorldId,
+        files: &mut Files,
+    ) -> Result<(), Diagnostic> {
         self.finish_types(resolve);
         let world = &resolve.worlds[id];
         let linking_symbol = component_type_object::linking_symbol(&world.name);
@@ -253,7 +294,8 @@ impl WorldGenerator for C {
                     );
                     (format!("{snake}_string_len(s)"), 2)
                 }
-                StringEncoding::CompactUTF16 => unimplemented!(),
+                // Rejected in `preprocess`.
+                StringEncoding::CompactUTF16 => unreachable!(),
             };
             let ty = self.char_type();
             uwrite!(
@@ -289,7 +331,7 @@ impl WorldGenerator for C {
             );
         }
         let version = env!("CARGO_PKG_VERSION");
-        let mut h_str = wit_bindgen_core::Source::default();
+        let mut h_str = wit_bindgen_core::Source::new(Style::C);
         uwriteln!(
             h_str,
             "// Generated by `wit-bindgen` {version}. DO NOT EDIT!"
@@ -315,7 +357,7 @@ impl WorldGenerator for C {
             uwriteln!(h_str, "#include {include}");
         }
 
-        let mut c_str = wit_bindgen_core::Source::default();
+        let mut c_str = wit_bindgen_core::Source::new(Style::C);
         uwriteln!(
             c_str,
             "// Generated by `wit-bindgen` {version}. DO NOT EDIT!"
@@ -383,14 +425,37 @@ impl WorldGenerator for C {
             #endif"
         );
 
-        files.push(&format!("{snake}.c"), c_str.as_bytes());
-        files.push(&format!("{snake}.h"), h_str.as_bytes());
-        files.push(
+        let object = component_type_object::object(resolve, id, self.opts.string_encoding)
+            .map_err(|e| Diagnostic::new(format!("failed to encode component type: {e:?}")))?;
+        files.create(
+            &format!("{snake}.c"),
+            FileInfo::text(PRODUCER, "c"),
+            c_str.as_bytes(),
+        )?;
+        files.create(
+            &format!("{snake}.h"),
+            FileInfo::text(PRODUCER, "c"),
+            h_str.as_bytes(),
+        )?;
+        files.create(
             &format!("{snake}_component_type.o",),
-            component_type_object::object(resolve, id, self.opts.string_encoding)
-                .unwrap()
-                .as_slice(),
-        );
+            FileInfo::binary(PRODUCER),
+            object.as_slice(),
+        )?;
+
+        if self.opts.generate_stub {
+            let mut stub = wit_bindgen_core::Source::new(Style::C);
+            uwriteln!(stub, "// Generated by `wit-bindgen` {version}.");
+            uwriteln!(stub, "#include <stdlib.h>");
+            uwriteln!(stub, "#include \"{snake}.h\"");
+            stub.push_str(&self.stub);
+            files.create(
+                &format!("{snake}_stub.c"),
+                FileInfo::text(PRODUCER, "c"),
+                stub.as_bytes(),
+            )?;
+        }
+        Ok(())
     }
 }
 
@@ -417,7 +482,7 @@ impl C {
         match self.opts.string_encoding {
             StringEncoding::UTF8 => "char",
             StringEncoding::UTF16 => "char16_t",
-            StringEncoding::CompactUTF16 => panic!("Compact UTF16 unsupported"),
+            StringEncoding::CompactUTF16 => unreachable!("rejected in `preprocess`"),
         }
     }
 
@@ -472,7 +537,7 @@ impl C {
         // bindings as they'll be the same as the first.
         let mut name = self.owner_namespace(resolve, ty);
         name.push_str("_");
-        push_ty_name(resolve, &Type::Id(ty), &mut name);
+        push_ty_name(&self.idents, resolve, &Type::Id(ty), &mut name);
         name.push_str("_t");
         if self.names.insert(&name).is_err() {
             return;
@@ -544,20 +609,20 @@ impl C {
     }
 
     fn print_dtor(&mut self, resolve: &Resolve, id: TypeId) {
-        let ty = Type::Id(id);
-        if !owns_anything(resolve, &ty) {
+        if self.type_info.get(id).fixed_size() {
             return;
         }
+        let ty = Type::Id(id);
         let pos = self.src.h_helpers.len();
         self.src.h_helpers("\nvoid ");
         let ns = self.owner_namespace(resolve, id);
         self.src.h_helpers(&ns);
         self.src.h_helpers("_");
-        self.src.h_helpers.print_ty_name(resolve, &ty);
+        self.src.h_helpers.print_ty_name(&self.idents, resolve, &ty);
         self.src.h_helpers("_free(");
         self.src.h_helpers(&ns);
         self.src.h_helpers("_");
-        self.src.h_helpers.print_ty_name(resolve, &ty);
+        self.src.h_helpers.print_ty_name(&self.idents, resolve, &ty);
         self.src.h_helpers("_t *ptr)");
 
         self.src.c_helpers(&self.src.h_helpers[pos..].to_string());
@@ -570,15 +635,12 @@ impl C {
             TypeDefKind::Enum(_) => {}
 
             TypeDefKind::Record(r) => {
-                for field in r.fields.iter() {
+                for (i, field) in r.fields.iter().enumerate() {
                     if !owns_anything(resolve, &field.ty) {
                         continue;
                     }
-                    self.free(
-                        resolve,
-                        &field.ty,
-                        &format!("&ptr->{}", to_c_ident(&field.name)),
-                    );
+                    let member = self.idents.field(id, i, &field.name);
+                    self.free(resolve, &field.ty, &format!("&ptr->{member}"));
                 }
             }
 
@@ -611,7 +673,8 @@ impl C {
                             continue;
                         }
                         uwriteln!(self.src.c_helpers, "case {}: {{", i);
-                        let expr = format!("&ptr->val.{}", to_c_ident(&case.name));
+                        let member = self.payload_member(id, i, &case.name);
+                        let expr = format!("&ptr->val.{member}");
                         if let Some(ty) = &case.ty {
                             self.free(resolve, ty, &expr);
                         }
@@ -677,7 +740,7 @@ impl C {
             }
         }
         self.src.h_helpers("_");
-        self.src.h_helpers.print_ty_name(resolve, ty);
+        self.src.h_helpers.print_ty_name(&self.idents, resolve, ty);
         let name = mem::replace(&mut self.src.h_helpers, prev);
 
         self.src.c_helpers(&name);
@@ -689,32 +752,21 @@ impl C {
     fn owner_namespace(&mut self, resolve: &Resolve, id: TypeId) -> String {
         let ty = &resolve.types[id];
         match ty.owner {
-            TypeOwner::Interface(owner) => {
-                let key = &self.interface_names[&owner];
-                match key {
-                    WorldKey::Name(name) => name.to_snake_case(),
-                    WorldKey::Interface(id) => {
-                        let mut ns = String::new();
-                        let iface = &resolve.interfaces[*id];
-                        let pkg = &resolve.packages[iface.package.unwrap()];
-                        ns.push_str(&pkg.name.namespace.to_snake_case());
-                        ns.push_str("_");
-                        ns.push_str(&pkg.name.name.to_snake_case());
-                        ns.push_str("_");
-                        ns.push_str(&iface.name.as_ref().unwrap().to_snake_case());
-                        ns
-                    }
-                }
-            }
-
-            TypeOwner::World(owner) => resolve.worlds[owner].name.to_snake_case(),
-
             // Namespace everything else under the "default" world being
             // generated to avoid putting too much into the root namespace in C.
             TypeOwner::None => self.world.to_snake_case(),
+
+            owner => self.idents.qualifier(resolve, owner),
         }
     }
 
+    /// Returns the name of the member of the `val` union of the variant `id`
+    /// which holds the payload of its `index`th case.
+    fn payload_member(&self, id: TypeId, index: usize, name: &str) -> String {
+        let case = self.idents.case(id, index, name);
+        Profile::C.escape(case.to_snake_case())
+    }
+
     fn type_name(&mut self, resolve: &Resolve, ty: &Type) -> String {
         let mut name = String::new();
         self.push_type_name(resolve, ty, &mut name);
@@ -745,10 +797,10 @@ impl C {
                 let ty = &resolve.types[*id];
                 let ns = self.owner_namespace(resolve, *id);
                 match &ty.name {
-                    Some(name) => {
+                    Some(_) => {
                         dst.push_str(&ns);
                         dst.push_str("_");
-                        dst.push_str(&name.to_snake_case());
+                        dst.push_str(&self.idents.type_name(resolve, *id));
                         dst.push_str("_t");
                     }
                     None => match &ty.kind {
@@ -758,7 +810,7 @@ impl C {
                             self.private_anonymous_types.remove(id);
                             dst.push_str(&ns);
                             dst.push_str("_");
-                            push_ty_name(resolve, &Type::Id(*id), dst);
+                            push_ty_name(&self.idents, resolve, &Type::Id(*id), dst);
                             dst.push_str("_t");
                         }
                     },
@@ -874,11 +926,11 @@ impl<'a> wit_bindgen_core::InterfaceGenerator<'a> for InterfaceGenerator<'a> {
         self.src.h_defs("\n");
         self.docs(docs, SourceType::HDefs);
         self.src.h_defs("typedef struct {\n");
-        for field in record.fields.iter() {
+        for (i, field) in record.fields.iter().enumerate() {
             self.docs(&field.docs, SourceType::HDefs);
             self.print_ty(SourceType::HDefs, &field.ty);
             self.src.h_defs(" ");
-            self.src.h_defs(&to_c_ident(&field.name));
+            self.src.h_defs(&self.gen.idents.field(id, i, &field.name));
             self.src.h_defs(";\n");
         }
         self.src.h_defs("} ");
@@ -915,17 +967,13 @@ impl<'a> wit_bindgen_core::InterfaceGenerator<'a> for InterfaceGenerator<'a> {
         if flags.flags.len() > 0 {
             self.src.h_defs("\n");
         }
-        let ns = self
-            .gen
-            .owner_namespace(self.resolve, id)
-            .to_shouty_snake_case();
+        let prefix = self.constant_prefix(id);
         for (i, flag) in flags.flags.iter().enumerate() {
             self.docs(&flag.docs, SourceType::HDefs);
             uwriteln!(
                 self.src.h_defs,
-                "#define {ns}_{}_{} (1 << {i})",
-                name.to_shouty_snake_case(),
-                flag.name.to_shouty_snake_case(),
+                "#define {prefix}_{} (1 << {i})",
+                self.gen.idents.case(id, i, &flag.name),
             );
         }
 
@@ -940,11 +988,11 @@ impl<'a> wit_bindgen_core::InterfaceGenerator<'a> for InterfaceGenerator<'a> {
         self.src.h_defs(int_repr(variant.tag()));
         self.src.h_defs(" tag;\n");
         self.src.h_defs("union {\n");
-        for case in variant.cases.iter() {
+        for (i, case) in variant.cases.iter().enumerate() {
             if let Some(ty) = get_nonempty_type(self.resolve, case.ty.as_ref()) {
                 self.print_ty(SourceType::HDefs, ty);
                 self.src.h_defs(" ");
-                self.src.h_defs(&to_c_ident(&case.name));
+                self.src.h_defs(&self.gen.payload_member(id, i, &case.name));
                 self.src.h_defs(";\n");
             }
         }
@@ -955,17 +1003,13 @@ impl<'a> wit_bindgen_core::InterfaceGenerator<'a> for InterfaceGenerator<'a> {
         if variant.cases.len() > 0 {
             self.src.h_defs("\n");
         }
-        let ns = self
-            .gen
-            .owner_namespace(self.resolve, id)
-            .to_shouty_snake_case();
+        let prefix = self.constant_prefix(id);
         for (i, case) in variant.cases.iter().enumerate() {
             self.docs(&case.docs, SourceType::HDefs);
             uwriteln!(
                 self.src.h_defs,
-                "#define {ns}_{}_{} {i}",
-                name.to_shouty_snake_case(),
-                case.name.to_shouty_snake_case(),
+                "#define {prefix}_{} {i}",
+                self.gen.idents.case(id, i, &case.name),
             );
         }
 
@@ -1041,17 +1085,13 @@ impl<'a> wit_bindgen_core::InterfaceGenerator<'a> for InterfaceGenerator<'a> {
         if enum_.cases.len() > 0 {
             self.src.h_defs("\n");
         }
-        let ns = self
-            .gen
-            .owner_namespace(self.resolve, id)
-            .to_shouty_snake_case();
+        let prefix = self.constant_prefix(id);
         for (i, case) in enum_.cases.iter().enumerate() {
             self.docs(&case.docs, SourceType::HDefs);
             uwriteln!(
                 self.src.h_defs,
-                "#define {ns}_{}_{} {i}",
-                name.to_shouty_snake_case(),
-                case.name.to_shouty_snake_case(),
+                "#define {prefix}_{} {i}",
+                self.gen.idents.case(id, i, &case.name),
             );
         }
 
@@ -1091,26 +1131,28 @@ impl InterfaceGenerator<'_> {
     fn c_func_name(&self, interface_name: Option<&WorldKey>, func: &Function) -> String {
         let mut name = String::new();
         match interface_name {
-            Some(WorldKey::Name(k)) => name.push_str(&k.to_snake_case()),
-            Some(WorldKey::Interface(id)) => {
-                if !self.in_import {
+            Some(key) => {
+                if let (WorldKey::Interface(_), false) = (key, self.in_import) {
                     name.push_str("exports_");
                 }
-                let iface = &self.resolve.interfaces[*id];
-                let pkg = &self.resolve.packages[iface.package.unwrap()];
-                name.push_str(&pkg.name.namespace.to_snake_case());
-                name.push_str("_");
-                name.push_str(&pkg.name.name.to_snake_case());
-                name.push_str("_");
-                name.push_str(&iface.name.as_ref().unwrap().to_snake_case());
+                let module = self.gen.idents.module(self.resolve, key, self.in_import);
+                name.push_str(&module);
             }
             None => name.push_str(&self.gen.world.to_snake_case()),
         }
         name.push_str("_");
-        name.push_str(&func.name.to_snake_case());
+        name.push_str(&self.gen.idents.function(self.interface, func));
         name
     }
 
+    /// Returns the prefix of the constants defined for the cases of the
+    /// flags, variant or enum `id`.
+    fn constant_prefix(&mut self, id: TypeId) -> String {
+        let ns = self.gen.owner_namespace(self.resolve, id);
+        let name = self.gen.idents.type_name(self.resolve, id);
+        format!("{ns}_{name}").to_shouty_snake_case()
+    }
+
     fn import(&mut self, interface_name: Option<&WorldKey>, func: &Function) {
         self.docs(&func.docs, SourceType::HFns);
         let sig = self.resolve.wasm_signature(AbiVariant::GuestImport, func);
@@ -1234,6 +1276,16 @@ impl InterfaceGenerator<'_> {
         // it's what we'll be calling.
         let h_sig = self.print_sig(interface_name, func, !self.gen.opts.no_sig_flattening);
 
+        // Functions without results get an empty body in the stub, the rest
+        // abort until they're implemented.
+        if self.gen.opts.generate_stub {
+            let body = match func.results.len() {
+                0 => "",
+                _ => "abort();\n",
+            };
+            uwrite!(self.gen.stub, "\n{} {{\n{body}}}\n", h_sig.sig);
+        }
+
         // Generate, in the C source file, the raw wasm signature that has the
         // canonical ABI.
         uwriteln!(
@@ -1427,9 +1479,9 @@ impl InterfaceGenerator<'_> {
         return ret;
     }
 
-    fn print_typedef_target(&mut self, id: TypeId, name: &str) {
-        let ns = self.gen.owner_namespace(self.resolve, id).to_snake_case();
-        let snake = name.to_snake_case();
+    fn print_typedef_target(&mut self, id: TypeId, _name: &str) {
+        let ns = self.gen.owner_namespace(self.resolve, id);
+        let snake = self.gen.idents.type_name(self.resolve, id);
         self.src.h_defs(&ns);
         self.src.h_defs("_");
         self.src.h_defs(&snake);
@@ -1612,8 +1664,8 @@ impl Bindgen for FunctionBindgen<'_, '_> {
         ptr
     }
 
-    fn is_list_canonical(&self, resolve: &Resolve, ty: &Type) -> bool {
-        resolve.all_bits_valid(ty)
+    fn is_list_canonical(&self, _resolve: &Resolve, ty: &Type) -> bool {
+        self.gen.gen.type_info.is_plain_old_data(ty)
     }
 
     fn emit(
@@ -1709,10 +1761,11 @@ impl Bindgen for FunctionBindgen<'_, '_> {
                 results.push(operands[0].clone());
             }
 
-            Instruction::RecordLower { record, .. } => {
+            Instruction::RecordLower { record, ty, .. } => {
                 let op = &operands[0];
-                for f in record.fields.iter() {
-                    results.push(format!("({}).{}", op, to_c_ident(&f.name)));
+                for (i, f) in record.fields.iter().enumerate() {
+                    let field = self.gen.gen.idents.field(*ty, i, &f.name);
+                    results.push(format!("({}).{}", op, field));
                 }
             }
             Instruction::RecordLift { ty, .. } => {
@@ -1775,6 +1828,7 @@ impl Bindgen for FunctionBindgen<'_, '_> {
 
             Instruction::VariantLower {
                 variant,
+                ty: variant_ty,
                 results: result_types,
                 ..
             } => {
@@ -1815,7 +1869,8 @@ impl Bindgen for FunctionBindgen<'_, '_> {
                             operands[0],
                         );
                         self.src.push_str(".");
-                        self.src.push_str(&to_c_ident(&case.name));
+                        let member = self.gen.gen.payload_member(*variant_ty, i, &case.name);
+                        self.src.push_str(&member);
                         self.src.push_str(";\n");
                     }
                     self.src.push_str(&block);
@@ -1828,13 +1883,15 @@ impl Bindgen for FunctionBindgen<'_, '_> {
                 self.src.push_str("}\n");
             }
 
-            Instruction::VariantLift { variant, ty, .. } => {
+            Instruction::VariantLift {
+                variant, ty: id, ..
+            } => {
                 let blocks = self
                     .blocks
                     .drain(self.blocks.len() - variant.cases.len()..)
                     .collect::<Vec<_>>();
 
-                let ty = self.gen.type_string(&Type::Id(*ty));
+                let ty = self.gen.type_string(&Type::Id(*id));
                 let result = self.locals.tmp("variant");
                 uwriteln!(self.src, "{} {};", ty, result);
                 uwriteln!(self.src, "{}.tag = {};", result, operands[0]);
@@ -1849,7 +1906,7 @@ impl Bindgen for FunctionBindgen<'_, '_> {
                     if let Some(_) = get_nonempty_type(self.gen.resolve, case.ty.as_ref()) {
                         let mut dst = format!("{}.val", result);
                         dst.push_str(".");
-                        dst.push_str(&to_c_ident(&case.name));
+                        dst.push_str(&self.gen.gen.payload_member(*id, i, &case.name));
                         self.store_op(&block_results[0], &dst);
                     }
                     self.src.push_str("break;\n}\n");
@@ -2545,8 +2602,8 @@ impl Source {
 trait SourceExt {
     fn as_source(&mut self) -> &mut wit_bindgen_core::Source;
 
-    fn print_ty_name(&mut self, resolve: &Resolve, ty: &Type) {
-        push_ty_name(resolve, ty, self.as_source().as_mut_string());
+    fn print_ty_name(&mut self, idents: &Names, resolve: &Resolve, ty: &Type) {
+        push_ty_name(idents, resolve, ty, self.as_source().as_mut_string());
     }
 }
 
@@ -2556,7 +2613,7 @@ impl SourceExt for wit_bindgen_core::Source {
     }
 }
 
-fn push_ty_name(resolve: &Resolve, ty: &Type, src: &mut String) {
+fn push_ty_name(idents: &Names, resolve: &Resolve, ty: &Type, src: &mut String) {
     match ty {
         Type::Bool => src.push_str("bool"),
         Type::Char => src.push_str("char32"),
@@ -2573,11 +2630,11 @@ fn push_ty_name(resolve: &Resolve, ty: &Type, src: &mut String) {
         Type::String => src.push_str("string"),
         Type::Id(id) => {
             let ty = &resolve.types[*id];
-            if let Some(name) = &ty.name {
-                return src.push_str(&name.to_snake_case());
+            if ty.name.is_some() {
+                return src.push_str(&idents.type_name(resolve, *id));
             }
             match &ty.kind {
-                TypeDefKind::Type(t) => push_ty_name(resolve, t, src),
+                TypeDefKind::Type(t) => push_ty_name(idents, resolve, t, src),
                 TypeDefKind::Record(_)
                 | TypeDefKind::Flags(_)
                 | TypeDefKind::Enum(_)
@@ -2590,41 +2647,46 @@ fn push_ty_name(resolve: &Resolve, ty: &Type, src: &mut String) {
                     src.push_str(&t.types.len().to_string());
                     for ty in t.types.iter() {
                         src.push_str("_");
-                        push_ty_name(resolve, ty, src);
+                        push_ty_name(idents, resolve, ty, src);
                     }
                 }
                 TypeDefKind::Option(ty) => {
                     src.push_str("option_");
-                    push_ty_name(resolve, ty, src);
+                    push_ty_name(idents, resolve, ty, src);
                 }
                 TypeDefKind::Result(r) => {
                     src.push_str("result_");
-                    push_optional_ty_name(resolve, r.ok.as_ref(), src);
+                    push_optional_ty_name(idents, resolve, r.ok.as_ref(), src);
                     src.push_str("_");
-                    push_optional_ty_name(resolve, r.err.as_ref(), src);
+                    push_optional_ty_name(idents, resolve, r.err.as_ref(), src);
                 }
                 TypeDefKind::List(t) => {
                     src.push_str("list_");
-                    push_ty_name(resolve, t, src);
+                    push_ty_name(idents, resolve, t, src);
                 }
                 TypeDefKind::Future(t) => {
                     src.push_str("future_");
-                    push_optional_ty_name(resolve, t.as_ref(), src);
+                    push_optional_ty_name(idents, resolve, t.as_ref(), src);
                 }
                 TypeDefKind::Stream(s) => {
                     src.push_str("stream_");
-                    push_optional_ty_name(resolve, s.element.as_ref(), src);
+                    push_optional_ty_name(idents, resolve, s.element.as_ref(), src);
                     src.push_str("_");
-                    push_optional_ty_name(resolve, s.end.as_ref(), src);
+                    push_optional_ty_name(idents, resolve, s.end.as_ref(), src);
                 }
                 TypeDefKind::Unknown => unreachable!(),
             }
         }
     }
 
-    fn push_optional_ty_name(resolve: &Resolve, ty: Option<&Type>, dst: &mut String) {
+    fn push_optional_ty_name(
+        idents: &Names,
+        resolve: &Resolve,
+        ty: Option<&Type>,
+        dst: &mut String,
+    ) {
         match ty {
-            Some(ty) => push_ty_name(resolve, ty, dst),
+            Some(ty) => push_ty_name(idents, resolve, ty, dst),
             None => dst.push_str("void"),
         }
     }
@@ -2741,42 +2803,5 @@ pub fn optional_owns_anything(resolve: &Resolve, ty: Option<&Type>) -> bool {
 }
 
 pub fn to_c_ident(name: &str) -> String {
-    match name {
-        // Escape C keywords.
-        // Source: https://en.cppreference.com/w/c/keyword
-        "auto" => "auto_".into(),
-        "else" => "else_".into(),
-        "long" => "long_".into(),
-        "switch" => "switch_".into(),
-        "break" => "break_".into(),
-        "enum" => "enum_".into(),
-        "register" => "register_".into(),
-        "typedef" => "typedef_".into(),
-        "case" => "case_".into(),
-        "extern" => "extern_".into(),
-        "return" => "return_".into(),
-        "union" => "union_".into(),
-        "char" => "char_".into(),
-        "float" => "float_".into(),
-        "short" => "short_".into(),
-        "unsigned" => "unsigned_".into(),
-        "const" => "const_".into(),
-        "for" => "for_".into(),
-        "signed" => "signed_".into(),
-        "void" => "void_".into(),
-        "continue" => "continue_".into(),
-        "goto" => "goto_".into(),
-        "sizeof" => "sizeof_".into(),
-        "volatile" => "volatile_".into(),
-        "default" => "default_".into(),
-        "if" => "if_".into(),
-        "static" => "static_".into(),
-        "while" => "while_".into(),
-        "do" => "do_".into(),
-        "int" => "int_".into(),
-        "struct" => "struct_".into(),
-        "_Packed" => "_Packed_".into(),
-        "double" => "double_".into(),
-        s => s.to_snake_case(),
-    }
+    Profile::C.ident(name, Casing::Snake)
 }
@@ -11,9 +11,10 @@ macro_rules! codegen_test {
                 "guest-c",
                 $test.as_ref(),
                 |resolve, world, files| {
-                    wit_bindgen_c::Opts::default()
-                        .build()
-                        .generate(resolve, world, files)
+                    let mut opts = wit_bindgen_c::Opts::default();
+                    opts.generate_stub = true;
+                    opts.build()
+                        .generate(resolve, world, &Default::default(), files)
                 },
                 verify,
             );
@@ -23,7 +24,8 @@ macro_rules! codegen_test {
                 |resolve, world, files| {
                     let mut opts = wit_bindgen_c::Opts::default();
                     opts.no_sig_flattening = true;
-                    opts.build().generate(resolve, world, files)
+                    opts.build()
+                        .generate(resolve, world, &Default::default(), files)
                 },
                 verify,
             );
@@ -34,10 +36,18 @@ macro_rules! codegen_test {
 test_helpers::codegen_tests!();
 
 fn verify(dir: &Path, name: &str) {
+    compile(dir, &format!("{}.c", name.to_snake_case()));
+    let stub = format!("{}_stub.c", name.to_snake_case());
+    if dir.join(&stub).exists() {
+        compile(dir, &stub);
+    }
+}
+
+fn compile(dir: &Path, file: &str) {
     let path = PathBuf::from(env::var_os("WASI_SDK_PATH").unwrap());
     let mut cmd = Command::new(path.join("bin/clang"));
     cmd.arg("--sysroot").arg(path.join("share/wasi-sysroot"));
-    cmd.arg(dir.join(format!("{}.c", name.to_snake_case())));
+    cmd.arg(dir.join(file));
     cmd.arg("-I").arg(dir);
     cmd.arg("-Wall")
         .arg("-Wextra")
@@ -0,0 +1,831 @@
+//! Detection of breaking changes between two versions of a world.
+//!
+//! `compare` walks an old and a new version of a world side by side and
+//! reports every difference between them as a `Change`. Each change is
+//! classified twice:
+//!
+//! * At the canonical ABI level, which is whether a component built against
+//!   one version can be linked with the other. Reordering the cases of an enum
+//!   is breaking here, as it changes their discriminants.
+//! * At the source level, which is whether code written against one version
+//!   still makes sense for the other. Renaming a record field is breaking
+//!   here, while widening an integer isn't as every old value is still valid.
+//!
+//! Whether adding or removing a function is breaking depends on which side
+//! of the world it's on. A new import has to be provided by every host, so
+//! it's breaking, while a new export is just ignored by existing users of the
+//! component. The reverse holds for removals.
+//!
+//! Adding a case to a variant or enum depends on the direction its values
+//! flow in. A guest built against the old version traps on a discriminant it
+//! doesn't know, so a new case is breaking in the results of an import or the
+//! parameters of an export. Values flowing out of the guest only ever use the
+//! cases it knows about, so a new case is compatible there.
+//!
+//! Items are matched by name, with package versions ignored so that two
+//! releases of the same package can be compared.
+
+use std::collections::HashSet;
+use std::fmt;
+use wit_bindgen_core::wit_parser::*;
+
+/// Whether a change is compatible at some level.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compat {
+    Compatible,
+    Breaking,
+}
+
+use Compat::*;
+
+impl fmt::Display for Compat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Compatible => f.write_str("compatible"),
+            Breaking => f.write_str("breaking"),
+        }
+    }
+}
+
+/// A difference between two versions of a world.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Change {
+    /// The item which changed, written as in WIT, for example `a:b/iface#ty`
+    /// or `a:b/iface#ty.field`.
+    pub path: String,
+    /// What changed.
+    pub description: String,
+    /// Whether the change is compatible at the canonical ABI level.
+    pub abi: Compat,
+    /// Whether the change is compatible at the source level.
+    pub source: Compat,
+}
+
+impl Change {
+    /// Returns whether this change is breaking at any level.
+    pub fn is_breaking(&self) -> bool {
+        self.abi == Breaking || self.source == Breaking
+    }
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} (ABI: {}, source: {})",
+            self.path, self.description, self.abi, self.source
+        )
+    }
+}
+
+/// Returns every change between the world `old_world` in `old` and the world
+/// `new_world` in `new`.
+pub fn compare(
+    old: &Resolve,
+    old_world: WorldId,
+    new: &Resolve,
+    new_world: WorldId,
+) -> Vec<Change> {
+    let mut differ = Differ {
+        old,
+        new,
+        changes: Vec::new(),
+        visited: HashSet::new(),
+        inbound: inbound_types(old, old_world),
+    };
+    let (old_world, new_world) = (&old.worlds[old_world], &new.worlds[new_world]);
+    differ.items(&old_world.imports, &new_world.imports, true);
+    differ.items(&old_world.exports, &new_world.exports, false);
+    differ.changes
+}
+
+struct Differ<'a> {
+    old: &'a Resolve,
+    new: &'a Resolve,
+    changes: Vec<Change>,
+    /// Pairs of named types which have already been compared.
+    visited: HashSet<(TypeId, TypeId)>,
+    /// Types in the old world whose values can flow into the guest.
+    inbound: HashSet<TypeId>,
+}
+
+impl Differ<'_> {
+    fn change(&mut self, path: &str, description: String, abi: Compat, source: Compat) {
+        self.changes.push(Change {
+            path: path.to_string(),
+            description,
+            abi,
+            source,
+        });
+    }
+
+    /// Compares the imports or exports of two worlds.
+    fn items<'b>(
+        &mut self,
+        old: impl IntoIterator<Item = (&'b WorldKey, &'b WorldItem)>,
+        new: impl IntoIterator<Item = (&'b WorldKey, &'b WorldItem)>,
+        import: bool,
+    ) {
+        let old = old
+            .into_iter()
+            .map(|(k, i)| (key_name(self.old, k), i))
+            .collect::<Vec<_>>();
+        let new = new
+            .into_iter()
+            .map(|(k, i)| (key_name(self.new, k), i))
+            .collect::<Vec<_>>();
+        let (side, added, removed) = if import {
+            ("import", Breaking, Compatible)
+        } else {
+            ("export", Compatible, Breaking)
+        };
+
+        for (name, old_item) in old.iter() {
+            let new_item = match new.iter().find(|(n, _)| n == name) {
+                Some((_, item)) => *item,
+                None => {
+                    let description = format!("{side} removed");
+                    self.change(name, description, removed, removed);
+                    continue;
+                }
+            };
+            match (old_item, new_item) {
+                (WorldItem::Interface(a), WorldItem::Interface(b)) => {
+                    self.interface(name, *a, *b, side, added, removed)
+                }
+                (WorldItem::Function(a), WorldItem::Function(b)) => self.function(name, a, b),
+                (WorldItem::Type(a), WorldItem::Type(b)) => {
+                    self.type_id(name, *a, *b);
+                }
+                _ => self.change(
+                    name,
+                    format!(
+                        "{side} changed from {} to {}",
+                        kind(old_item),
+                        kind(new_item)
+                    ),
+                    Breaking,
+                    Breaking,
+                ),
+            }
+        }
+        for (name, _) in new.iter() {
+            if !old.iter().any(|(n, _)| n == name) {
+                self.change(name, format!("{side} added"), added, added);
+            }
+        }
+    }
+
+    fn interface(
+        &mut self,
+        path: &str,
+        old: InterfaceId,
+        new: InterfaceId,
+        side: &str,
+        added: Compat,
+        removed: Compat,
+    ) {
+        let (old, new) = (&self.old.interfaces[old], &self.new.interfaces[new]);
+        for (name, a) in old.types.iter() {
+            let path = format!("{path}#{name}");
+            match new.types.get(name) {
+                Some(b) => self.type_id(&path, *a, *b),
+                // Types don't exist in the canonical ABI, so this only
+                // affects code which referred to the type by name.
+                None => self.change(&path, "type removed".to_string(), Compatible, Breaking),
+            }
+        }
+        for (name, _) in new.types.iter() {
+            if !old.types.contains_key(name) {
+                let path = format!("{path}#{name}");
+                self.change(&path, "type added".to_string(), Compatible, Compatible);
+            }
+        }
+        for (name, a) in old.functions.iter() {
+            let path = format!("{path}#{name}");
+            match new.functions.get(name) {
+                Some(b) => self.function(&path, a, b),
+                None => self.change(
+                    &path,
+                    format!("{side}ed function removed"),
+                    removed,
+                    removed,
+                ),
+            }
+        }
+        for (name, _) in new.functions.iter() {
+            if !old.functions.contains_key(name) {
+                let path = format!("{path}#{name}");
+                self.change(&path, format!("{side}ed function added"), added, added);
+            }
+        }
+    }
+
+    fn function(&mut self, path: &str, old: &Function, new: &Function) {
+        if old.params.len() != new.params.len() {
+            self.change(
+                path,
+                format!(
+                    "number of parameters changed from {} to {}",
+                    old.params.len(),
+                    new.params.len()
+                ),
+                Breaking,
+                Breaking,
+            );
+        }
+        for ((a_name, a), (b_name, b)) in old.params.iter().zip(new.params.iter()) {
+            if a_name != b_name {
+                self.change(
+                    path,
+                    format!("parameter `{a_name}` renamed to `{b_name}`"),
+                    Compatible,
+                    Compatible,
+                );
+            }
+            self.ty(&format!("{path}({a_name})"), a, b);
+        }
+
+        match (&old.results, &new.results) {
+            (Results::Anon(a), Results::Anon(b)) => self.ty(&format!("{path}->"), a, b),
+            (Results::Named(a), Results::Named(b)) if a.len() == b.len() => {
+                for ((a_name, a), (b_name, b)) in a.iter().zip(b.iter()) {
+                    if a_name != b_name {
+                        self.change(
+                            path,
+                            format!("result `{a_name}` renamed to `{b_name}`"),
+                            Compatible,
+                            Breaking,
+                        );
+                    }
+                    self.ty(&format!("{path}->{a_name}"), a, b);
+                }
+            }
+            _ => self.change(path, "results changed".to_string(), Breaking, Breaking),
+        }
+    }
+
+    fn ty(&mut self, path: &str, old: &Type, new: &Type) {
+        match (old, new) {
+            (Type::Id(a), Type::Id(b)) => self.type_id(path, *a, *b),
+            (Type::Id(_), _) | (_, Type::Id(_)) => self.type_changed(path, old, new),
+            (a, b) if a == b => {}
+            (a, b) if widens(a, b) => self.change(
+                path,
+                format!(
+                    "widened from `{}` to `{}`",
+                    self.old_name(a),
+                    self.new_name(b)
+                ),
+                Breaking,
+                Compatible,
+            ),
+            _ => self.type_changed(path, old, new),
+        }
+    }
+
+    fn type_changed(&mut self, path: &str, old: &Type, new: &Type) {
+        let description = format!(
+            "type changed from `{}` to `{}`",
+            self.old_name(old),
+            self.new_name(new)
+        );
+        self.change(path, description, Breaking, Breaking);
+    }
+
+    fn type_id(&mut self, path: &str, old: TypeId, new: TypeId) {
+        let old = dealias(self.old, old);
+        let new = dealias(self.new, new);
+
+        // Named types are compared once, with changes reported at their
+        // definition rather than at every use.
+        let path = match &self.old.types[old].name {
+            Some(_) => {
+                if !self.visited.insert((old, new)) {
+                    return;
+                }
+                type_path(self.old, old)
+            }
+            None => path.to_string(),
+        };
+        let path = path.as_str();
+
+        match (&self.old.types[old].kind, &self.new.types[new].kind) {
+            (TypeDefKind::Record(a), TypeDefKind::Record(b)) => {
+                for (a, b) in a.fields.iter().zip(b.fields.iter()) {
+                    if a.name != b.name {
+                        self.change(
+                            path,
+                            format!("field `{}` renamed to `{}`", a.name, b.name),
+                            Compatible,
+                            Breaking,
+                        );
+                    }
+                    self.ty(&format!("{path}.{}", a.name), &a.ty, &b.ty);
+                }
+                for f in a.fields.iter().skip(b.fields.len()) {
+                    let description = format!("field `{}` removed", f.name);
+                    self.change(path, description, Breaking, Breaking);
+                }
+                for f in b.fields.iter().skip(a.fields.len()) {
+                    let description = format!("field `{}` added", f.name);
+                    self.change(path, description, Breaking, Breaking);
+                }
+            }
+            (TypeDefKind::Tuple(a), TypeDefKind::Tuple(b)) if a.types.len() == b.types.len() => {
+                for (i, (a, b)) in a.types.iter().zip(b.types.iter()).enumerate() {
+                    self.ty(&format!("{path}.{i}"), a, b);
+                }
+            }
+            (TypeDefKind::Variant(a), TypeDefKind::Variant(b)) => {
+                let old_cases = a.cases.iter().map(|c| (c.name.as_str(), c.ty.as_ref()));
+                let new_cases = b.cases.iter().map(|c| (c.name.as_str(), c.ty.as_ref()));
+                let added = self.case_added(old);
+                self.cases(
+                    path,
+                    old_cases.collect(),
+                    new_cases.collect(),
+                    "case",
+                    added,
+                );
+                self.tag_size(path, a.tag(), b.tag());
+            }
+            (TypeDefKind::Enum(a), TypeDefKind::Enum(b)) => {
+                let old_cases = a.cases.iter().map(|c| (c.name.as_str(), None));
+                let new_cases = b.cases.iter().map(|c| (c.name.as_str(), None));
+                let added = self.case_added(old);
+                self.cases(
+                    path,
+                    old_cases.collect(),
+                    new_cases.collect(),
+                    "case",
+                    added,
+                );
+                self.tag_size(path, a.tag(), b.tag());
+            }
+            (TypeDefKind::Flags(a), TypeDefKind::Flags(b)) => {
+                let old_flags = a.flags.iter().map(|f| (f.name.as_str(), None));
+                let new_flags = b.flags.iter().map(|f| (f.name.as_str(), None));
+                let (old_flags, new_flags) = (old_flags.collect(), new_flags.collect());
+                self.cases(path, old_flags, new_flags, "flag", Compatible);
+                let (old_repr, new_repr) = (flags_repr(a), flags_repr(b));
+                if old_repr != new_repr {
+                    let description =
+                        format!("representation changed from `{old_repr}` to `{new_repr}`");
+                    self.change(path, description, Breaking, Compatible);
+                }
+            }
+            (TypeDefKind::Union(a), TypeDefKind::Union(b)) if a.cases.len() == b.cases.len() => {
+                for (i, (a, b)) in a.cases.iter().zip(b.cases.iter()).enumerate() {
+                    self.ty(&format!("{path}.{i}"), &a.ty, &b.ty);
+                }
+            }
+            (TypeDefKind::Option(a), TypeDefKind::Option(b))
+            | (TypeDefKind::List(a), TypeDefKind::List(b)) => self.ty(path, a, b),
+            (TypeDefKind::Result(a), TypeDefKind::Result(b)) => {
+                self.optional_ty(&format!("{path}.ok"), a.ok.as_ref(), b.ok.as_ref());
+                self.optional_ty(&format!("{path}.err"), a.err.as_ref(), b.err.as_ref());
+            }
+            (TypeDefKind::Future(a), TypeDefKind::Future(b)) => {
+                self.optional_ty(path, a.as_ref(), b.as_ref())
+            }
+            (TypeDefKind::Stream(a), TypeDefKind::Stream(b)) => {
+                self.optional_ty(
+                    &format!("{path}.element"),
+                    a.element.as_ref(),
+                    b.element.as_ref(),
+                );
+                self.optional_ty(&format!("{path}.end"), a.end.as_ref(), b.end.as_ref());
+            }
+            (TypeDefKind::Type(a), TypeDefKind::Type(b)) => self.ty(path, a, b),
+            (TypeDefKind::Type(a), _) => self.ty(path, a, &Type::Id(new)),
+            (_, TypeDefKind::Type(b)) => self.ty(path, &Type::Id(old), b),
+            _ => self.type_changed(path, &Type::Id(old), &Type::Id(new)),
+        }
+    }
+
+    fn optional_ty(&mut self, path: &str, old: Option<&Type>, new: Option<&Type>) {
+        match (old, new) {
+            (Some(a), Some(b)) => self.ty(path, a, b),
+            (None, None) => {}
+            _ => {
+                let name = |resolve, ty: Option<&Type>| match ty {
+                    Some(ty) => type_name(resolve, ty),
+                    None => "_".to_string(),
+                };
+                let description = format!(
+                    "type changed from `{}` to `{}`",
+                    name(self.old, old),
+                    name(self.new, new)
+                );
+                self.change(path, description, Breaking, Breaking);
+            }
+        }
+    }
+
+    /// Returns how adding a case to the variant or enum `id` affects the ABI.
+    fn case_added(&self, id: TypeId) -> Compat {
+        if self.inbound.contains(&id) {
+            Breaking
+        } else {
+            Compatible
+        }
+    }
+
+    /// Compares the cases of a variant or enum, or the flags of a flags type,
+    /// whose discriminants or bits are given by their position. `added` is
+    /// how adding a case affects the ABI.
+    fn cases(
+        &mut self,
+        path: &str,
+        old: Vec<(&str, Option<&Type>)>,
+        new: Vec<(&str, Option<&Type>)>,
+        what: &str,
+        added: Compat,
+    ) {
+        for (i, (name, a)) in old.iter().enumerate() {
+            match new.iter().position(|(n, _)| n == name) {
+                Some(j) => {
+                    if i != j {
+                        let description = format!("{what} `{name}` moved from position {i} to {j}");
+                        self.change(path, description, Breaking, Compatible);
+                    }
+                    self.optional_ty(&format!("{path}.{name}"), *a, new[j].1);
+                }
+                None => {
+                    let description = format!("{what} `{name}` removed");
+                    self.change(path, description, Breaking, Breaking);
+                }
+            }
+        }
+        for (name, _) in new.iter() {
+            if !old.iter().any(|(n, _)| n == name) {
+                // Existing values are still valid, but code which matches
+                // exhaustively on the type no longer covers every case.
+                let description = format!("{what} `{name}` added");
+                self.change(path, description, added, Breaking);
+            }
+        }
+    }
+
+    fn tag_size(&mut self, path: &str, old: Int, new: Int) {
+        if old != new {
+            let description = format!(
+                "discriminant changed from `{}` to `{}`",
+                int_name(old),
+                int_name(new)
+            );
+            self.change(path, description, Breaking, Compatible);
+        }
+    }
+
+    fn old_name(&self, ty: &Type) -> String {
+        type_name(self.old, ty)
+    }
+
+    fn new_name(&self, ty: &Type) -> String {
+        type_name(self.new, ty)
+    }
+}
+
+/// Returns whether every value of `old` is also a value of `new`.
+fn widens(old: &Type, new: &Type) -> bool {
+    fn int(ty: &Type) -> Option<(bool, u32)> {
+        Some(match ty {
+            Type::U8 => (false, 8),
+            Type::U16 => (false, 16),
+            Type::U32 => (false, 32),
+            Type::U64 => (false, 64),
+            Type::S8 => (true, 8),
+            Type::S16 => (true, 16),
+            Type::S32 => (true, 32),
+            Type::S64 => (true, 64),
+            _ => return None,
+        })
+    }
+    match (old, new) {
+        (Type::Float32, Type::Float64) => true,
+        _ => match (int(old), int(new)) {
+            (Some((false, a)), Some((false, b))) | (Some((true, a)), Some((true, b))) => a < b,
+            (Some((false, a)), Some((true, b))) => a < b,
+            _ => false,
+        },
+    }
+}
+
+/// Returns every type in `world` whose values can flow into the guest, which
+/// are those used by the results of its imports and the parameters of its
+/// exports.
+fn inbound_types(resolve: &Resolve, world: WorldId) -> HashSet<TypeId> {
+    fn functions<'a>(
+        resolve: &'a Resolve,
+        items: impl IntoIterator<Item = &'a WorldItem>,
+    ) -> Vec<&'a Function> {
+        let mut functions = Vec::new();
+        for item in items {
+            match item {
+                WorldItem::Interface(id) => {
+                    functions.extend(resolve.interfaces[*id].functions.values())
+                }
+                WorldItem::Function(f) => functions.push(f),
+                WorldItem::Type(_) => {}
+            }
+        }
+        functions
+    }
+
+    fn visit(resolve: &Resolve, ty: &Type, types: &mut HashSet<TypeId>) {
+        let id = match ty {
+            Type::Id(id) => dealias(resolve, *id),
+            _ => return,
+        };
+        if !types.insert(id) {
+            return;
+        }
+        let inner: Vec<&Type> = match &resolve.types[id].kind {
+            TypeDefKind::Record(r) => r.fields.iter().map(|f| &f.ty).collect(),
+            TypeDefKind::Tuple(t) => t.types.iter().collect(),
+            TypeDefKind::Variant(v) => v.cases.iter().filter_map(|c| c.ty.as_ref()).collect(),
+            TypeDefKind::Union(u) => u.cases.iter().map(|c| &c.ty).collect(),
+            TypeDefKind::Option(t) | TypeDefKind::List(t) | TypeDefKind::Type(t) => vec![t],
+            TypeDefKind::Result(r) => r.ok.iter().chain(r.err.iter()).collect(),
+            TypeDefKind::Future(t) => t.iter().collect(),
+            TypeDefKind::Stream(s) => s.element.iter().chain(s.end.iter()).collect(),
+            _ => Vec::new(),
+        };
+        for ty in inner {
+            visit(resolve, ty, types);
+        }
+    }
+
+    let world = &resolve.worlds[world];
+    let mut types = HashSet::new();
+    for f in functions(resolve, world.imports.values()) {
+        for ty in f.results.iter_types() {
+            visit(resolve, ty, &mut types);
+        }
+    }
+    for f in functions(resolve, world.exports.values()) {
+        for (_, ty) in f.params.iter() {
+            visit(resolve, ty, &mut types);
+        }
+    }
+    types
+}
+
+/// Follows `type a = b` aliases to the type they refer to.
+fn dealias(resolve: &Resolve, mut id: TypeId) -> TypeId {
+    loop {
+        match resolve.types[id].kind {
+            TypeDefKind::Type(Type::Id(next)) => id = next,
+            _ => break id,
+        }
+    }
+}
+
+/// The name of a world item, without any package version.
+fn key_name(resolve: &Resolve, key: &WorldKey) -> String {
+    match key {
+        WorldKey::Name(name) => name.clone(),
+        WorldKey::Interface(id) => interface_name(resolve, *id),
+    }
+}
+
+fn interface_name(resolve: &Resolve, id: InterfaceId) -> String {
+    let iface = &resolve.interfaces[id];
+    let name = iface.name.as_deref().unwrap_or("<anonymous>");
+    match iface.package {
+        Some(pkg) => {
+            let pkg = &resolve.packages[pkg].name;
+            format!("{}:{}/{name}", pkg.namespace, pkg.name)
+        }
+        None => name.to_string(),
+    }
+}
+
+fn type_path(resolve: &Resolve, id: TypeId) -> String {
+    let ty = &resolve.types[id];
+    let name = ty.name.as_deref().unwrap_or("<anonymous>");
+    match ty.owner {
+        TypeOwner::Interface(i) if resolve.interfaces[i].name.is_some() => {
+            format!("{}#{name}", interface_name(resolve, i))
+        }
+        _ => name.to_string(),
+    }
+}
+
+fn kind(item: &WorldItem) -> &'static str {
+    match item {
+        WorldItem::Interface(_) => "an interface",
+        WorldItem::Function(_) => "a function",
+        WorldItem::Type(_) => "a type",
+    }
+}
+
+/// Returns how `ty` is written in WIT.
+fn type_name(resolve: &Resolve, ty: &Type) -> String {
+    let id = match ty {
+        Type::Bool => return "bool".to_string(),
+        Type::U8 => return "u8".to_string(),
+        Type::U16 => return "u16".to_string(),
+        Type::U32 => return "u32".to_string(),
+        Type::U64 => return "u64".to_string(),
+        Type::S8 => return "s8".to_string(),
+        Type::S16 => return "s16".to_string(),
+        Type::S32 => return "s32".to_string(),
+        Type::S64 => return "s64".to_string(),
+        Type::Float32 => return "float32".to_string(),
+        Type::Float64 => return "float64".to_string(),
+        Type::Char => return "char".to_string(),
+        Type::String => return "string".to_string(),
+        Type::Id(id) => *id,
+    };
+    let ty = &resolve.types[id];
+    if let Some(name) = &ty.name {
+        return name.clone();
+    }
+    let optional = |ty: Option<&Type>| match ty {
+        Some(ty) => type_name(resolve, ty),
+        None => "_".to_string(),
+    };
+    let list = |types: &mut dyn Iterator<Item = &Type>| {
+        types
+            .map(|t| type_name(resolve, t))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    match &ty.kind {
+        TypeDefKind::Type(t) => type_name(resolve, t),
+        TypeDefKind::List(t) => format!("list<{}>", type_name(resolve, t)),
+        TypeDefKind::Option(t) => format!("option<{}>", type_name(resolve, t)),
+        TypeDefKind::Result(r) => {
+            format!(
+                "result<{}, {}>",
+                optional(r.ok.as_ref()),
+                optional(r.err.as_ref())
+            )
+        }
+        TypeDefKind::Tuple(t) => format!("tuple<{}>", list(&mut t.types.iter())),
+        TypeDefKind::Union(u) => {
+            format!("union {{ {} }}", list(&mut u.cases.iter().map(|c| &c.ty)))
+        }
+        TypeDefKind::Future(t) => format!("future<{}>", optional(t.as_ref())),
+        TypeDefKind::Stream(s) => format!(
+            "stream<{}, {}>",
+            optional(s.element.as_ref()),
+            optional(s.end.as_ref())
+        ),
+        TypeDefKind::Record(_) => "record".to_string(),
+        TypeDefKind::Flags(_) => "flags".to_string(),
+        TypeDefKind::Variant(_) => "variant".to_string(),
+        TypeDefKind::Enum(_) => "enum".to_string(),
+        TypeDefKind::Unknown => unreachable!(),
+    }
+}
+
+/// Describes how a flags type is passed in the canonical ABI.
+fn flags_repr(flags: &Flags) -> String {
+    match flags.repr() {
+        FlagsRepr::U8 => "u8".to_string(),
+        FlagsRepr::U16 => "u16".to_string(),
+        FlagsRepr::U32(1) => "u32".to_string(),
+        FlagsRepr::U32(n) => format!("{n} x u32"),
+    }
+}
+
+fn int_name(int: Int) -> &'static str {
+    match int {
+        Int::U8 => "u8",
+        Int::U16 => "u16",
+        Int::U32 => "u32",
+        Int::U64 => "u64",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn world(src: &str) -> (Resolve, WorldId) {
+        let pkg = UnresolvedPackage::parse("test.wit".as_ref(), src).unwrap();
+        let mut resolve = Resolve::default();
+        let pkg = resolve.push(pkg).unwrap();
+        let world = resolve.select_world(pkg, Some("w")).unwrap();
+        (resolve, world)
+    }
+
+    fn changes(old: &str, new: &str) -> Vec<String> {
+        let (old, old_world) = world(old);
+        let (new, new_world) = world(new);
+        compare(&old, old_world, &new, new_world)
+            .iter()
+            .map(|c| c.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn unchanged() {
+        let src = "
+            package a:b@1.0.0
+            interface i {
+                record r { a: u8, b: list<string> }
+                f: func(x: r) -> result<r>
+            }
+            world w { export i }
+        ";
+        assert!(changes(src, &src.replace("1.0.0", "1.1.0")).is_empty());
+    }
+
+    #[test]
+    fn functions() {
+        let old = "
+            package a:b
+            interface i { f: func() }
+            world w {
+                import i
+                export e: interface { g: func() }
+            }
+        ";
+        let new = "
+            package a:b
+            interface i { f: func() g: func() }
+            world w {
+                import i
+                export e: interface { h: func() }
+            }
+        ";
+        assert_eq!(
+            changes(old, new),
+            [
+                "a:b/i#g: imported function added (ABI: breaking, source: breaking)",
+                "e#g: exported function removed (ABI: breaking, source: breaking)",
+                "e#h: exported function added (ABI: compatible, source: compatible)",
+            ]
+        );
+    }
+
+    #[test]
+    fn types() {
+        let old = "
+            package a:b
+            interface i {
+                enum color { red, green }
+                record point { x: u8, y: u32 }
+                f: func(c: color, p: point)
+            }
+            world w { export i }
+        ";
+        let new = "
+            package a:b
+            interface i {
+                enum color { green, red, blue }
+                record point { x: u16, z: u32 }
+                f: func(c: color, p: point)
+            }
+            world w { export i }
+        ";
+        assert_eq!(
+            changes(old, new),
+            [
+                "a:b/i#color: case `red` moved from position 0 to 1 (ABI: breaking, source: compatible)",
+                "a:b/i#color: case `green` moved from position 1 to 0 (ABI: breaking, source: compatible)",
+                "a:b/i#color: case `blue` added (ABI: breaking, source: breaking)",
+                "a:b/i#point.x: widened from `u8` to `u16` (ABI: breaking, source: compatible)",
+                "a:b/i#point: field `y` renamed to `z` (ABI: compatible, source: breaking)",
+            ]
+        );
+    }
+
+    #[test]
+    fn added_cases() {
+        let old = "
+            package a:b
+            interface i {
+                enum to-host { a }
+                enum to-guest { a }
+                f: func(x: to-host) -> to-guest
+            }
+            interface e {
+                variant to-host { a }
+                variant to-guest { a(u8) }
+                g: func(x: to-guest) -> list<to-host>
+            }
+            world w {
+                import i
+                export e
+            }
+        ";
+        let new = old
+            .replace("{ a }", "{ a, b }")
+            .replace("{ a(u8) }", "{ a(u8), b }");
+        assert_eq!(
+            changes(old, &new),
+            [
+                "a:b/i#to-host: case `b` added (ABI: compatible, source: breaking)",
+                "a:b/i#to-guest: case `b` added (ABI: breaking, source: breaking)",
+                "a:b/e#to-host: case `b` added (ABI: compatible, source: breaking)",
+                "a:b/e#to-guest: case `b` added (ABI: breaking, source: breaking)",
+            ]
+        );
+    }
+}
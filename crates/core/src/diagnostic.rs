@@ -0,0 +1,234 @@
+use std::fmt;
+use wit_parser::*;
+
+/// An error produced when a generator can't produce bindings for its input.
+///
+/// Diagnostics carry a human-readable `reason` along with as much context as
+/// is known about where the problem was found. Context is filled in from the
+/// inside out: the code which detects the problem typically knows the type or
+/// function involved, and `WorldGenerator::generate` then fills in the
+/// interface and world.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// The name of the world being generated.
+    pub world: Option<String>,
+    /// The name of the interface, as it's referred to in the world.
+    pub interface: Option<String>,
+    /// The name of the type which couldn't be generated.
+    pub ty: Option<String>,
+    /// The name of the function which couldn't be generated.
+    pub function: Option<String>,
+    /// Description of what went wrong.
+    pub reason: String,
+}
+
+impl Diagnostic {
+    pub fn new(reason: impl Into<String>) -> Diagnostic {
+        Diagnostic {
+            reason: reason.into(),
+            ..Diagnostic::default()
+        }
+    }
+
+    /// Creates a diagnostic indicating that `what` isn't supported by the
+    /// generator named `generator`.
+    pub fn unsupported(generator: &str, what: impl fmt::Display) -> Diagnostic {
+        Diagnostic::new(format!(
+            "{what} is not supported by the {generator} generator"
+        ))
+    }
+
+    /// Records `world` as the world this diagnostic was found in, unless a
+    /// world has already been recorded.
+    pub fn in_world(mut self, resolve: &Resolve, world: WorldId) -> Diagnostic {
+        if self.world.is_none() {
+            self.world = Some(resolve.worlds[world].name.clone());
+        }
+        self
+    }
+
+    /// Records `name` as the interface this diagnostic was found in, unless
+    /// an interface has already been recorded.
+    pub fn in_interface(mut self, resolve: &Resolve, name: &WorldKey) -> Diagnostic {
+        if self.interface.is_none() {
+            self.interface = Some(resolve.name_world_key(name));
+        }
+        self
+    }
+
+    /// Records `ty` as the type this diagnostic is about, unless a type has
+    /// already been recorded.
+    ///
+    /// If `ty` is owned by an interface that interface is recorded as well.
+    pub fn for_type(mut self, resolve: &Resolve, ty: TypeId) -> Diagnostic {
+        if self.ty.is_some() {
+            return self;
+        }
+        let def = &resolve.types[ty];
+        self.ty = Some(match &def.name {
+            Some(name) => name.clone(),
+            None => "<anonymous>".to_string(),
+        });
+        if let TypeOwner::Interface(id) = def.owner {
+            if self.interface.is_none() {
+                self.interface = Some(interface_name(resolve, id));
+            }
+        }
+        self
+    }
+
+    /// Records `func` as the function this diagnostic is about, unless a
+    /// function has already been recorded.
+    pub fn for_function(mut self, func: &Function) -> Diagnostic {
+        if self.function.is_none() {
+            self.function = Some(func.name.clone());
+        }
+        self
+    }
+}
+
+fn interface_name(resolve: &Resolve, id: InterfaceId) -> String {
+    let iface = &resolve.interfaces[id];
+    let name = iface.name.as_deref().unwrap_or("<anonymous>");
+    match iface.package {
+        Some(pkg) => {
+            let pkg = &resolve.packages[pkg].name;
+            format!("{}:{}/{name}", pkg.namespace, pkg.name)
+        }
+        None => name.to_string(),
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut location = Vec::new();
+        if let Some(ty) = &self.ty {
+            location.push(format!("type `{ty}`"));
+        }
+        if let Some(func) = &self.function {
+            location.push(format!("function `{func}`"));
+        }
+        if let Some(iface) = &self.interface {
+            location.push(format!("interface `{iface}`"));
+        }
+        if let Some(world) = &self.world {
+            location.push(format!("world `{world}`"));
+        }
+        if location.is_empty() {
+            write!(f, "failed to generate bindings: {}", self.reason)
+        } else {
+            write!(
+                f,
+                "failed to generate bindings for {}: {}",
+                location.join(" in "),
+                self.reason
+            )
+        }
+    }
+}
+
+impl std::error::Error for Diagnostic {}
+
+/// Checks every type used by `world`, returning a diagnostic for the first
+/// one that `check` rejects.
+///
+/// This is intended to be called from `WorldGenerator::preprocess` so a
+/// generator can reject input it doesn't support before any bindings are
+/// generated. The `check` closure returns a diagnostic if the type is
+/// unsupported, and the type's name is then attached to it.
+pub fn check_types(
+    resolve: &Resolve,
+    world: WorldId,
+    mut check: impl FnMut(&TypeDef) -> Option<Diagnostic>,
+) -> Result<(), Diagnostic> {
+    let mut live = LiveTypes::default();
+    let world = &resolve.worlds[world];
+    for (_, item) in world.imports.iter().chain(world.exports.iter()) {
+        match item {
+            WorldItem::Function(f) => live.add_func(resolve, f),
+            WorldItem::Interface(id) => live.add_interface(resolve, *id),
+            WorldItem::Type(id) => live.add_type_id(resolve, *id),
+        }
+    }
+    for id in live.iter() {
+        if let Some(diagnostic) = check(&resolve.types[id]) {
+            return Err(diagnostic.for_type(resolve, id));
+        }
+    }
+    Ok(())
+}
+
+/// A `check_types` callback rejecting `future` and `stream` types, which
+/// most generators don't support yet.
+pub fn reject_async_types(generator: &str) -> impl FnMut(&TypeDef) -> Option<Diagnostic> + '_ {
+    move |ty| match &ty.kind {
+        TypeDefKind::Future(_) => Some(Diagnostic::unsupported(generator, "the `future` type")),
+        TypeDefKind::Stream(_) => Some(Diagnostic::unsupported(generator, "the `stream` type")),
+        _ => None,
+    }
+}
+
+/// Returns a diagnostic for the first function in `world` which takes or
+/// returns a `future` or `stream`.
+///
+/// This is for generators which can define these types but can't yet lift or
+/// lower them through the canonical ABI.
+pub fn reject_async_functions(
+    resolve: &Resolve,
+    world: WorldId,
+    generator: &str,
+) -> Result<(), Diagnostic> {
+    let world = &resolve.worlds[world];
+    for (key, item) in world.imports.iter().chain(world.exports.iter()) {
+        let funcs = match item {
+            WorldItem::Function(f) => vec![f],
+            WorldItem::Interface(id) => resolve.interfaces[*id].functions.values().collect(),
+            WorldItem::Type(_) => continue,
+        };
+        for func in funcs {
+            let mut live = LiveTypes::default();
+            live.add_func(resolve, func);
+            let uses_async = live.iter().any(|id| {
+                matches!(
+                    resolve.types[id].kind,
+                    TypeDefKind::Future(_) | TypeDefKind::Stream(_)
+                )
+            });
+            if !uses_async {
+                continue;
+            }
+            let mut diagnostic = Diagnostic::unsupported(
+                generator,
+                "passing a `future` or `stream` to or from a function",
+            )
+            .for_function(func);
+            if let WorldItem::Interface(_) = item {
+                diagnostic = diagnostic.in_interface(resolve, key);
+            }
+            return Err(diagnostic);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Diagnostic;
+
+    #[test]
+    fn display() {
+        let d = Diagnostic::new("oops");
+        assert_eq!(d.to_string(), "failed to generate bindings: oops");
+
+        let d = Diagnostic {
+            world: Some("w".to_string()),
+            interface: Some("a:b/c".to_string()),
+            ty: Some("t".to_string()),
+            ..Diagnostic::new("oops")
+        };
+        assert_eq!(
+            d.to_string(),
+            "failed to generate bindings for type `t` in interface `a:b/c` in world `w`: oops"
+        );
+    }
+}
@@ -0,0 +1,354 @@
+//! Line-based diffs between two versions of a generated file.
+
+use std::fmt::Write;
+use std::ops::Range;
+
+/// The number of unchanged lines shown around each change.
+const CONTEXT: usize = 3;
+
+/// One step of turning the old text into the new, along with the 0-based
+/// line of the old and new text it occurs at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Edit {
+    /// The old line is kept as the new line.
+    Equal(usize, usize),
+    /// The old line is removed.
+    Delete(usize, usize),
+    /// The new line is added.
+    Insert(usize, usize),
+}
+
+impl Edit {
+    fn position(&self) -> (usize, usize) {
+        match *self {
+            Edit::Equal(a, b) | Edit::Delete(a, b) | Edit::Insert(a, b) => (a, b),
+        }
+    }
+}
+
+/// Returns a unified diff turning `old` into `new`, or an empty string if
+/// they have the same lines.
+///
+/// `old_name` and `new_name` are used for the `---` and `+++` header lines.
+pub fn unified_diff(old: &str, new: &str, old_name: &str, new_name: &str) -> String {
+    let old = old.lines().collect::<Vec<_>>();
+    let new = new.lines().collect::<Vec<_>>();
+    let edits = edits(&old, &new);
+    let changes = edits
+        .iter()
+        .enumerate()
+        .filter(|(_, e)| !matches!(e, Edit::Equal(..)))
+        .map(|(i, _)| i)
+        .collect::<Vec<_>>();
+    if changes.is_empty() {
+        return String::new();
+    }
+
+    // Group changes that are close enough for their context to overlap into
+    // hunks, each a range of `edits`.
+    let mut hunks: Vec<(usize, usize)> = Vec::new();
+    for i in changes {
+        let start = i.saturating_sub(CONTEXT);
+        let end = (i + CONTEXT + 1).min(edits.len());
+        match hunks.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => hunks.push((start, end)),
+        }
+    }
+
+    let mut out = format!("--- {old_name}\n+++ {new_name}\n");
+    for (start, end) in hunks {
+        let edits = &edits[start..end];
+        let (mut old_start, mut new_start) = edits[0].position();
+        let old_len = edits
+            .iter()
+            .filter(|e| !matches!(e, Edit::Insert(..)))
+            .count();
+        let new_len = edits
+            .iter()
+            .filter(|e| !matches!(e, Edit::Delete(..)))
+            .count();
+        // Line numbers are 1-based, except that an empty range refers to the
+        // line before it.
+        if old_len > 0 {
+            old_start += 1;
+        }
+        if new_len > 0 {
+            new_start += 1;
+        }
+        writeln!(out, "@@ -{old_start},{old_len} +{new_start},{new_len} @@").unwrap();
+        for edit in edits {
+            match edit {
+                Edit::Equal(i, _) => writeln!(out, " {}", old[*i]).unwrap(),
+                Edit::Delete(i, _) => writeln!(out, "-{}", old[*i]).unwrap(),
+                Edit::Insert(_, i) => writeln!(out, "+{}", new[*i]).unwrap(),
+            }
+        }
+    }
+    out
+}
+
+/// Computes the shortest sequence of edits turning `old` into `new` with the
+/// linear space variant of Myers' algorithm, which splits the texts at the
+/// middle of the shortest edit script and recurses on both halves.
+fn edits(old: &[&str], new: &[&str]) -> Vec<Edit> {
+    let max = (old.len() + new.len()).div_ceil(2) + 1;
+    let mut vf = V::new(max);
+    let mut vb = V::new(max);
+    let mut edits = Vec::new();
+    conquer(
+        old,
+        0..old.len(),
+        new,
+        0..new.len(),
+        &mut vf,
+        &mut vb,
+        &mut edits,
+    );
+
+    // Each run of changes is split up by the recursion, so put the deleted
+    // lines of a run before the inserted ones as diffs usually show them.
+    let mut i = 0;
+    while i < edits.len() {
+        let (old_start, new_start) = edits[i].position();
+        let len = edits[i..]
+            .iter()
+            .take_while(|e| !matches!(e, Edit::Equal(..)))
+            .count();
+        let deleted = edits[i..i + len]
+            .iter()
+            .filter(|e| matches!(e, Edit::Delete(..)))
+            .count();
+        for j in 0..len {
+            edits[i + j] = if j < deleted {
+                Edit::Delete(old_start + j, new_start)
+            } else {
+                Edit::Insert(old_start + deleted, new_start + j - deleted)
+            };
+        }
+        i += len.max(1);
+    }
+    edits
+}
+
+/// The furthest reaching `x` on each diagonal `k`, for `k` in `-max..=max`.
+struct V {
+    offset: isize,
+    v: Vec<usize>,
+}
+
+impl V {
+    fn new(max: usize) -> V {
+        V {
+            offset: max as isize,
+            v: vec![0; 2 * max + 1],
+        }
+    }
+}
+
+impl std::ops::Index<isize> for V {
+    type Output = usize;
+    fn index(&self, k: isize) -> &usize {
+        &self.v[(k + self.offset) as usize]
+    }
+}
+
+impl std::ops::IndexMut<isize> for V {
+    fn index_mut(&mut self, k: isize) -> &mut usize {
+        &mut self.v[(k + self.offset) as usize]
+    }
+}
+
+fn conquer(
+    old: &[&str],
+    mut old_range: Range<usize>,
+    new: &[&str],
+    mut new_range: Range<usize>,
+    vf: &mut V,
+    vb: &mut V,
+    edits: &mut Vec<Edit>,
+) {
+    while !old_range.is_empty()
+        && !new_range.is_empty()
+        && old[old_range.start] == new[new_range.start]
+    {
+        edits.push(Edit::Equal(old_range.start, new_range.start));
+        old_range.start += 1;
+        new_range.start += 1;
+    }
+    let mut suffix = 0;
+    while suffix < old_range.len()
+        && suffix < new_range.len()
+        && old[old_range.end - suffix - 1] == new[new_range.end - suffix - 1]
+    {
+        suffix += 1;
+    }
+    old_range.end -= suffix;
+    new_range.end -= suffix;
+
+    if old_range.is_empty() || new_range.is_empty() {
+        for i in old_range.clone() {
+            edits.push(Edit::Delete(i, new_range.start));
+        }
+        for i in new_range.clone() {
+            edits.push(Edit::Insert(old_range.end, i));
+        }
+    } else {
+        let (x, y) = middle_snake(old, old_range.clone(), new, new_range.clone(), vf, vb);
+        conquer(
+            old,
+            old_range.start..x,
+            new,
+            new_range.start..y,
+            vf,
+            vb,
+            edits,
+        );
+        conquer(old, x..old_range.end, new, y..new_range.end, vf, vb, edits);
+    }
+
+    for i in 0..suffix {
+        edits.push(Edit::Equal(old_range.end + i, new_range.end + i));
+    }
+}
+
+/// Returns the point at which the shortest edit script turning the non-empty
+/// `old_range` of `old` into the non-empty `new_range` of `new` is split in
+/// two, found by searching from both ends at once until the paths overlap.
+fn middle_snake(
+    old: &[&str],
+    old_range: Range<usize>,
+    new: &[&str],
+    new_range: Range<usize>,
+    vf: &mut V,
+    vb: &mut V,
+) -> (usize, usize) {
+    let old = &old[old_range.clone()];
+    let new = &new[new_range.clone()];
+    let (n, m) = (old.len(), new.len());
+    let delta = n as isize - m as isize;
+    let odd = delta & 1 == 1;
+    vf[1] = 0;
+    vb[1] = 0;
+    let max = ((n + m).div_ceil(2) + 1) as isize;
+    for d in 0..max {
+        let mut k = d;
+        while k >= -d {
+            let x0 = if k == -d || (k != d && vf[k - 1] < vf[k + 1]) {
+                vf[k + 1]
+            } else {
+                vf[k - 1] + 1
+            };
+            let y0 = (x0 as isize - k) as usize;
+            let (mut x, mut y) = (x0, y0);
+            while x < n && y < m && old[x] == new[y] {
+                x += 1;
+                y += 1;
+            }
+            vf[k] = x;
+            if odd && (k - delta).abs() < d && vf[k] + vb[-(k - delta)] >= n {
+                return (old_range.start + x0, new_range.start + y0);
+            }
+            k -= 2;
+        }
+
+        let mut k = d;
+        while k >= -d {
+            let mut x = if k == -d || (k != d && vb[k - 1] < vb[k + 1]) {
+                vb[k + 1]
+            } else {
+                vb[k - 1] + 1
+            };
+            let mut y = (x as isize - k) as usize;
+            while x < n && y < m && old[n - x - 1] == new[m - y - 1] {
+                x += 1;
+                y += 1;
+            }
+            vb[k] = x;
+            if !odd && (k - delta).abs() <= d && vb[k] + vf[-(k - delta)] >= n {
+                return (old_range.start + n - x, new_range.start + m - y);
+            }
+            k -= 2;
+        }
+    }
+    unreachable!("the paths from both ends always overlap")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diff() {
+        assert_eq!(unified_diff("a\nb\n", "a\nb\n", "a", "b"), "");
+
+        let old = "1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n";
+        let new = "1\n2\n3\n4\nfive\n6\n7\n8\n9\n10\n11\n";
+        assert_eq!(
+            unified_diff(old, new, "a/x", "b/x"),
+            "--- a/x
++++ b/x
+@@ -2,9 +2,10 @@
+ 2
+ 3
+ 4
+-5
++five
+ 6
+ 7
+ 8
+ 9
+ 10
++11
+"
+        );
+
+        assert_eq!(
+            unified_diff("", "a\n", "a/x", "b/x"),
+            "--- a/x\n+++ b/x\n@@ -0,0 +1,1 @@\n+a\n"
+        );
+        assert_eq!(
+            unified_diff("a\n", "", "a/x", "b/x"),
+            "--- a/x\n+++ b/x\n@@ -1,1 +0,0 @@\n-a\n"
+        );
+    }
+
+    #[test]
+    fn edits_are_minimal() {
+        fn check(old: &[&str], new: &[&str], expected: usize) {
+            let edits = edits(old, new);
+            let mut rebuilt = Vec::new();
+            let mut changes = 0;
+            for edit in &edits {
+                match *edit {
+                    Edit::Equal(i, j) => {
+                        assert_eq!(old[i], new[j]);
+                        rebuilt.push(old[i]);
+                    }
+                    Edit::Delete(..) => changes += 1,
+                    Edit::Insert(_, j) => {
+                        changes += 1;
+                        rebuilt.push(new[j]);
+                    }
+                }
+            }
+            assert_eq!(rebuilt, new);
+            assert_eq!(changes, expected);
+        }
+
+        check(
+            &["a", "b", "c", "a", "b", "b", "a"],
+            &["c", "b", "a", "b", "a", "c"],
+            5,
+        );
+        check(&["x", "y"], &["y", "x"], 2);
+        check(&[], &["a", "b"], 2);
+
+        // Rewriting every line of a large file needs space linear in its size.
+        let old = (0..5000).map(|i| format!("old {i}")).collect::<Vec<_>>();
+        let new = (0..5000).map(|i| format!("new {i}")).collect::<Vec<_>>();
+        let old = old.iter().map(|s| s.as_str()).collect::<Vec<_>>();
+        let new = new.iter().map(|s| s.as_str()).collect::<Vec<_>>();
+        check(&old, &new, 10000);
+    }
+}
@@ -0,0 +1,298 @@
+//! Selection of the parts of a world to generate bindings for.
+//!
+//! A `Filter` is applied to a `Resolve` before it's handed to a generator, so
+//! every generator only sees the interfaces, functions and types which were
+//! selected and needs no support of its own.
+
+use crate::Diagnostic;
+use std::collections::HashSet;
+use wit_parser::*;
+
+/// Patterns selecting which items of a world bindings are generated for.
+///
+/// Patterns are matched against the same paths accepted by `Renames`:
+/// `a:b/iface` for an interface, `a:b/iface#item` for a function or type in
+/// an interface, and `item` for one defined directly in a world. A `*` in a
+/// pattern matches any sequence of characters.
+///
+/// A function is kept if it, or the interface it's defined in, matches an
+/// `only` pattern (or there are none), and neither matches an `exclude` pattern.
+/// Types follow the functions which use them, so a type only used by
+/// functions which were filtered out is dropped as well. Types which no
+/// function uses are selected with the same rules as functions, and any type
+/// can be kept explicitly by naming it in an `only` pattern. Interfaces left
+/// with nothing in them are removed from the world.
+#[derive(Debug, Clone, Default)]
+pub struct Filter {
+    only: Vec<String>,
+    exclude: Vec<String>,
+}
+
+impl Filter {
+    pub fn new(only: Vec<String>, exclude: Vec<String>) -> Result<Filter, Diagnostic> {
+        for pattern in only.iter().chain(exclude.iter()) {
+            if pattern.is_empty() {
+                return Err(Diagnostic::new("empty filter pattern"));
+            }
+        }
+        Ok(Filter { only, exclude })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.only.is_empty() && self.exclude.is_empty()
+    }
+
+    /// Returns a copy of `resolve` in which `world`, and the interfaces it
+    /// refers to, only contain the items selected by this filter.
+    ///
+    /// Returns an error if a pattern doesn't match anything in the world,
+    /// which is most likely a typo.
+    pub fn apply(&self, resolve: &Resolve, world: WorldId) -> Result<Resolve, Diagnostic> {
+        let mut matched = HashSet::new();
+        let w = &resolve.worlds[world];
+
+        // Functions which are kept, by the interface they're in.
+        let mut funcs = HashSet::new();
+        // Types used by any function in the world, and the types which must be
+        // kept, starting with those used by the functions which are kept.
+        let mut used = LiveTypes::default();
+        let mut live = LiveTypes::default();
+        // Types which aren't reachable from a function but are candidates to
+        // be kept on their own.
+        let mut types = Vec::new();
+
+        for (key, item) in w.imports.iter().chain(w.exports.iter()) {
+            let item_path = resolve.name_world_key(key);
+            match item {
+                WorldItem::Function(f) => {
+                    used.add_func(resolve, f);
+                    if self.selects(&[&item_path], &mut matched) {
+                        live.add_func(resolve, f);
+                        funcs.insert((None, f.name.clone()));
+                    }
+                }
+                WorldItem::Interface(id) => {
+                    let iface = &resolve.interfaces[*id];
+                    for (name, f) in iface.functions.iter() {
+                        used.add_func(resolve, f);
+                        let path = format!("{item_path}#{name}");
+                        if self.selects(&[&item_path, &path], &mut matched) {
+                            live.add_func(resolve, f);
+                            funcs.insert((Some(*id), name.clone()));
+                        }
+                    }
+                    for (name, ty) in iface.types.iter() {
+                        types.push((*ty, vec![item_path.clone(), format!("{item_path}#{name}")]));
+                    }
+                }
+                WorldItem::Type(ty) => types.push((*ty, vec![item_path])),
+            }
+        }
+
+        let used = used.iter().collect::<HashSet<_>>();
+        for (ty, paths) in types {
+            let paths = paths.iter().map(|p| p.as_str()).collect::<Vec<_>>();
+            let explicit = self.only.iter().any(|p| glob(p, paths.last().unwrap()));
+            let selected = self.selects(&paths, &mut matched);
+            if selected && (explicit || !used.contains(&ty)) {
+                live.add_type_id(resolve, ty);
+            }
+        }
+
+        if let Some(pattern) = self
+            .only
+            .iter()
+            .chain(self.exclude.iter())
+            .find(|p| !matched.contains(&p.as_str()))
+        {
+            return Err(Diagnostic::new(format!(
+                "filter pattern `{pattern}` doesn't match anything in the world"
+            )));
+        }
+
+        let live = live.iter().collect::<HashSet<_>>();
+        let mut resolve = resolve.clone();
+        let mut interfaces = HashSet::new();
+        {
+            let w = &resolve.worlds[world];
+            for (_, item) in w.imports.iter().chain(w.exports.iter()) {
+                if let WorldItem::Interface(id) = item {
+                    interfaces.insert(*id);
+                }
+            }
+        }
+        for id in interfaces {
+            let iface = &mut resolve.interfaces[id];
+            iface
+                .functions
+                .retain(|name, _| funcs.contains(&(Some(id), name.clone())));
+            iface.types.retain(|_, ty| live.contains(ty));
+        }
+
+        let Resolve {
+            worlds, interfaces, ..
+        } = &mut resolve;
+        let w = &mut worlds[world];
+        let keep = |item: &WorldItem| match item {
+            WorldItem::Function(f) => funcs.contains(&(None, f.name.clone())),
+            WorldItem::Interface(id) => {
+                let iface = &interfaces[*id];
+                !iface.functions.is_empty() || !iface.types.is_empty()
+            }
+            WorldItem::Type(ty) => live.contains(ty),
+        };
+        w.imports.retain(|_, item| keep(item));
+        w.exports.retain(|_, item| keep(item));
+        Ok(resolve)
+    }
+
+    /// Returns whether an item with the given paths, from the outermost to the
+    /// item itself, is selected, recording which patterns matched.
+    fn selects<'a>(&'a self, paths: &[&str], matched: &mut HashSet<&'a str>) -> bool {
+        let mut matches = |patterns: &'a [String]| {
+            let mut any = false;
+            for pattern in patterns {
+                if paths.iter().any(|path| glob(pattern, path)) {
+                    matched.insert(pattern.as_str());
+                    any = true;
+                }
+            }
+            any
+        };
+        let only = self.only.is_empty() || matches(&self.only);
+        let exclude = matches(&self.exclude);
+        only && !exclude
+    }
+}
+
+/// Matches `s` against `pattern`, in which `*` matches any sequence of
+/// characters.
+fn glob(pattern: &str, s: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap();
+    let mut rest = match s.strip_prefix(first) {
+        Some(rest) => rest,
+        None => return false,
+    };
+    let mut parts = parts.collect::<Vec<_>>();
+    let last = match parts.pop() {
+        Some(last) => last,
+        None => return rest.is_empty(),
+    };
+    for part in parts {
+        match rest.find(part) {
+            Some(i) => rest = &rest[i + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn world(filter: &Filter) -> Result<Resolve, Diagnostic> {
+        let pkg = UnresolvedPackage::parse(
+            "test.wit".as_ref(),
+            "
+                package a:b
+
+                interface types {
+                    record point { x: u32 }
+                    record size { w: u32 }
+                    type standalone = u8
+                }
+
+                interface geometry {
+                    use types.{point, size}
+                    move: func(p: point) -> point
+                    grow: func(s: size) -> size
+                }
+
+                world w {
+                    import geometry
+                    import log: func(msg: string)
+                }
+            ",
+        )
+        .unwrap();
+        let mut resolve = Resolve::default();
+        let pkg = resolve.push(pkg).unwrap();
+        let world = resolve.select_world(pkg, Some("w")).unwrap();
+        filter.apply(&resolve, world)
+    }
+
+    fn contents(resolve: &Resolve) -> Vec<String> {
+        let w = resolve.worlds.iter().next().unwrap().1;
+        let mut items = Vec::new();
+        for (key, item) in w.imports.iter() {
+            let path = resolve.name_world_key(key);
+            if let WorldItem::Interface(id) = item {
+                let iface = &resolve.interfaces[*id];
+                for name in iface.types.keys().chain(iface.functions.keys()) {
+                    items.push(format!("{path}#{name}"));
+                }
+            } else {
+                items.push(path);
+            }
+        }
+        items.sort();
+        items
+    }
+
+    #[test]
+    fn glob() {
+        assert!(super::glob("a:b/*", "a:b/types"));
+        assert!(super::glob("*#get-*", "a:b/i#get-x"));
+        assert!(super::glob("log", "log"));
+        assert!(super::glob("a*a", "aba"));
+        assert!(!super::glob("a*a", "a"));
+        assert!(!super::glob("log", "logs"));
+        assert!(!super::glob("a:b/*#x", "a:b/i#y"));
+    }
+
+    #[test]
+    fn filter() {
+        let all = world(&Filter::default()).unwrap();
+        assert_eq!(
+            contents(&all),
+            [
+                "a:b/geometry#grow",
+                "a:b/geometry#move",
+                "a:b/geometry#point",
+                "a:b/geometry#size",
+                "a:b/types#point",
+                "a:b/types#size",
+                "a:b/types#standalone",
+                "log",
+            ]
+        );
+
+        let filter = Filter::new(vec!["a:b/geometry#move".to_string()], Vec::new()).unwrap();
+        assert_eq!(
+            contents(&world(&filter).unwrap()),
+            ["a:b/geometry#move", "a:b/geometry#point", "a:b/types#point"]
+        );
+
+        let filter = Filter::new(Vec::new(), vec!["a:b/geometry".to_string()]).unwrap();
+        assert_eq!(
+            contents(&world(&filter).unwrap()),
+            ["a:b/types#standalone", "log"]
+        );
+
+        let filter = Filter::new(
+            vec!["a:b/types#size".to_string(), "log".to_string()],
+            Vec::new(),
+        )
+        .unwrap();
+        assert_eq!(
+            contents(&world(&filter).unwrap()),
+            ["a:b/types#size", "log"]
+        );
+
+        let filter = Filter::new(vec!["nope".to_string()], Vec::new()).unwrap();
+        let err = world(&filter).unwrap_err();
+        assert!(err.reason.contains("`nope` doesn't match"));
+    }
+}
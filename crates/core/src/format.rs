@@ -0,0 +1,751 @@
+//! Language-aware layout of generated source code.
+//!
+//! Generators push source text into a [`Source`](crate::Source) without
+//! worrying about indentation. The layout engine here tracks the nesting of
+//! delimiters, skipping over comments and string literals, and re-indents
+//! each line according to the conventions of the target language described by
+//! a [`Style`].
+
+/// The layout conventions of a target language.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Style {
+    /// The text of one level of indentation.
+    pub indent: &'static str,
+
+    /// Where the opening brace of a block is placed.
+    pub braces: BraceStyle,
+
+    /// The width beyond which lines are broken at commas, if any.
+    pub max_width: Option<usize>,
+
+    /// Whether a trailing comma is added to the last item of a list which
+    /// has been broken over multiple lines.
+    pub trailing_commas: bool,
+
+    /// Whether `case` and `default` labels are placed at the indentation of
+    /// their enclosing `switch`, as `gofmt` does.
+    pub outdent_case_labels: bool,
+
+    /// Whether lines starting with `#` are preprocessor directives which are
+    /// never indented.
+    pub preprocessor: bool,
+
+    /// The syntax of raw string literals in the language, in which escapes
+    /// aren't processed.
+    pub raw_strings: RawStrings,
+
+    /// Whether the text is code made up of delimited blocks.
+    ///
+    /// Prose, such as markdown, is only indented explicitly through
+    /// [`Source::indent`](crate::Source::indent) and is otherwise left
+    /// as-is.
+    pub code: bool,
+}
+
+/// Where the opening brace of a block is placed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BraceStyle {
+    /// At the end of the line which starts the block.
+    SameLine,
+    /// On a line of its own.
+    NextLine,
+}
+
+/// The syntax of raw string literals.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RawStrings {
+    /// The language has no raw string literals.
+    None,
+    /// Rust's `r"..."` and `r#"..."#` literals.
+    Rust,
+    /// Literals delimited by backticks, as in Go.
+    Backtick,
+}
+
+impl Style {
+    pub const C: Style = Style {
+        indent: "  ",
+        braces: BraceStyle::SameLine,
+        max_width: None,
+        trailing_commas: false,
+        outdent_case_labels: false,
+        preprocessor: true,
+        raw_strings: RawStrings::None,
+        code: true,
+    };
+
+    pub const RUST: Style = Style {
+        indent: "    ",
+        braces: BraceStyle::SameLine,
+        max_width: Some(100),
+        trailing_commas: true,
+        outdent_case_labels: false,
+        preprocessor: false,
+        raw_strings: RawStrings::Rust,
+        code: true,
+    };
+
+    pub const GO: Style = Style {
+        indent: "\t",
+        braces: BraceStyle::SameLine,
+        max_width: None,
+        trailing_commas: true,
+        outdent_case_labels: true,
+        preprocessor: false,
+        raw_strings: RawStrings::Backtick,
+        code: true,
+    };
+
+    pub const JAVA: Style = Style {
+        indent: "    ",
+        braces: BraceStyle::SameLine,
+        max_width: Some(100),
+        trailing_commas: false,
+        outdent_case_labels: false,
+        preprocessor: false,
+        raw_strings: RawStrings::None,
+        code: true,
+    };
+
+    pub const MARKDOWN: Style = Style {
+        indent: "  ",
+        braces: BraceStyle::SameLine,
+        max_width: None,
+        trailing_commas: false,
+        outdent_case_labels: false,
+        preprocessor: false,
+        raw_strings: RawStrings::None,
+        code: false,
+    };
+}
+
+impl Default for Style {
+    /// A generic brace-delimited style indenting with two spaces.
+    fn default() -> Style {
+        Style {
+            preprocessor: false,
+            ..Style::C
+        }
+    }
+}
+
+/// Lays out `src` according to `style`.
+pub fn format(src: &str, style: Style) -> String {
+    let mut dst = String::new();
+    let mut layout = Layout::new(style);
+    layout.push_str(&mut dst, src);
+    dst
+}
+
+/// Lexical state of the layout engine, used to find the delimiters which
+/// affect indentation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum Lex {
+    #[default]
+    Code,
+    LineComment,
+    BlockComment,
+    Str {
+        escaped: bool,
+    },
+    /// A character literal, or possibly a Rust lifetime or label, after
+    /// `len` characters.
+    Char {
+        len: u8,
+        escaped: bool,
+        escape: bool,
+    },
+    RawStr {
+        hashes: usize,
+        closing: Option<usize>,
+    },
+    Backtick,
+}
+
+impl Lex {
+    fn in_literal(&self) -> bool {
+        matches!(
+            self,
+            Lex::Str { .. } | Lex::RawStr { .. } | Lex::Backtick | Lex::Char { .. }
+        )
+    }
+
+    /// Advances the state past `c`, given the text `before` it on the same
+    /// line.
+    ///
+    /// Returns `true` if `c` is code, as opposed to part of a comment or
+    /// literal.
+    fn step(&mut self, style: &Style, before: &str, c: char) -> bool {
+        let prev = before.chars().next_back();
+        match *self {
+            Lex::Code => {}
+            Lex::LineComment => return false,
+            Lex::BlockComment => {
+                if prev == Some('*') && c == '/' {
+                    *self = Lex::Code;
+                }
+                return false;
+            }
+            Lex::Str { escaped } => {
+                *self = match c {
+                    '"' if !escaped => Lex::Code,
+                    '\\' => Lex::Str { escaped: !escaped },
+                    _ => Lex::Str { escaped: false },
+                };
+                return false;
+            }
+            Lex::Char {
+                len,
+                escaped,
+                escape,
+            } => {
+                match c {
+                    '\'' if !escape && (escaped || len == 1) => *self = Lex::Code,
+                    '\\' if len == 0 => {
+                        *self = Lex::Char {
+                            len: 1,
+                            escaped: true,
+                            escape: true,
+                        }
+                    }
+                    '\n' => *self = Lex::Code,
+                    _ if !escaped && len >= 1 => {
+                        // Not a character literal after all, but a lifetime
+                        // or a label.
+                        *self = Lex::Code;
+                        return self.step(style, before, c);
+                    }
+                    _ => {
+                        *self = Lex::Char {
+                            len: len.saturating_add(1),
+                            escaped,
+                            escape: c == '\\' && !escape,
+                        }
+                    }
+                }
+                return false;
+            }
+            Lex::RawStr { hashes, closing } => {
+                *self = match (c, closing) {
+                    ('"', _) if hashes == 0 => Lex::Code,
+                    ('"', _) => Lex::RawStr {
+                        hashes,
+                        closing: Some(hashes),
+                    },
+                    ('#', Some(1)) => Lex::Code,
+                    ('#', Some(n)) => Lex::RawStr {
+                        hashes,
+                        closing: Some(n - 1),
+                    },
+                    _ => Lex::RawStr {
+                        hashes,
+                        closing: None,
+                    },
+                };
+                return false;
+            }
+            Lex::Backtick => {
+                if c == '`' {
+                    *self = Lex::Code;
+                }
+                return false;
+            }
+        }
+
+        match c {
+            '/' if prev == Some('/') => *self = Lex::LineComment,
+            '*' if prev == Some('/') => *self = Lex::BlockComment,
+            '"' => {
+                *self = match raw_string_hashes(style, before) {
+                    Some(hashes) => Lex::RawStr {
+                        hashes,
+                        closing: None,
+                    },
+                    None => Lex::Str { escaped: false },
+                }
+            }
+            '\'' => {
+                *self = Lex::Char {
+                    len: 0,
+                    escaped: false,
+                    escape: false,
+                }
+            }
+            '`' if style.raw_strings == RawStrings::Backtick => *self = Lex::Backtick,
+            _ => return true,
+        }
+        false
+    }
+}
+
+/// If a `"` following `before` starts a raw string, returns the number of
+/// `#`s delimiting it.
+fn raw_string_hashes(style: &Style, before: &str) -> Option<usize> {
+    if style.raw_strings != RawStrings::Rust {
+        return None;
+    }
+    let without_hashes = before.trim_end_matches('#');
+    let hashes = before.len() - without_hashes.len();
+    let prefix = without_hashes.strip_suffix('r')?;
+    let prefix = prefix.strip_suffix('b').unwrap_or(prefix);
+    match prefix.chars().next_back() {
+        Some(c) if c.is_alphanumeric() || c == '_' => None,
+        _ => Some(hashes),
+    }
+}
+
+fn is_opener(c: char) -> bool {
+    matches!(c, '{' | '(' | '[')
+}
+
+fn is_closer(c: char) -> bool {
+    matches!(c, '}' | ')' | ']')
+}
+
+/// The streaming layout engine behind [`Source`](crate::Source).
+///
+/// Text is appended to a `String` owned by the caller. Each line is indented
+/// as soon as its first character is seen so the output is always complete
+/// up to the last character pushed, and lines are only rewritten (to trim
+/// trailing whitespace or to break long lines) once they're finished.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Layout {
+    style: Style,
+    depth: usize,
+    /// The delimiters which are currently open, and whether each one
+    /// increased the indentation.
+    ///
+    /// Only the first delimiter opened on a line increases the indentation,
+    /// so `foo(bar(|| {` only indents what follows by one level.
+    open: Vec<bool>,
+    /// Whether a delimiter opened on the current line has increased the
+    /// indentation.
+    line_indented: bool,
+    lex: Lex,
+    /// The byte offset of the start of the current line.
+    line_start: usize,
+    /// The lexical state at the start of the current line.
+    line_lex: Lex,
+    /// The length of the indentation written for the current line, or
+    /// `None` if the line doesn't have any content yet.
+    prefix: Option<usize>,
+    /// Whether the current line consists only of closing delimiters so far.
+    only_closers: bool,
+    /// Whether the indentation of the current line was reduced as it's a
+    /// `case` label.
+    outdented: bool,
+}
+
+impl Layout {
+    pub(crate) fn new(style: Style) -> Layout {
+        Layout {
+            style,
+            ..Layout::default()
+        }
+    }
+
+    pub(crate) fn indent(&mut self, amt: usize) {
+        self.depth += amt;
+    }
+
+    pub(crate) fn deindent(&mut self, amt: usize) {
+        // Note that a `saturating_sub` is used here and below to prevent a
+        // panic in the case of invalid code being generated in debug mode.
+        // It's typically easier to debug those issues through looking at the
+        // source code rather than getting a panic.
+        self.depth = self.depth.saturating_sub(amt);
+    }
+
+    pub(crate) fn push_str(&mut self, dst: &mut String, src: &str) {
+        for c in src.chars() {
+            self.push(dst, c);
+        }
+    }
+
+    fn push(&mut self, dst: &mut String, c: char) {
+        if c == '\n' {
+            self.end_line(dst);
+            return;
+        }
+
+        if self.prefix.is_none() {
+            let verbatim = self.line_lex.in_literal();
+            if !self.style.code || verbatim {
+                // Prose is indented explicitly, and lines in the middle of a
+                // multi-line literal are left exactly as they are.
+                let level = if verbatim { 0 } else { self.depth };
+                self.start_line(dst, level);
+            } else if c.is_whitespace() {
+                return;
+            } else {
+                let level = if self.style.preprocessor && c == '#' && self.lex == Lex::Code {
+                    0
+                } else {
+                    self.depth
+                };
+                self.start_line(dst, level);
+                if self.line_lex == Lex::BlockComment && c == '*' {
+                    dst.push(' ');
+                }
+            }
+        }
+
+        if !self.style.code {
+            dst.push(c);
+            return;
+        }
+
+        let before = &dst[self.line_start..];
+        let is_code = self.lex.step(&self.style, before, c);
+        if is_code && is_closer(c) {
+            if let Some(true) = self.open.pop() {
+                self.depth = self.depth.saturating_sub(1);
+                self.line_indented = false;
+                if self.only_closers {
+                    self.outdent(dst);
+                }
+            }
+        } else {
+            self.only_closers = false;
+        }
+        if is_code && is_opener(c) {
+            let indents = !self.line_indented;
+            if indents {
+                self.depth += 1;
+                self.line_indented = true;
+            }
+            self.open.push(indents);
+        }
+        dst.push(c);
+
+        if self.style.outdent_case_labels && !self.outdented && self.lex == Lex::Code {
+            let content = &dst[self.line_start + self.prefix.unwrap()..];
+            if content == "case " || content == "default:" {
+                self.outdented = true;
+                self.outdent(dst);
+            }
+        }
+    }
+
+    fn start_line(&mut self, dst: &mut String, level: usize) {
+        for _ in 0..level {
+            dst.push_str(self.style.indent);
+        }
+        self.prefix = Some(level * self.style.indent.len());
+        self.only_closers = true;
+    }
+
+    /// Removes one level of indentation from the current line.
+    fn outdent(&mut self, dst: &mut String) {
+        let prefix = self.prefix.unwrap();
+        let unit = self.style.indent.len();
+        if prefix >= unit {
+            dst.replace_range(self.line_start..self.line_start + unit, "");
+            self.prefix = Some(prefix - unit);
+        }
+    }
+
+    fn end_line(&mut self, dst: &mut String) {
+        if self.lex == Lex::LineComment || matches!(self.lex, Lex::Char { .. }) {
+            self.lex = Lex::Code;
+        }
+
+        if self.style.code && !self.line_lex.in_literal() {
+            let trimmed = dst.trim_end().len().max(self.line_start);
+            dst.truncate(trimmed);
+
+            if self.prefix.is_none() && self.skip_blank_line(dst) {
+                self.next_line(dst);
+                return;
+            }
+            if self.prefix.is_some() && self.line_lex == Lex::Code {
+                self.relayout_line(dst);
+            }
+        }
+
+        dst.push('\n');
+        self.next_line(dst);
+    }
+
+    fn next_line(&mut self, dst: &str) {
+        self.line_start = dst.len();
+        self.line_lex = self.lex;
+        self.prefix = None;
+        self.only_closers = false;
+        self.outdented = false;
+        self.line_indented = false;
+    }
+
+    /// Returns whether an empty line ending here should be dropped, as it
+    /// either follows another empty line or the opening of a block.
+    fn skip_blank_line(&self, dst: &str) -> bool {
+        let before = &dst[..self.line_start];
+        if before.is_empty() {
+            return false;
+        }
+        before.ends_with("\n\n") || before.trim_end().ends_with('{')
+    }
+
+    /// Breaks the finished current line if it's too long, and moves opening
+    /// braces to their own line if the style asks for it.
+    fn relayout_line(&mut self, dst: &mut String) {
+        let prefix = self.prefix.unwrap();
+        let level = prefix / self.style.indent.len().max(1);
+        let line = &dst[self.line_start + prefix..];
+        if line.starts_with("//") || line.starts_with("/*") || line.starts_with('#') {
+            return;
+        }
+
+        let mut lines = Vec::new();
+        self.wrap(line, level, 0, &mut lines);
+        if self.style.braces == BraceStyle::NextLine {
+            lines = lines
+                .into_iter()
+                .flat_map(|(level, line)| self.brace_on_next_line(level, line))
+                .collect();
+        }
+        if lines.len() == 1 {
+            return;
+        }
+
+        let mut out = String::new();
+        for (i, (level, line)) in lines.iter().enumerate() {
+            if i > 0 {
+                out.push('\n');
+            }
+            for _ in 0..*level {
+                out.push_str(self.style.indent);
+            }
+            out.push_str(line);
+        }
+        dst.replace_range(self.line_start.., &out);
+    }
+
+    /// Breaks `line`, indented at `level`, at the commas of its first
+    /// delimited list if it's wider than the style allows.
+    fn wrap(&self, line: &str, level: usize, nesting: usize, out: &mut Vec<(usize, String)>) {
+        let width = level * self.style.indent.len() + line.len();
+        let fits = match self.style.max_width {
+            Some(max) => width <= max,
+            None => true,
+        };
+        let group = if fits || nesting > 8 {
+            None
+        } else {
+            self.first_list(line)
+        };
+        let (open, close, commas) = match group {
+            Some(group) => group,
+            None => {
+                out.push((level, line.to_string()));
+                return;
+            }
+        };
+
+        let mut items = Vec::new();
+        let mut start = open + 1;
+        for comma in commas.iter().copied().chain([close]) {
+            let item = line[start..comma].trim();
+            if !item.is_empty() {
+                items.push(item);
+            }
+            start = comma + 1;
+        }
+        let had_trailing_comma = line[..close].trim_end().ends_with(',');
+        let trailing_comma = self.style.trailing_commas || had_trailing_comma;
+
+        self.wrap(&line[..=open], level, nesting + 1, out);
+        if line[open..].starts_with('[') {
+            // Arrays can be long, so pack as many items on each line as fit.
+            let max = self.style.max_width.unwrap_or(usize::MAX);
+            let indent = (level + 1) * self.style.indent.len();
+            let mut cur = String::new();
+            for (i, item) in items.iter().enumerate() {
+                let comma = if i + 1 < items.len() || trailing_comma {
+                    ","
+                } else {
+                    ""
+                };
+                if !cur.is_empty() && indent + cur.len() + 1 + item.len() + comma.len() > max {
+                    out.push((level + 1, std::mem::take(&mut cur)));
+                }
+                if !cur.is_empty() {
+                    cur.push(' ');
+                }
+                cur.push_str(item);
+                cur.push_str(comma);
+            }
+            out.push((level + 1, cur));
+        } else {
+            for (i, item) in items.iter().enumerate() {
+                let comma = if i + 1 < items.len() || trailing_comma {
+                    ","
+                } else {
+                    ""
+                };
+                self.wrap(&format!("{item}{comma}"), level + 1, nesting + 1, out);
+            }
+        }
+        self.wrap(&line[close..], level, nesting + 1, out);
+    }
+
+    /// Finds the first parenthesized or bracketed list in `line` which
+    /// closes on the same line and directly contains a comma.
+    ///
+    /// Returns the byte offsets of the opening and closing delimiters and of
+    /// the commas separating the items of the list.
+    fn first_list(&self, line: &str) -> Option<(usize, usize, Vec<usize>)> {
+        let mut lex = Lex::Code;
+        let mut stack: Vec<(usize, Vec<usize>)> = Vec::new();
+        let mut angles = 0;
+        let mut prev = ' ';
+        for (i, c) in line.char_indices() {
+            let is_code = lex.step(&self.style, &line[..i], c);
+            if !is_code {
+                prev = c;
+                continue;
+            }
+            match c {
+                // Generic arguments such as `Vec<T>` or `::<T>` can contain
+                // commas which don't separate the items of the enclosing list.
+                '<' if prev.is_alphanumeric() || prev == ':' => angles += 1,
+                '>' if angles > 0 && prev != '-' && prev != '=' => angles -= 1,
+                '(' | '[' => stack.push((i, Vec::new())),
+                '{' => stack.push((i, Vec::new())),
+                ')' | ']' | '}' => {
+                    if let Some((open, commas)) = stack.pop() {
+                        if stack.is_empty() && c != '}' && !commas.is_empty() {
+                            return Some((open, i, commas));
+                        }
+                    }
+                }
+                ',' if angles == 0 => {
+                    if let Some((_, commas)) = stack.last_mut() {
+                        commas.push(i);
+                    }
+                }
+                _ => {}
+            }
+            prev = c;
+        }
+        None
+    }
+
+    fn brace_on_next_line(&self, level: usize, line: String) -> Vec<(usize, String)> {
+        let mut lex = Lex::Code;
+        let mut last_code = None;
+        for (i, c) in line.char_indices() {
+            if lex.step(&self.style, &line[..i], c) && !c.is_whitespace() {
+                last_code = Some((i, c));
+            }
+        }
+        match last_code {
+            Some((i, '{')) if i + 1 == line.len() && !line[..i].trim().is_empty() => {
+                vec![
+                    (level, line[..i].trim_end().to_string()),
+                    (level, "{".to_string()),
+                ]
+            }
+            _ => vec![(level, line)],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{format, BraceStyle, Style};
+
+    #[test]
+    fn indents_blocks() {
+        let src = "fn foo() {\nif x {\ny\n} else {\nz\n}\n}\n";
+        assert_eq!(
+            format(src, Style::RUST),
+            "fn foo() {\n    if x {\n        y\n    } else {\n        z\n    }\n}\n"
+        );
+        assert_eq!(
+            format(src, Style::GO),
+            "fn foo() {\n\tif x {\n\t\ty\n\t} else {\n\t\tz\n\t}\n}\n"
+        );
+    }
+
+    #[test]
+    fn ignores_literals_and_comments() {
+        let src = "foo(\"{\", '}', r#\"(\"#) {\n// {\n/* ( */\nx\n}\n";
+        assert_eq!(
+            format(src, Style::RUST),
+            "foo(\"{\", '}', r#\"(\"#) {\n    // {\n    /* ( */\n    x\n}\n"
+        );
+        let src = "fn f<'a>(x: &'a str) {\ny\n}\n";
+        assert_eq!(
+            format(src, Style::RUST),
+            "fn f<'a>(x: &'a str) {\n    y\n}\n"
+        );
+    }
+
+    #[test]
+    fn multiline_literals_are_verbatim() {
+        let src = "x := `\n  {\n`\nif y {\nz\n}\n";
+        assert_eq!(format(src, Style::GO), "x := `\n  {\n`\nif y {\n\tz\n}\n");
+    }
+
+    #[test]
+    fn closers_and_case_labels() {
+        let src = "foo(bar(|| {\nx\n}))\n";
+        assert_eq!(format(src, Style::RUST), "foo(bar(|| {\n    x\n}))\n");
+        let src = "switch x {\ncase 1:\ny()\ndefault:\nz()\n}\n";
+        assert_eq!(
+            format(src, Style::GO),
+            "switch x {\ncase 1:\n\ty()\ndefault:\n\tz()\n}\n"
+        );
+    }
+
+    #[test]
+    fn preprocessor_and_blank_lines() {
+        let src = "struct {\n#ifdef X\nint x;\n\n\n#endif\n}\n";
+        assert_eq!(
+            format(src, Style::C),
+            "struct {\n#ifdef X\n  int x;\n\n#endif\n}\n"
+        );
+        assert_eq!(format("a {\n\nb\n}\n", Style::C), "a {\n  b\n}\n");
+    }
+
+    #[test]
+    fn wraps_long_lines() {
+        let style = Style {
+            max_width: Some(20),
+            ..Style::RUST
+        };
+        assert_eq!(
+            format("foo(alpha, beta, gamma);\n", style),
+            "foo(\n    alpha,\n    beta,\n    gamma,\n);\n"
+        );
+        assert_eq!(
+            format("x = [1, 2, 3, 4, 5, 6, 7, 8];\n", style),
+            "x = [\n    1, 2, 3, 4, 5,\n    6, 7, 8,\n];\n"
+        );
+        assert_eq!(
+            format("f(arg: Map<K, V>, b);\n", style),
+            "f(\n    arg: Map<K, V>,\n    b,\n);\n"
+        );
+    }
+
+    #[test]
+    fn next_line_braces() {
+        let style = Style {
+            braces: BraceStyle::NextLine,
+            ..Style::JAVA
+        };
+        assert_eq!(
+            format("void f() {\nx();\n}\n", style),
+            "void f()\n{\n    x();\n}\n"
+        );
+    }
+
+    #[test]
+    fn markdown_is_only_indented_explicitly() {
+        assert_eq!(
+            format("- a {\n    - b\n", Style::MARKDOWN),
+            "- a {\n    - b\n"
+        );
+    }
+}
@@ -1,16 +1,29 @@
-use std::collections::{btree_map::Entry, BTreeMap, HashMap};
+use std::collections::{btree_map::Entry, BTreeMap, HashMap, HashSet};
 use std::fmt::{self, Write};
 use std::ops::Deref;
 use wit_parser::*;
 
 pub use wit_parser;
+mod diagnostic;
+mod diff;
+mod filter;
+mod format;
+mod naming;
 mod ns;
+mod snippet;
 
+pub use diagnostic::{check_types, reject_async_functions, reject_async_types, Diagnostic};
+pub use diff::unified_diff;
+pub use filter::Filter;
+pub use format::{format, BraceStyle, RawStrings, Style};
+pub use naming::{Casing, Collision, Names, Profile, Qualify, Renames};
 pub use ns::Ns;
+pub use snippet::{render_error, ErrorSpan};
 
 #[derive(Default)]
 pub struct Types {
     type_info: HashMap<TypeId, TypeInfo>,
+    in_progress: HashSet<TypeId>,
 }
 
 #[derive(Default, Clone, Copy, Debug)]
@@ -33,8 +46,61 @@ pub struct TypeInfo {
     /// error case in the result of a function.
     pub error: bool,
 
+    /// Whether or not this type is ever used (transitively) within a
+    /// parameter or result of a function other than through the error case of
+    /// a `result`.
+    pub non_error: bool,
+
     /// Whether or not this type (transitively) has a list.
     pub has_list: bool,
+
+    /// Whether or not this type (transitively) has a string.
+    ///
+    /// Strings are lists too, so this implies `has_list`.
+    pub has_string: bool,
+
+    /// Whether or not this type (transitively) has a `float32` or `float64`.
+    pub has_float: bool,
+
+    /// Whether or not this type (transitively) has a `future` or `stream`
+    /// handle.
+    pub has_handle: bool,
+
+    /// Whether or not this type (transitively) has a flags type.
+    pub has_flags: bool,
+
+    /// Whether or not this type is made up only of integers and floats,
+    /// possibly nested within records, tuples and aliases.
+    ///
+    /// Values of such types have the same representation in linear memory as
+    /// the equivalent C struct and every bit pattern is a valid value, so
+    /// lists of them can be lifted and lowered with a plain copy.
+    pub plain_old_data: bool,
+
+    /// Whether or not this type refers to itself, possibly through a chain of
+    /// type aliases.
+    ///
+    /// `wit-parser` rejects such types so this is only set for input which
+    /// didn't come from a validated `Resolve`, and the rest of the information
+    /// for such types may be incomplete.
+    pub recursive: bool,
+}
+
+impl TypeInfo {
+    /// Whether or not values of this type have a fixed canonical ABI size
+    /// and don't own any memory or handles, meaning they can be copied
+    /// bitwise.
+    pub fn fixed_size(&self) -> bool {
+        !self.has_list && !self.has_handle
+    }
+
+    /// Whether or not values of this type can be compared for total
+    /// equality, hashed and totally ordered.
+    ///
+    /// Floats only have a partial order and handles can't be compared at all.
+    pub fn total_eq(&self) -> bool {
+        !self.has_float && !self.has_handle
+    }
 }
 
 impl std::ops::BitOrAssign for TypeInfo {
@@ -42,7 +108,15 @@ impl std::ops::BitOrAssign for TypeInfo {
         self.borrowed |= rhs.borrowed;
         self.owned |= rhs.owned;
         self.error |= rhs.error;
+        self.non_error |= rhs.non_error;
         self.has_list |= rhs.has_list;
+        self.has_string |= rhs.has_string;
+        self.has_float |= rhs.has_float;
+        self.has_handle |= rhs.has_handle;
+        self.has_flags |= rhs.has_flags;
+        self.recursive |= rhs.recursive;
+        // `plain_old_data` holds only if it holds for every component, so
+        // it's computed separately in `type_id_info`.
     }
 }
 
@@ -86,6 +160,7 @@ impl Types {
             } else {
                 info.owned = true;
             }
+            info.non_error = true;
         }
         let mut live = LiveTypes::default();
         for ty in func.results.iter_types() {
@@ -96,6 +171,26 @@ impl Types {
             self.type_info.get_mut(&id).unwrap().owned = true;
         }
 
+        // Everything reachable from a result is used outside of an error
+        // position except for what's only reachable through the error case of
+        // a `result`.
+        let mut live = LiveTypes::default();
+        for ty in func.results.iter_types() {
+            if let Type::Id(id) = ty {
+                if let TypeDefKind::Result(r) = &resolve.types[*id].kind {
+                    self.type_info.get_mut(id).unwrap().non_error = true;
+                    if let Some(ok) = &r.ok {
+                        live.add_type(resolve, ok);
+                    }
+                    continue;
+                }
+            }
+            live.add_type(resolve, ty);
+        }
+        for id in live.iter() {
+            self.type_info.get_mut(&id).unwrap().non_error = true;
+        }
+
         for ty in func.results.iter_types() {
             let id = match ty {
                 Type::Id(id) => *id,
@@ -127,23 +222,45 @@ impl Types {
         self.type_info[&id]
     }
 
+    /// Returns whether values of `ty` are plain old data, as described by
+    /// [`TypeInfo::plain_old_data`].
+    pub fn is_plain_old_data(&self, ty: &Type) -> bool {
+        match ty {
+            Type::Id(id) => self.get(*id).plain_old_data,
+            _ => primitive_info(ty).plain_old_data,
+        }
+    }
+
     pub fn type_id_info(&mut self, resolve: &Resolve, ty: TypeId) -> TypeInfo {
         if let Some(info) = self.type_info.get(&ty) {
             return *info;
         }
+        if !self.in_progress.insert(ty) {
+            return TypeInfo {
+                recursive: true,
+                ..TypeInfo::default()
+            };
+        }
         let mut info = TypeInfo::default();
+        let mut plain_old_data = false;
         match &resolve.types[ty].kind {
             TypeDefKind::Record(r) => {
+                plain_old_data = true;
                 for field in r.fields.iter() {
-                    info |= self.type_info(resolve, &field.ty);
+                    let field = self.type_info(resolve, &field.ty);
+                    plain_old_data &= field.plain_old_data;
+                    info |= field;
                 }
             }
             TypeDefKind::Tuple(t) => {
+                plain_old_data = true;
                 for ty in t.types.iter() {
-                    info |= self.type_info(resolve, ty);
+                    let ty = self.type_info(resolve, ty);
+                    plain_old_data &= ty.plain_old_data;
+                    info |= ty;
                 }
             }
-            TypeDefKind::Flags(_) => {}
+            TypeDefKind::Flags(_) => info.has_flags = true,
             TypeDefKind::Enum(_) => {}
             TypeDefKind::Variant(v) => {
                 for case in v.cases.iter() {
@@ -156,6 +273,7 @@ impl Types {
             }
             TypeDefKind::Type(ty) => {
                 info = self.type_info(resolve, ty);
+                plain_old_data = info.plain_old_data;
             }
             TypeDefKind::Option(ty) => {
                 info = self.type_info(resolve, ty);
@@ -171,26 +289,27 @@ impl Types {
             }
             TypeDefKind::Future(ty) => {
                 info = self.optional_type_info(resolve, ty.as_ref());
+                info.has_handle = true;
             }
             TypeDefKind::Stream(stream) => {
                 info = self.optional_type_info(resolve, stream.element.as_ref());
                 info |= self.optional_type_info(resolve, stream.end.as_ref());
+                info.has_handle = true;
             }
             TypeDefKind::Unknown => unreachable!(),
         }
+        info.plain_old_data = plain_old_data && !info.recursive;
+        self.in_progress.remove(&ty);
         let prev = self.type_info.insert(ty, info);
         assert!(prev.is_none());
         info
     }
 
     pub fn type_info(&mut self, resolve: &Resolve, ty: &Type) -> TypeInfo {
-        let mut info = TypeInfo::default();
         match ty {
-            Type::String => info.has_list = true,
-            Type::Id(id) => return self.type_id_info(resolve, *id),
-            _ => {}
+            Type::Id(id) => self.type_id_info(resolve, *id),
+            _ => primitive_info(ty),
         }
-        info
     }
 
     fn optional_type_info(&mut self, resolve: &Resolve, ty: Option<&Type>) -> TypeInfo {
@@ -201,88 +320,186 @@ impl Types {
     }
 }
 
+fn primitive_info(ty: &Type) -> TypeInfo {
+    let mut info = TypeInfo::default();
+    match ty {
+        Type::String => {
+            info.has_list = true;
+            info.has_string = true;
+        }
+        Type::Float32 | Type::Float64 => {
+            info.has_float = true;
+            info.plain_old_data = true;
+        }
+        Type::U8
+        | Type::S8
+        | Type::U16
+        | Type::S16
+        | Type::U32
+        | Type::S32
+        | Type::U64
+        | Type::S64 => info.plain_old_data = true,
+        Type::Bool | Type::Char | Type::Id(_) => {}
+    }
+    info
+}
+
+/// The set of files produced by a generator.
+///
+/// Each file carries a [`FileInfo`] describing where it came from and how it
+/// should be written out.
 #[derive(Default)]
 pub struct Files {
-    files: BTreeMap<String, Vec<u8>>,
+    files: BTreeMap<String, File>,
+}
+
+struct File {
+    info: FileInfo,
+    contents: Vec<u8>,
+}
+
+/// Metadata about a generated file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileInfo {
+    /// The name of the generator which produced this file, for example
+    /// `wit-bindgen-rust`.
+    pub producer: String,
+    /// Whether the file is text or binary.
+    pub kind: FileKind,
+    /// The language the file is written in, if any.
+    pub language: Option<String>,
+    /// Whether the file should be marked as executable when it's written.
+    pub executable: bool,
+}
+
+/// Whether a generated file is text or binary.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileKind {
+    Text,
+    Binary,
+}
+
+impl FileInfo {
+    /// Metadata for a text file written in `language`.
+    pub fn text(producer: &str, language: &str) -> FileInfo {
+        FileInfo {
+            producer: producer.to_string(),
+            kind: FileKind::Text,
+            language: Some(language.to_string()),
+            executable: false,
+        }
+    }
+
+    /// Metadata for a binary file.
+    pub fn binary(producer: &str) -> FileInfo {
+        FileInfo {
+            producer: producer.to_string(),
+            kind: FileKind::Binary,
+            language: None,
+            executable: false,
+        }
+    }
+}
+
+impl fmt::Display for FileKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FileKind::Text => f.write_str("text"),
+            FileKind::Binary => f.write_str("binary"),
+        }
+    }
 }
 
 impl Files {
-    pub fn push(&mut self, name: &str, contents: &[u8]) {
+    /// Adds a new file named `name`.
+    ///
+    /// Returns an error if a file with the same name has already been
+    /// generated, as it would otherwise be overwritten.
+    pub fn create(
+        &mut self,
+        name: &str,
+        info: FileInfo,
+        contents: &[u8],
+    ) -> Result<(), Diagnostic> {
         match self.files.entry(name.to_owned()) {
             Entry::Vacant(entry) => {
-                entry.insert(contents.to_owned());
+                entry.insert(File {
+                    info,
+                    contents: contents.to_owned(),
+                });
+                Ok(())
             }
-            Entry::Occupied(ref mut entry) => {
-                entry.get_mut().extend_from_slice(contents);
+            Entry::Occupied(entry) => Err(Diagnostic::new(format!(
+                "`{name}` is generated by both {} and {}",
+                entry.get().info.producer,
+                info.producer
+            ))),
+        }
+    }
+
+    /// Appends `contents` to the file `name`, which must have already been
+    /// created with [`Files::create`].
+    pub fn append(&mut self, name: &str, contents: &[u8]) -> Result<(), Diagnostic> {
+        match self.files.get_mut(name) {
+            Some(file) => {
+                file.contents.extend_from_slice(contents);
+                Ok(())
             }
+            None => Err(Diagnostic::new(format!(
+                "cannot append to `{name}` as it hasn't been created"
+            ))),
         }
     }
 
     pub fn get_size(&mut self, name: &str) -> Option<usize> {
-        match self.files.get(name) {
-            Some(data) => Some(data.len()),
-            None => None,
-        }
+        self.files.get(name).map(|file| file.contents.len())
+    }
+
+    /// Returns the metadata of the file `name`.
+    pub fn info(&self, name: &str) -> Option<&FileInfo> {
+        self.files.get(name).map(|file| &file.info)
     }
 
     pub fn remove(&mut self, name: &str) -> Option<Vec<u8>> {
-        return self.files.remove(name);
+        self.files.remove(name).map(|file| file.contents)
     }
 
     pub fn iter(&self) -> impl Iterator<Item = (&'_ str, &'_ [u8])> {
-        self.files.iter().map(|p| (p.0.as_str(), p.1.as_slice()))
+        self.files
+            .iter()
+            .map(|(name, file)| (name.as_str(), file.contents.as_slice()))
     }
 }
 
+/// A buffer of generated source code.
+///
+/// Text pushed into a `Source` is laid out as it's pushed according to the
+/// [`Style`] of the buffer, so generators don't need to track indentation
+/// themselves.
 #[derive(Default)]
 pub struct Source {
     s: String,
-    indent: usize,
+    layout: format::Layout,
 }
 
 impl Source {
-    pub fn push_str(&mut self, src: &str) {
-        let lines = src.lines().collect::<Vec<_>>();
-        for (i, line) in lines.iter().enumerate() {
-            let trimmed = line.trim();
-            if trimmed.starts_with('}') && self.s.ends_with("  ") {
-                self.s.pop();
-                self.s.pop();
-            }
-            self.s.push_str(if lines.len() == 1 {
-                line
-            } else {
-                line.trim_start()
-            });
-            if trimmed.ends_with('{') {
-                self.indent += 1;
-            }
-            if trimmed.starts_with('}') {
-                // Note that a `saturating_sub` is used here to prevent a panic
-                // here in the case of invalid code being generated in debug
-                // mode. It's typically easier to debug those issues through
-                // looking at the source code rather than getting a panic.
-                self.indent = self.indent.saturating_sub(1);
-            }
-            if i != lines.len() - 1 || src.ends_with('\n') {
-                self.newline();
-            }
+    pub fn new(style: Style) -> Source {
+        Source {
+            s: String::new(),
+            layout: format::Layout::new(style),
         }
     }
 
-    pub fn indent(&mut self, amt: usize) {
-        self.indent += amt;
+    pub fn push_str(&mut self, src: &str) {
+        self.layout.push_str(&mut self.s, src);
     }
 
-    pub fn deindent(&mut self, amt: usize) {
-        self.indent -= amt;
+    pub fn indent(&mut self, amt: usize) {
+        self.layout.indent(amt);
     }
 
-    fn newline(&mut self) {
-        self.s.push('\n');
-        for _ in 0..self.indent {
-            self.s.push_str("  ");
-        }
+    pub fn deindent(&mut self, amt: usize) {
+        self.layout.deindent(amt);
     }
 
     pub fn as_mut_string(&mut self) -> &mut String {
@@ -338,7 +555,8 @@ macro_rules! uwriteln {
 
 #[cfg(test)]
 mod tests {
-    use super::Source;
+    use super::{FileInfo, FileKind, Files, Source, Types};
+    use wit_parser::{Resolve, UnresolvedPackage};
 
     #[test]
     fn simple_append() {
@@ -352,7 +570,7 @@ mod tests {
         s.push_str(" a ");
         assert_eq!(s.s, "xyz  a ");
         s.push_str("\na");
-        assert_eq!(s.s, "xyz  a \na");
+        assert_eq!(s.s, "xyz  a\na");
     }
 
     #[test]
@@ -385,17 +603,115 @@ mod tests {
         );
         assert_eq!(s.s, "function() {\n  x\n}");
     }
+
+    #[test]
+    fn files() {
+        let mut files = Files::default();
+        files
+            .create("a.rs", FileInfo::text("one", "rust"), b"x")
+            .unwrap();
+        files.append("a.rs", b"y").unwrap();
+        assert_eq!(files.iter().collect::<Vec<_>>(), [("a.rs", &b"xy"[..])]);
+        assert_eq!(files.info("a.rs").unwrap().kind, FileKind::Text);
+
+        let err = files
+            .create("a.rs", FileInfo::binary("two"), b"z")
+            .unwrap_err();
+        assert_eq!(err.reason, "`a.rs` is generated by both one and two");
+        assert!(files.append("b.rs", b"z").is_err());
+    }
+
+    #[test]
+    fn type_info() {
+        let mut resolve = Resolve::default();
+        let pkg = UnresolvedPackage::parse(
+            "test.wit".as_ref(),
+            "
+                package my:test
+
+                interface i {
+                    record point { x: s32, y: s32 }
+                    type point-alias = point
+                    record sample { value: float32, at: u64 }
+                    record named { name: string, point: point }
+                    variant shape { dot(point), empty }
+                    record failure { code: u32 }
+                    type handle = future<u32>
+                    flags perms { read, write }
+                    record file { perms: perms }
+
+                    f: func(a: point-alias, b: sample, c: shape) -> result<named, failure>
+                    g: func() -> handle
+                }
+            ",
+        )
+        .unwrap();
+        resolve.push(pkg).unwrap();
+        let mut types = Types::default();
+        types.analyze(&resolve);
+
+        let iface = resolve.interfaces.iter().next().unwrap().1;
+        let info = |name: &str| types.get(iface.types[name]);
+
+        let point = info("point");
+        assert!(point.plain_old_data && point.fixed_size() && point.total_eq());
+        assert!(point.non_error && !point.error);
+        assert!(info("point-alias").plain_old_data);
+
+        let sample = info("sample");
+        assert!(sample.plain_old_data && sample.has_float && !sample.total_eq());
+
+        let named = info("named");
+        assert!(named.has_string && named.has_list && !named.fixed_size());
+        assert!(!named.plain_old_data && named.total_eq());
+
+        let shape = info("shape");
+        assert!(!shape.plain_old_data && shape.fixed_size());
+
+        let failure = info("failure");
+        assert!(failure.error && !failure.non_error);
+
+        let handle = info("handle");
+        assert!(handle.has_handle && !handle.fixed_size() && !handle.total_eq());
+        assert!(!point.recursive && !handle.recursive);
+
+        assert!(info("file").has_flags && !point.has_flags);
+    }
 }
 
 pub trait WorldGenerator {
-    fn generate(&mut self, resolve: &Resolve, id: WorldId, files: &mut Files) {
+    /// Generates bindings for the parts of the world `id` selected by
+    /// `filter`.
+    ///
+    /// Interfaces, functions and types which aren't selected are removed from
+    /// a copy of `resolve` before any of the methods below are called, so
+    /// generators don't need to do anything to support filtering.
+    fn generate(
+        &mut self,
+        resolve: &Resolve,
+        id: WorldId,
+        filter: &Filter,
+        files: &mut Files,
+    ) -> Result<(), Diagnostic> {
+        let filtered;
+        let resolve = if filter.is_empty() {
+            resolve
+        } else {
+            filtered = filter
+                .apply(resolve, id)
+                .map_err(|e| e.in_world(resolve, id))?;
+            &filtered
+        };
         let world = &resolve.worlds[id];
-        self.preprocess(resolve, id);
+        let in_world = |e: Diagnostic| e.in_world(resolve, id);
+        self.preprocess(resolve, id).map_err(in_world)?;
 
-        fn unwrap_name(key: &WorldKey) -> &str {
+        fn unwrap_name(key: &WorldKey) -> Result<&str, Diagnostic> {
             match key {
-                WorldKey::Name(name) => name,
-                WorldKey::Interface(_) => panic!("unexpected interface key"),
+                WorldKey::Name(name) => Ok(name),
+                WorldKey::Interface(_) => Err(Diagnostic::new(
+                    "unexpected interface key for a function or type in a world",
+                )),
             }
         }
 
@@ -403,16 +719,20 @@ pub trait WorldGenerator {
         let mut types = Vec::new();
         for (name, import) in world.imports.iter() {
             match import {
-                WorldItem::Function(f) => funcs.push((unwrap_name(name), f)),
-                WorldItem::Interface(id) => self.import_interface(resolve, name, *id, files),
-                WorldItem::Type(id) => types.push((unwrap_name(name), *id)),
+                WorldItem::Function(f) => funcs.push((unwrap_name(name).map_err(in_world)?, f)),
+                WorldItem::Interface(id) => self
+                    .import_interface(resolve, name, *id, files)
+                    .map_err(|e| in_world(e.in_interface(resolve, name)))?,
+                WorldItem::Type(id) => types.push((unwrap_name(name).map_err(in_world)?, *id)),
             }
         }
         if !types.is_empty() {
-            self.export_types(resolve, id, &types, files);
+            self.export_types(resolve, id, &types, files)
+                .map_err(in_world)?;
         }
         if !funcs.is_empty() {
-            self.import_funcs(resolve, id, &funcs, files);
+            self.import_funcs(resolve, id, &funcs, files)
+                .map_err(in_world)?;
         }
         funcs.clear();
 
@@ -425,23 +745,30 @@ pub trait WorldGenerator {
         let mut interfaces = Vec::new();
         for (name, export) in world.exports.iter() {
             match export {
-                WorldItem::Function(f) => funcs.push((unwrap_name(name), f)),
+                WorldItem::Function(f) => funcs.push((unwrap_name(name).map_err(in_world)?, f)),
                 WorldItem::Interface(id) => interfaces.push((name, id)),
-                WorldItem::Type(_) => unreachable!(),
+                WorldItem::Type(_) => {
+                    return Err(in_world(Diagnostic::new(
+                        "types cannot be exported from a world",
+                    )))
+                }
             }
         }
         if !funcs.is_empty() {
-            self.export_funcs(resolve, id, &funcs, files);
+            self.export_funcs(resolve, id, &funcs, files)
+                .map_err(in_world)?;
         }
         for (name, id) in interfaces {
-            self.export_interface(resolve, name, *id, files);
+            self.export_interface(resolve, name, *id, files)
+                .map_err(|e| in_world(e.in_interface(resolve, name)))?;
         }
-        self.finish(resolve, id, files);
+        self.finish(resolve, id, files).map_err(in_world)
     }
 
-    fn preprocess(&mut self, resolve: &Resolve, world: WorldId) {
+    fn preprocess(&mut self, resolve: &Resolve, world: WorldId) -> Result<(), Diagnostic> {
         drop(resolve);
         drop(world);
+        Ok(())
     }
 
     fn import_interface(
@@ -450,36 +777,41 @@ pub trait WorldGenerator {
         name: &WorldKey,
         iface: InterfaceId,
         files: &mut Files,
-    );
+    ) -> Result<(), Diagnostic>;
     fn export_interface(
         &mut self,
         resolve: &Resolve,
         name: &WorldKey,
         iface: InterfaceId,
         files: &mut Files,
-    );
+    ) -> Result<(), Diagnostic>;
     fn import_funcs(
         &mut self,
         resolve: &Resolve,
         world: WorldId,
         funcs: &[(&str, &Function)],
         files: &mut Files,
-    );
+    ) -> Result<(), Diagnostic>;
     fn export_funcs(
         &mut self,
         resolve: &Resolve,
         world: WorldId,
         funcs: &[(&str, &Function)],
         files: &mut Files,
-    );
+    ) -> Result<(), Diagnostic>;
     fn export_types(
         &mut self,
         resolve: &Resolve,
         world: WorldId,
         types: &[(&str, TypeId)],
         files: &mut Files,
-    );
-    fn finish(&mut self, resolve: &Resolve, world: WorldId, files: &mut Files);
+    ) -> Result<(), Diagnostic>;
+    fn finish(
+        &mut self,
+        resolve: &Resolve,
+        world: WorldId,
+        files: &mut Files,
+    ) -> Result<(), Diagnostic>;
 }
 
 /// This is a possible replacement for the `Generator` trait above, currently
@@ -505,15 +837,47 @@ pub trait InterfaceGenerator<'a> {
     fn type_list(&mut self, id: TypeId, name: &str, ty: &Type, docs: &Docs);
     fn type_builtin(&mut self, id: TypeId, name: &str, ty: &Type, docs: &Docs);
 
-    fn types(&mut self, iface: InterfaceId) {
+    /// Generates a definition for `future<payload>`.
+    ///
+    /// Not all generators support futures, so by default this returns an
+    /// error.
+    fn type_future(
+        &mut self,
+        id: TypeId,
+        name: &str,
+        payload: Option<&Type>,
+        docs: &Docs,
+    ) -> Result<(), Diagnostic> {
+        drop((name, payload, docs));
+        Err(Diagnostic::new("the `future` type is not supported yet").for_type(self.resolve(), id))
+    }
+
+    /// Generates a definition for `stream<element, end>`.
+    ///
+    /// Not all generators support streams, so by default this returns an
+    /// error.
+    fn type_stream(
+        &mut self,
+        id: TypeId,
+        name: &str,
+        stream: &Stream,
+        docs: &Docs,
+    ) -> Result<(), Diagnostic> {
+        drop((name, stream, docs));
+        Err(Diagnostic::new("the `stream` type is not supported yet").for_type(self.resolve(), id))
+    }
+
+    fn types(&mut self, iface: InterfaceId) -> Result<(), Diagnostic> {
         let iface = &self.resolve().interfaces[iface];
         for (name, id) in iface.types.iter() {
-            self.define_type(name, *id);
+            self.define_type(name, *id)?;
         }
+        Ok(())
     }
 
-    fn define_type(&mut self, name: &str, id: TypeId) {
-        let ty = &self.resolve().types[id];
+    fn define_type(&mut self, name: &str, id: TypeId) -> Result<(), Diagnostic> {
+        let resolve = self.resolve();
+        let ty = &resolve.types[id];
         match &ty.kind {
             TypeDefKind::Record(record) => self.type_record(id, name, record, &ty.docs),
             TypeDefKind::Flags(flags) => self.type_flags(id, name, flags, &ty.docs),
@@ -525,9 +889,10 @@ pub trait InterfaceGenerator<'a> {
             TypeDefKind::Union(u) => self.type_union(id, name, u, &ty.docs),
             TypeDefKind::List(t) => self.type_list(id, name, t, &ty.docs),
             TypeDefKind::Type(t) => self.type_alias(id, name, t, &ty.docs),
-            TypeDefKind::Future(_) => todo!("generate for future"),
-            TypeDefKind::Stream(_) => todo!("generate for stream"),
+            TypeDefKind::Future(t) => return self.type_future(id, name, t.as_ref(), &ty.docs),
+            TypeDefKind::Stream(s) => return self.type_stream(id, name, s, &ty.docs),
             TypeDefKind::Unknown => unreachable!(),
         }
+        Ok(())
     }
 }
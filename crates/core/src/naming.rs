@@ -0,0 +1,875 @@
+//! Conversion of WIT names to identifiers in a target language.
+//!
+//! Each generator describes its language with a `Profile`: its keywords,
+//! prefixes it reserves for its own helpers, and the case convention used for
+//! each kind of item. `Names` then assigns an identifier to every interface,
+//! type, function, field and case in a world, guaranteeing that no two items
+//! in the same scope end up with the same identifier.
+//!
+//! Languages without modules, like C and Go, put every type and function of a
+//! world in one namespace and qualify them with the name of their interface
+//! instead, for example `a_b_iface_point_t`. Their profiles describe that with
+//! `Qualify`, and `Names` then checks identifiers for collisions in their
+//! qualified form.
+//!
+//! Individual identifiers can be overridden with `Renames`, which maps the
+//! path of an item to the identifier to use for it. Paths are written the
+//! same way as in WIT itself:
+//!
+//! * `a:b/iface` or `name` for an interface imported or exported by a world.
+//! * `a:b/iface#item` for a type or function in an interface, or just `item`
+//!   for one defined in a world.
+//! * `a:b/iface#ty.member` or `ty.member` for a field of a record or a case of
+//!   a variant, enum, flags or union. Union cases are named by their index.
+
+use crate::Diagnostic;
+use heck::*;
+use std::collections::{HashMap, HashSet};
+use wit_parser::*;
+
+/// A case convention for identifiers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Casing {
+    /// `snake_case`
+    Snake,
+    /// `SHOUTY_SNAKE_CASE`
+    ShoutySnake,
+    /// `UpperCamelCase`
+    UpperCamel,
+    /// `lowerCamelCase`
+    LowerCamel,
+}
+
+impl Casing {
+    /// Converts the WIT name `name` to this case.
+    pub fn convert(&self, name: &str) -> String {
+        match self {
+            Casing::Snake => name.to_snake_case(),
+            Casing::ShoutySnake => name.to_shouty_snake_case(),
+            Casing::UpperCamel => name.to_upper_camel_case(),
+            Casing::LowerCamel => name.to_lower_camel_case(),
+        }
+    }
+}
+
+/// A description of how identifiers are formed in a target language.
+#[derive(Debug, Clone, Copy)]
+pub struct Profile {
+    /// Words which can't be used as identifiers.
+    pub keywords: &'static [&'static str],
+    /// Prefixes of identifiers the generator uses for its own helpers.
+    pub reserved_prefixes: &'static [&'static str],
+    /// The case used for interface modules.
+    pub modules: Casing,
+    /// The case used for type names.
+    pub types: Casing,
+    /// The case used for function names.
+    pub functions: Casing,
+    /// The case used for record fields.
+    pub fields: Casing,
+    /// The case used for cases of variants, enums and unions, and for flags.
+    pub cases: Casing,
+    /// How types and functions are qualified, if the language puts all of
+    /// them in one namespace rather than a module per interface.
+    pub qualify: Option<Qualify>,
+}
+
+/// How a language without modules qualifies the name of a type or function
+/// with the name of the interface or world it's defined in.
+///
+/// The qualifier is the module name of the interface. Those of interfaces
+/// identified by a package are formed from the whole path, for example
+/// `a_b_iface` for `a:b/iface`.
+#[derive(Debug, Clone, Copy)]
+pub struct Qualify {
+    /// Placed between the qualifier and the name of the item.
+    pub separator: &'static str,
+    /// Appended to the qualified names of types.
+    pub type_suffix: &'static str,
+}
+
+impl Profile {
+    pub const RUST: Profile = Profile {
+        // Source: https://doc.rust-lang.org/reference/keywords.html
+        keywords: &[
+            "as", "break", "const", "continue", "crate", "else", "enum", "extern", "false", "fn",
+            "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref",
+            "return", "self", "static", "struct", "super", "trait", "true", "type", "unsafe",
+            "use", "where", "while", "async", "await", "dyn", "abstract", "become", "box", "do",
+            "final", "macro", "override", "priv", "typeof", "unsized", "virtual", "yield", "try",
+        ],
+        reserved_prefixes: &[],
+        modules: Casing::Snake,
+        types: Casing::UpperCamel,
+        functions: Casing::Snake,
+        fields: Casing::Snake,
+        cases: Casing::UpperCamel,
+        qualify: None,
+    };
+
+    pub const C: Profile = Profile {
+        // Source: https://en.cppreference.com/w/c/keyword
+        keywords: &[
+            "auto", "else", "long", "switch", "break", "enum", "register", "typedef", "case",
+            "extern", "return", "union", "char", "float", "short", "unsigned", "const", "for",
+            "signed", "void", "continue", "goto", "sizeof", "volatile", "default", "if", "static",
+            "while", "do", "int", "struct", "_Packed", "double",
+        ],
+        reserved_prefixes: &["cabi_"],
+        modules: Casing::Snake,
+        types: Casing::Snake,
+        functions: Casing::Snake,
+        fields: Casing::Snake,
+        cases: Casing::ShoutySnake,
+        qualify: Some(Qualify {
+            separator: "_",
+            type_suffix: "_t",
+        }),
+    };
+
+    pub const GO: Profile = Profile {
+        // Source: https://go.dev/ref/spec#Keywords
+        keywords: &[
+            "break",
+            "default",
+            "func",
+            "interface",
+            "select",
+            "case",
+            "defer",
+            "go",
+            "map",
+            "struct",
+            "chan",
+            "else",
+            "goto",
+            "package",
+            "switch",
+            "const",
+            "fallthrough",
+            "if",
+            "range",
+            "type",
+            "continue",
+            "for",
+            "import",
+            "return",
+            "var",
+        ],
+        reserved_prefixes: &[],
+        modules: Casing::UpperCamel,
+        types: Casing::UpperCamel,
+        functions: Casing::UpperCamel,
+        fields: Casing::UpperCamel,
+        cases: Casing::UpperCamel,
+        qualify: Some(Qualify {
+            separator: "",
+            type_suffix: "",
+        }),
+    };
+
+    pub const JAVA: Profile = Profile {
+        // Source: https://docs.oracle.com/javase/tutorial/java/nutsandbolts/_keywords.html
+        keywords: &[
+            "abstract",
+            "continue",
+            "for",
+            "new",
+            "switch",
+            "assert",
+            "default",
+            "goto",
+            "package",
+            "synchronized",
+            "boolean",
+            "do",
+            "if",
+            "private",
+            "this",
+            "break",
+            "double",
+            "implements",
+            "protected",
+            "throw",
+            "byte",
+            "else",
+            "import",
+            "public",
+            "throws",
+            "case",
+            "enum",
+            "instanceof",
+            "return",
+            "transient",
+            "catch",
+            "extends",
+            "int",
+            "short",
+            "try",
+            "char",
+            "final",
+            "interface",
+            "static",
+            "void",
+            "class",
+            "finally",
+            "long",
+            "strictfp",
+            "volatile",
+            "const",
+            "float",
+            "native",
+            "super",
+            "while",
+        ],
+        reserved_prefixes: &[],
+        modules: Casing::UpperCamel,
+        types: Casing::UpperCamel,
+        functions: Casing::LowerCamel,
+        fields: Casing::LowerCamel,
+        cases: Casing::ShoutySnake,
+        qualify: None,
+    };
+
+    /// Returns whether `ident` can't be used as-is because it's a keyword or
+    /// starts with a reserved prefix.
+    pub fn is_reserved(&self, ident: &str) -> bool {
+        self.keywords.contains(&ident)
+            || self.reserved_prefixes.iter().any(|p| ident.starts_with(p))
+    }
+
+    /// Escapes `ident` if it's reserved by appending an underscore.
+    pub fn escape(&self, ident: String) -> String {
+        if self.is_reserved(&ident) {
+            ident + "_"
+        } else {
+            ident
+        }
+    }
+
+    /// Converts the WIT name `name` to an identifier in `case`, escaping it
+    /// if necessary.
+    pub fn ident(&self, name: &str, case: Casing) -> String {
+        self.escape(case.convert(name))
+    }
+}
+
+/// A user-provided map from item paths to the identifiers to use for them.
+#[derive(Debug, Clone, Default)]
+pub struct Renames {
+    map: HashMap<String, String>,
+}
+
+impl Renames {
+    /// Parses renames written as `path=identifier`.
+    pub fn parse<'a>(specs: impl IntoIterator<Item = &'a String>) -> Result<Renames, Diagnostic> {
+        let mut renames = Renames::default();
+        for spec in specs {
+            let (path, ident) = spec.split_once('=').ok_or_else(|| {
+                Diagnostic::new(format!(
+                    "invalid rename `{spec}`, expected `path=identifier`"
+                ))
+            })?;
+            renames.insert(path.trim(), ident.trim())?;
+        }
+        Ok(renames)
+    }
+
+    /// Renames the item at `path` to `ident`.
+    pub fn insert(&mut self, path: &str, ident: &str) -> Result<(), Diagnostic> {
+        if ident.is_empty() {
+            return Err(Diagnostic::new(format!("empty identifier for `{path}`")));
+        }
+        if self
+            .map
+            .insert(path.to_string(), ident.to_string())
+            .is_some()
+        {
+            return Err(Diagnostic::new(format!(
+                "`{path}` is renamed more than once"
+            )));
+        }
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+}
+
+/// Identifiers assigned to the items in a world.
+///
+/// Names are assigned up-front by `assign` in the order items appear in the
+/// world, so the first of two colliding items keeps its natural name and the
+/// second gets a numeric suffix. Lookups for items which weren't assigned,
+/// for example types from interfaces the world doesn't mention, fall back to
+/// converting the WIT name with the profile.
+///
+/// For profiles with `Qualify`, the names returned for types and functions
+/// are unqualified, and generators add the qualifier themselves.
+pub struct Names {
+    profile: Profile,
+    renames: Renames,
+    used_renames: HashSet<String>,
+    /// The identifiers used in each scope, along with the path of the item
+    /// using each one.
+    scopes: HashMap<Scope, HashMap<String, String>>,
+    collisions: Vec<Collision>,
+    modules: HashMap<(String, bool), String>,
+    qualifiers: HashMap<InterfaceId, String>,
+    types: HashMap<TypeId, String>,
+    type_paths: HashMap<TypeId, String>,
+    functions: HashMap<(Option<InterfaceId>, String), String>,
+    members: HashMap<(TypeId, usize), String>,
+}
+
+/// Two items which map to the same identifier in a target language.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Collision {
+    /// The path of the item which was given a numeric suffix.
+    pub path: String,
+    /// The path of the item which kept the identifier.
+    pub other: String,
+    /// The identifier both items map to.
+    pub ident: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Scope {
+    /// Interface modules at the root of the imports or exports of a world.
+    Modules(bool),
+    /// Interface modules within a package.
+    Package(PackageId, bool),
+    /// Types and functions defined directly in the world, and with
+    /// `Qualify` those of every interface as well.
+    World,
+    /// Types and functions in an interface.
+    Interface(InterfaceId),
+    /// Fields or cases of a type.
+    Members(TypeId),
+}
+
+impl Default for Names {
+    fn default() -> Names {
+        Names::new(Profile::RUST, Renames::default())
+    }
+}
+
+impl Names {
+    pub fn new(profile: Profile, renames: Renames) -> Names {
+        Names {
+            profile,
+            renames,
+            used_renames: HashSet::new(),
+            scopes: HashMap::new(),
+            collisions: Vec::new(),
+            modules: HashMap::new(),
+            qualifiers: HashMap::new(),
+            types: HashMap::new(),
+            type_paths: HashMap::new(),
+            functions: HashMap::new(),
+            members: HashMap::new(),
+        }
+    }
+
+    pub fn profile(&self) -> &Profile {
+        &self.profile
+    }
+
+    /// Assigns identifiers to everything imported or exported by `world`.
+    ///
+    /// Returns an error if a rename collides with another identifier or if a
+    /// rename doesn't match any item.
+    pub fn assign(&mut self, resolve: &Resolve, world: WorldId) -> Result<(), Diagnostic> {
+        let world = &resolve.worlds[world];
+
+        // Interface modules at the root share their namespace with the
+        // modules for package namespaces, so reserve those first. Qualified
+        // module names already include the package namespace.
+        for import in [true, false] {
+            let items = if import {
+                &world.imports
+            } else {
+                &world.exports
+            };
+            for (key, _) in items.iter() {
+                if let (WorldKey::Interface(id), None) = (key, self.profile.qualify) {
+                    if let Some(pkg) = resolve.interfaces[*id].package {
+                        let ns = &resolve.packages[pkg].name.namespace;
+                        let ident = self.profile.ident(ns, self.profile.modules);
+                        self.scope(Scope::Modules(import))
+                            .insert(ident, format!("{ns}:"));
+                    }
+                }
+            }
+        }
+
+        let world_qualifier = self.profile.ident(&world.name, self.profile.modules);
+        let mut done = HashSet::new();
+        for (import, items) in [(true, &world.imports), (false, &world.exports)] {
+            for (key, item) in items.iter() {
+                match item {
+                    WorldItem::Interface(id) => {
+                        self.assign_module(resolve, key, *id, import)?;
+                        if done.insert(*id) {
+                            self.assign_interface(resolve, key, *id, import)?;
+                        }
+                    }
+                    WorldItem::Function(func) => {
+                        let path = resolve.name_world_key(key);
+                        self.assign_function(Scope::World, &world_qualifier, None, func, &path)?;
+                    }
+                    WorldItem::Type(id) => {
+                        let path = resolve.name_world_key(key);
+                        self.assign_type(resolve, Scope::World, &world_qualifier, *id, &path)?;
+                    }
+                }
+            }
+        }
+
+        let mut unused = self
+            .renames
+            .map
+            .keys()
+            .filter(|k| !self.used_renames.contains(*k))
+            .collect::<Vec<_>>();
+        unused.sort();
+        if let Some(path) = unused.first() {
+            return Err(Diagnostic::new(format!(
+                "rename of `{path}` doesn't match any item in the world"
+            )));
+        }
+        Ok(())
+    }
+
+    fn assign_module(
+        &mut self,
+        resolve: &Resolve,
+        key: &WorldKey,
+        id: InterfaceId,
+        import: bool,
+    ) -> Result<(), Diagnostic> {
+        let path = resolve.name_world_key(key);
+        let name = self.module_name(resolve, key);
+        let scope = match (key, resolve.interfaces[id].package) {
+            (WorldKey::Interface(_), Some(pkg)) if self.profile.qualify.is_none() => {
+                Scope::Package(pkg, import)
+            }
+            _ => Scope::Modules(import),
+        };
+        let ident = self.ident(scope, &path, &name, self.profile.modules, &|i| {
+            i.to_string()
+        })?;
+        self.modules.insert((path, import), ident);
+        Ok(())
+    }
+
+    /// Returns the WIT name the module of the interface `key` is named after.
+    fn module_name(&self, resolve: &Resolve, key: &WorldKey) -> String {
+        let id = match key {
+            WorldKey::Name(name) => return name.clone(),
+            WorldKey::Interface(id) => *id,
+        };
+        let iface = &resolve.interfaces[id];
+        let name = iface.name.as_deref().unwrap_or("");
+        match iface.package {
+            Some(pkg) if self.profile.qualify.is_some() => {
+                let pkg = &resolve.packages[pkg].name;
+                format!("{}-{}-{name}", pkg.namespace, pkg.name)
+            }
+            _ => name.to_string(),
+        }
+    }
+
+    fn assign_interface(
+        &mut self,
+        resolve: &Resolve,
+        key: &WorldKey,
+        id: InterfaceId,
+        import: bool,
+    ) -> Result<(), Diagnostic> {
+        let iface_path = resolve.name_world_key(key);
+        let qualifier = self.modules[&(iface_path.clone(), import)].clone();
+        let scope = match self.profile.qualify {
+            Some(_) => Scope::World,
+            None => Scope::Interface(id),
+        };
+        let iface = &resolve.interfaces[id];
+        for (name, ty) in iface.types.iter() {
+            let path = format!("{iface_path}#{name}");
+            self.assign_type(resolve, scope.clone(), &qualifier, *ty, &path)?;
+        }
+        for (name, func) in iface.functions.iter() {
+            let path = format!("{iface_path}#{name}");
+            self.assign_function(scope.clone(), &qualifier, Some(id), func, &path)?;
+        }
+        self.qualifiers.insert(id, qualifier);
+        Ok(())
+    }
+
+    fn assign_type(
+        &mut self,
+        resolve: &Resolve,
+        scope: Scope,
+        qualifier: &str,
+        id: TypeId,
+        path: &str,
+    ) -> Result<(), Diagnostic> {
+        let ty = &resolve.types[id];
+        let name = match &ty.name {
+            Some(name) => name,
+            None => return Ok(()),
+        };
+        let qualify = self.profile.qualify;
+        let key = |ident: &str| match qualify {
+            Some(q) => format!("{qualifier}{}{ident}{}", q.separator, q.type_suffix),
+            None => ident.to_string(),
+        };
+        let ident = self.ident(scope, path, name, self.profile.types, &key)?;
+        self.types.insert(id, ident);
+        self.type_paths.insert(id, path.to_string());
+
+        let members: Vec<String> = match &ty.kind {
+            TypeDefKind::Record(r) => r.fields.iter().map(|f| f.name.clone()).collect(),
+            TypeDefKind::Variant(v) => v.cases.iter().map(|c| c.name.clone()).collect(),
+            TypeDefKind::Enum(e) => e.cases.iter().map(|c| c.name.clone()).collect(),
+            TypeDefKind::Flags(f) => f.flags.iter().map(|f| f.name.clone()).collect(),
+            TypeDefKind::Union(u) => (0..u.cases.len()).map(|i| i.to_string()).collect(),
+            _ => Vec::new(),
+        };
+        let case = match &ty.kind {
+            TypeDefKind::Record(_) => self.profile.fields,
+            _ => self.profile.cases,
+        };
+        for (i, member) in members.iter().enumerate() {
+            let path = format!("{path}.{member}");
+            let ident = self.ident(Scope::Members(id), &path, member, case, &|i| i.to_string())?;
+            self.members.insert((id, i), ident);
+        }
+        Ok(())
+    }
+
+    fn assign_function(
+        &mut self,
+        scope: Scope,
+        qualifier: &str,
+        interface: Option<InterfaceId>,
+        func: &Function,
+        path: &str,
+    ) -> Result<(), Diagnostic> {
+        let qualify = self.profile.qualify;
+        let key = |ident: &str| match qualify {
+            Some(q) => format!("{qualifier}{}{ident}", q.separator),
+            None => ident.to_string(),
+        };
+        let ident = self.ident(scope, path, &func.name, self.profile.functions, &key)?;
+        self.functions.insert((interface, func.name.clone()), ident);
+        Ok(())
+    }
+
+    /// Picks an identifier for the item at `path` named `name` which is
+    /// unique within `scope`, once `key` turns it into the form it takes
+    /// there, such as its qualified name.
+    fn ident(
+        &mut self,
+        scope: Scope,
+        path: &str,
+        name: &str,
+        case: Casing,
+        key: &dyn Fn(&str) -> String,
+    ) -> Result<String, Diagnostic> {
+        if let Some(ident) = self.renames.map.get(path) {
+            let ident = ident.clone();
+            self.used_renames.insert(path.to_string());
+            if self.scope(scope.clone()).contains_key(&key(&ident)) {
+                return Err(Diagnostic::new(format!(
+                    "`{path}` is renamed to `{ident}` which is already used"
+                )));
+            }
+            self.scope(scope).insert(key(&ident), path.to_string());
+            return Ok(ident);
+        }
+        let base = self.profile.ident(name, case);
+        let used = self.scope(scope.clone());
+        let mut ident = base.clone();
+        let mut n = 1;
+        while used.contains_key(&key(&ident)) {
+            n += 1;
+            ident = format!("{base}{n}");
+        }
+        if n > 1 {
+            let collision = Collision {
+                path: path.to_string(),
+                other: used[&key(&base)].clone(),
+                ident: key(&base),
+            };
+            self.collisions.push(collision);
+        }
+        self.scope(scope).insert(key(&ident), path.to_string());
+        Ok(ident)
+    }
+
+    fn scope(&mut self, scope: Scope) -> &mut HashMap<String, String> {
+        self.scopes.entry(scope).or_default()
+    }
+
+    /// Returns the items assigned by `assign` whose natural identifier was
+    /// already taken, and which were given a numeric suffix instead.
+    pub fn collisions(&self) -> &[Collision] {
+        &self.collisions
+    }
+
+    /// Returns the module name for the interface imported or exported as
+    /// `key`.
+    pub fn module(&self, resolve: &Resolve, key: &WorldKey, import: bool) -> String {
+        let path = resolve.name_world_key(key);
+        if let Some(ident) = self.modules.get(&(path, import)) {
+            return ident.clone();
+        }
+        let name = self.module_name(resolve, key);
+        self.profile.ident(&name, self.profile.modules)
+    }
+
+    /// Returns the qualifier for the types and functions defined in `owner`,
+    /// for profiles with `Qualify`. This is the module name of an interface
+    /// or the name of a world, and empty for types without an owner.
+    pub fn qualifier(&self, resolve: &Resolve, owner: TypeOwner) -> String {
+        match owner {
+            TypeOwner::Interface(id) => match self.qualifiers.get(&id) {
+                Some(qualifier) => qualifier.clone(),
+                None => {
+                    let name = self.module_name(resolve, &WorldKey::Interface(id));
+                    self.profile.ident(&name, self.profile.modules)
+                }
+            },
+            TypeOwner::World(id) => self
+                .profile
+                .ident(&resolve.worlds[id].name, self.profile.modules),
+            TypeOwner::None => String::new(),
+        }
+    }
+
+    /// Returns the name of the type `id`, which must have a name in WIT.
+    pub fn type_name(&self, resolve: &Resolve, id: TypeId) -> String {
+        if let Some(ident) = self.types.get(&id) {
+            return ident.clone();
+        }
+        let name = resolve.types[id].name.as_deref().unwrap_or("");
+        self.profile.ident(name, self.profile.types)
+    }
+
+    /// Returns the path of the named type `id`, as used for renames, if it's
+    /// part of the world names were assigned for.
+    pub fn type_path(&self, id: TypeId) -> Option<&str> {
+        self.type_paths.get(&id).map(|s| s.as_str())
+    }
+
+    /// Returns the type at `path`, if it's part of the world names were
+    /// assigned for.
+    pub fn type_at(&self, path: &str) -> Option<TypeId> {
+        self.type_paths
+            .iter()
+            .find(|(_, p)| *p == path)
+            .map(|(id, _)| *id)
+    }
+
+    /// Returns the name of `func`, which is defined in `interface` or, if
+    /// that's `None`, directly in the world.
+    pub fn function(&self, interface: Option<InterfaceId>, func: &Function) -> String {
+        if let Some(ident) = self.functions.get(&(interface, func.name.clone())) {
+            return ident.clone();
+        }
+        self.profile.ident(&func.name, self.profile.functions)
+    }
+
+    /// Returns the name of the `index`th field of the record `id`, which is
+    /// named `name` in WIT.
+    pub fn field(&self, id: TypeId, index: usize, name: &str) -> String {
+        match self.members.get(&(id, index)) {
+            Some(ident) => ident.clone(),
+            None => self.profile.ident(name, self.profile.fields),
+        }
+    }
+
+    /// Returns the name of the `index`th case or flag of the type `id`,
+    /// which is named `name` in WIT.
+    pub fn case(&self, id: TypeId, index: usize, name: &str) -> String {
+        match self.members.get(&(id, index)) {
+            Some(ident) => ident.clone(),
+            None => self.profile.ident(name, self.profile.cases),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn world(src: &str) -> (Resolve, WorldId) {
+        let mut resolve = Resolve::default();
+        let pkg = UnresolvedPackage::parse("test.wit".as_ref(), src).unwrap();
+        let pkg = resolve.push(pkg).unwrap();
+        let world = resolve.select_world(pkg, Some("w")).unwrap();
+        (resolve, world)
+    }
+
+    fn type_id(resolve: &Resolve, name: &str) -> TypeId {
+        resolve
+            .types
+            .iter()
+            .find(|(_, t)| t.name.as_deref() == Some(name))
+            .unwrap()
+            .0
+    }
+
+    #[test]
+    fn profiles() {
+        assert_eq!(Profile::RUST.ident("type", Casing::Snake), "type_");
+        assert_eq!(Profile::RUST.ident("foo-bar", Casing::UpperCamel), "FooBar");
+        assert_eq!(
+            Profile::C.ident("cabi-realloc", Casing::Snake),
+            "cabi_realloc_"
+        );
+        assert_eq!(Profile::GO.ident("func", Casing::Snake), "func_");
+        assert_eq!(Profile::JAVA.ident("get-x", Casing::LowerCamel), "getX");
+    }
+
+    #[test]
+    fn collisions() {
+        let (resolve, world) = world(
+            "package a:b
+
+            interface i {
+                record point { x: u32 }
+                record coord { type: u32 }
+                f: func(x: point, y: coord)
+            }
+
+            world w {
+                import i
+            }",
+        );
+        let renames = Renames::parse(&["a:b/i#point=Coord".to_string()]).unwrap();
+        let mut names = Names::new(Profile::RUST, renames);
+        names.assign(&resolve, world).unwrap();
+
+        let point = type_id(&resolve, "point");
+        let coord = type_id(&resolve, "coord");
+        assert_eq!(names.type_name(&resolve, point), "Coord");
+        assert_eq!(names.type_name(&resolve, coord), "Coord2");
+        assert_eq!(
+            names.collisions(),
+            [Collision {
+                path: "a:b/i#coord".to_string(),
+                other: "a:b/i#point".to_string(),
+                ident: "Coord".to_string(),
+            }]
+        );
+        assert_eq!(names.field(point, 0, "x"), "x");
+        assert_eq!(names.field(coord, 0, "type"), "type_");
+    }
+
+    #[test]
+    fn qualified() {
+        let (resolve, world) = world(
+            "package a:b
+
+            world w {
+                import foo: interface {
+                    record bar-baz { x: u32 }
+                }
+                import foo-bar: interface {
+                    record baz { x: u32 }
+                }
+            }",
+        );
+        let bar_baz = type_id(&resolve, "bar-baz");
+        let baz = type_id(&resolve, "baz");
+        let owner = resolve.types[baz].owner;
+
+        let mut names = Names::new(Profile::GO, Renames::default());
+        names.assign(&resolve, world).unwrap();
+        assert_eq!(names.qualifier(&resolve, owner), "FooBar");
+        assert_eq!(names.type_name(&resolve, bar_baz), "BarBaz");
+        assert_eq!(names.type_name(&resolve, baz), "Baz2");
+        assert_eq!(
+            names.collisions(),
+            [Collision {
+                path: "foo-bar#baz".to_string(),
+                other: "foo#bar-baz".to_string(),
+                ident: "FooBarBaz".to_string(),
+            }]
+        );
+
+        let mut names = Names::new(Profile::C, Renames::default());
+        names.assign(&resolve, world).unwrap();
+        assert_eq!(names.qualifier(&resolve, owner), "foo_bar");
+        assert_eq!(names.collisions()[0].ident, "foo_bar_baz_t");
+
+        let mut names = Names::new(Profile::RUST, Renames::default());
+        names.assign(&resolve, world).unwrap();
+        assert!(names.collisions().is_empty());
+    }
+
+    #[test]
+    fn renames() {
+        let src = "package a:b
+
+            interface i {
+                record point { x: u32 }
+                get: func() -> point
+            }
+
+            world w {
+                import i
+                import run: func()
+            }";
+        let (resolve, world) = world(src);
+        let renames = Renames::parse(&[
+            "a:b/i#point=Coord".to_string(),
+            "a:b/i#point.x=horizontal".to_string(),
+            "a:b/i#get=fetch".to_string(),
+            "run=start".to_string(),
+            "a:b/i=things".to_string(),
+        ])
+        .unwrap();
+        let mut names = Names::new(Profile::RUST, renames);
+        names.assign(&resolve, world).unwrap();
+
+        let point = type_id(&resolve, "point");
+        let w = &resolve.worlds[world];
+        let (key, _) = w.imports.iter().next().unwrap();
+        let iface = resolve.interfaces.iter().next().unwrap().0;
+        assert_eq!(names.type_name(&resolve, point), "Coord");
+        assert_eq!(names.field(point, 0, "x"), "horizontal");
+        assert_eq!(names.type_path(point), Some("a:b/i#point"));
+        assert_eq!(names.type_at("a:b/i#point"), Some(point));
+        assert_eq!(names.module(&resolve, key, true), "things");
+        let get = &resolve.interfaces[iface].functions["get"];
+        assert_eq!(names.function(Some(iface), get), "fetch");
+        let run = w
+            .imports
+            .values()
+            .find_map(|i| match i {
+                WorldItem::Function(f) => Some(f),
+                _ => None,
+            })
+            .unwrap();
+        assert_eq!(names.function(None, run), "start");
+
+        let renames = Renames::parse(&["a:b/i#nope=x".to_string()]).unwrap();
+        let err = Names::new(Profile::RUST, renames)
+            .assign(&resolve, world)
+            .unwrap_err();
+        assert!(err.reason.contains("doesn't match"), "{err}");
+
+        let renames =
+            Renames::parse(&["a:b/i#point=Get".to_string(), "a:b/i#get=Get".to_string()]).unwrap();
+        let err = Names::new(Profile::RUST, renames)
+            .assign(&resolve, world)
+            .unwrap_err();
+        assert!(err.reason.contains("already used"), "{err}");
+
+        assert!(Renames::parse(&["nope".to_string()]).is_err());
+    }
+}
@@ -0,0 +1,217 @@
+//! Rendering of errors in WIT source along with the code they're about.
+//!
+//! `wit-parser` reports errors with a location as a message followed by the
+//! location and the offending line of source, all as text. `ErrorSpan`
+//! recovers the location from that text so it can be rendered consistently
+//! by the CLI, or mapped back to the tokens it came from by the macro.
+
+use anyhow::Error;
+use std::fmt::Write;
+use std::ops::Range;
+use std::path::PathBuf;
+
+/// The location in WIT source that an error was reported at.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ErrorSpan {
+    /// The error message, without the location.
+    pub message: String,
+    pub path: PathBuf,
+    /// The 1-based line.
+    pub line: usize,
+    /// The 1-based column, in bytes.
+    pub column: usize,
+    /// The length of the span in bytes, which is at least 1.
+    pub len: usize,
+    /// The line of source the span is on, if it was included in the error.
+    pub source: Option<String>,
+}
+
+impl ErrorSpan {
+    /// Finds the location of the first error in `err`'s chain which has one.
+    pub fn find(err: &Error) -> Option<ErrorSpan> {
+        err.chain().find_map(|e| ErrorSpan::parse(&e.to_string()))
+    }
+
+    /// Parses the location out of an error message, which looks like:
+    ///
+    /// ```text
+    /// expected `}`, found keyword `func`
+    ///      --> wit/world.wit:3:5
+    ///       |
+    ///     3 |     func
+    ///       |     ^---
+    /// ```
+    pub fn parse(msg: &str) -> Option<ErrorSpan> {
+        let mut lines = msg.lines();
+        let mut message = Vec::new();
+        let location = loop {
+            let line = lines.next()?;
+            match line.trim_start().strip_prefix("--> ") {
+                Some(location) => break location,
+                None => message.push(line),
+            }
+        };
+        let mut parts = location.rsplitn(3, ':');
+        let column = parts.next()?.parse().ok()?;
+        let line = parts.next()?.parse().ok()?;
+        let path = PathBuf::from(parts.next()?);
+
+        // The line of source has its number in the gutter, and is followed by
+        // the marker underlining the span.
+        let mut source = None;
+        let mut len = 1;
+        for l in lines {
+            let (gutter, text) = match l.split_once('|') {
+                Some(pair) => pair,
+                None => break,
+            };
+            let text = text.strip_prefix(' ').unwrap_or(text);
+            match gutter.trim().parse::<usize>() {
+                Ok(n) if n == line => source = Some(text.to_string()),
+                Ok(_) => break,
+                Err(_) if source.is_some() => {
+                    let marker = text.trim();
+                    if marker.starts_with('^') {
+                        len = marker.len();
+                    }
+                    break;
+                }
+                Err(_) => {}
+            }
+        }
+
+        Some(ErrorSpan {
+            message: message.join("\n").trim_end().to_string(),
+            path,
+            line,
+            column,
+            len,
+            source,
+        })
+    }
+
+    /// Returns the byte range of this span in `contents`, the source it was
+    /// reported in, if it's within it.
+    pub fn range(&self, contents: &str) -> Option<Range<usize>> {
+        let line = contents
+            .split_inclusive('\n')
+            .nth(self.line.checked_sub(1)?)?;
+        let line_start = line.as_ptr() as usize - contents.as_ptr() as usize;
+        let start = line_start + self.column.saturating_sub(1);
+        let end = (start + self.len).min(line_start + line.trim_end_matches('\n').len());
+        if start > end {
+            return None;
+        }
+        Some(start..end.max(start + 1).min(contents.len()))
+    }
+
+    /// Returns the location of this span along with the line of source it's
+    /// on, underlined, without the message.
+    pub fn snippet(&self) -> String {
+        let gutter = self.line.to_string().len();
+        let mut out = String::new();
+        writeln!(
+            out,
+            "{:gutter$}--> {}:{}:{}",
+            "",
+            self.path.display(),
+            self.line,
+            self.column
+        )
+        .unwrap();
+        if let Some(source) = &self.source {
+            // Anything but tabs before the span is replaced with a space so
+            // the marker lines up however tabs are displayed.
+            let indent = source
+                .get(..self.column.saturating_sub(1))
+                .unwrap_or(source)
+                .chars()
+                .map(|c| if c == '\t' { '\t' } else { ' ' })
+                .collect::<String>();
+            let underline = source
+                .get(self.column.saturating_sub(1)..)
+                .and_then(|rest| rest.get(..self.len.min(rest.len())))
+                .map(|s| s.chars().count())
+                .unwrap_or(1)
+                .max(1);
+            writeln!(out, "{:gutter$} |", "").unwrap();
+            writeln!(out, "{} | {}", self.line, source).unwrap();
+            writeln!(out, "{:gutter$} | {indent}{}", "", "^".repeat(underline)).unwrap();
+        }
+        out
+    }
+}
+
+/// Renders `err` for display to a user.
+///
+/// If an error in the chain has a location in WIT source it's rendered first
+/// along with the source it's about, and the rest of the chain follows as
+/// notes. Otherwise this is the same as `err`'s `Debug` rendering.
+pub fn render_error(err: &Error) -> String {
+    let mut span = None;
+    let mut notes = Vec::new();
+    for cause in err.chain() {
+        let msg = cause.to_string();
+        match ErrorSpan::parse(&msg) {
+            Some(s) if span.is_none() => span = Some(s),
+            _ => notes.push(msg),
+        }
+    }
+    let span = match span {
+        Some(span) => span,
+        None => return format!("{err:?}"),
+    };
+    let gutter = span.line.to_string().len();
+    let mut out = format!("{}\n{}", span.message, span.snippet());
+    if !notes.is_empty() {
+        writeln!(out, "{:gutter$} |", "").unwrap();
+    }
+    for note in notes {
+        writeln!(out, "{:gutter$} = note: {note}", "").unwrap();
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ERROR: &str = "expected `}`, found keyword `func`
+     --> wit/world.wit:3:5
+      |
+    3 | \tx: func
+      |    ^---";
+
+    #[test]
+    fn parse() {
+        let span = ErrorSpan::parse(ERROR).unwrap();
+        assert_eq!(span.message, "expected `}`, found keyword `func`");
+        assert_eq!(span.path, PathBuf::from("wit/world.wit"));
+        assert_eq!((span.line, span.column, span.len), (3, 5, 4));
+        assert_eq!(span.source.as_deref(), Some("\tx: func"));
+        assert_eq!(ErrorSpan::parse("no location here"), None);
+
+        let contents = "package a:b\nworld w {\n\tx: func\n}\n";
+        let range = span.range(contents).unwrap();
+        assert_eq!(&contents[range], "func");
+    }
+
+    #[test]
+    fn render() {
+        let err = Error::msg(ERROR).context("failed to parse package: wit");
+        assert_eq!(
+            render_error(&err),
+            "expected `}`, found keyword `func`
+ --> wit/world.wit:3:5
+  |
+3 | \tx: func
+  | \t   ^^^^
+  |
+  = note: failed to parse package: wit
+"
+        );
+
+        let err = Error::msg("no location").context("outer");
+        assert_eq!(render_error(&err), format!("{err:?}"));
+    }
+}
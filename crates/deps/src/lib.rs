@@ -0,0 +1,608 @@
+//! Resolution of a WIT package's dependencies on other packages which are
+//! available locally, such as in a sibling repository.
+//!
+//! Dependencies are listed by name in a `deps.toml` manifest next to the WIT
+//! package, each either a directory or file of WIT or a tarball of one,
+//! optionally gzipped:
+//!
+//! ```toml
+//! logging = { path = "../../logging/wit" }
+//! http = { tarball = "../vendor/http.tar.gz" }
+//! ```
+//!
+//! Paths are relative to the manifest. The SHA-256 checksum of each
+//! dependency is recorded in a `deps.lock` file alongside it. Only generating
+//! bindings updates it when dependencies change, while anything which just
+//! reads WIT leaves it alone, and any difference is an error when it's locked.
+//! Nothing is ever fetched over the network.
+
+use anyhow::{anyhow, bail, Context, Result};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use toml::value::{Table, Value};
+use wit_bindgen_core::wit_parser::{PackageId, Resolve, UnresolvedPackage};
+
+/// The name of the manifest listing dependencies.
+pub const MANIFEST: &str = "deps.toml";
+
+/// The name of the lockfile recording the checksum of each dependency.
+pub const LOCKFILE: &str = "deps.lock";
+
+/// Where a dependency is read from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    /// A directory containing a WIT package, or a single WIT file.
+    Path(PathBuf),
+    /// A tar archive containing a WIT package, which may be gzipped.
+    Tarball(PathBuf),
+}
+
+impl Source {
+    fn key(&self) -> &'static str {
+        match self {
+            Source::Path(_) => "path",
+            Source::Tarball(_) => "tarball",
+        }
+    }
+
+    fn path(&self) -> &Path {
+        match self {
+            Source::Path(path) | Source::Tarball(path) => path,
+        }
+    }
+}
+
+/// What happens when the lockfile doesn't match the dependencies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LockMode {
+    /// The lockfile is rewritten to match, when generating bindings.
+    Update,
+    /// The lockfile is left as it is, so that only reading WIT, such as to
+    /// check or lint it, never changes the source tree.
+    ReadOnly,
+    /// It's an error for the lockfile to be missing or out of date.
+    Locked,
+}
+
+/// An entry of the lockfile.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Locked {
+    source: Source,
+    sha256: String,
+}
+
+/// Pushes the package at `path`, a directory or a single WIT file, into
+/// `resolve` after the dependencies listed by the manifest next to it, if
+/// there is one.
+///
+/// Returns the package along with every file read, including the tarballs of
+/// dependencies. The lockfile is treated according to `mode`.
+pub fn push_path(
+    resolve: &mut Resolve,
+    path: &Path,
+    mode: LockMode,
+) -> Result<(PackageId, Vec<PathBuf>)> {
+    let dir = if path.is_dir() {
+        path
+    } else {
+        path.parent().unwrap_or(Path::new(""))
+    };
+    let mut sources = push_deps(resolve, dir, mode)?;
+    let pkg = if path.is_dir() {
+        let (pkg, files) = resolve.push_dir(path)?;
+        sources.extend(files);
+        pkg
+    } else {
+        let pkg = UnresolvedPackage::parse_file(path)?;
+        sources.push(path.to_path_buf());
+        resolve.push(pkg)?
+    };
+    check_versions(resolve)?;
+    Ok((pkg, sources))
+}
+
+/// Pushes the dependencies listed by the manifest in `dir` into `resolve`,
+/// each after any others it uses, returning every file read.
+///
+/// Nothing is done if there's no manifest.
+pub fn push_deps(resolve: &mut Resolve, dir: &Path, mode: LockMode) -> Result<Vec<PathBuf>> {
+    let manifest_path = dir.join(MANIFEST);
+    if !manifest_path.is_file() {
+        return Ok(Vec::new());
+    }
+    let manifest = read_manifest(&manifest_path)?;
+    let lock_path = dir.join(LOCKFILE);
+    let lock = if lock_path.is_file() {
+        read_lockfile(&lock_path)?
+    } else {
+        BTreeMap::new()
+    };
+
+    let mut sources = vec![manifest_path.clone()];
+    let mut new_lock = BTreeMap::new();
+    let mut deps = Vec::new();
+    for (name, source) in manifest.iter() {
+        let path = dir.join(source.path());
+        let sha256 = checksum(source, &path)
+            .with_context(|| format!("failed to read dependency `{name}` from {path:?}"))?;
+        let entry = Locked {
+            source: source.clone(),
+            sha256,
+        };
+        if mode == LockMode::Locked {
+            match lock.get(name) {
+                Some(prev) if *prev == entry => {}
+                Some(prev) if prev.source != entry.source => bail!(
+                    "dependency `{name}` has moved since {lock_path:?} was written, \
+                     and the lockfile can't be updated when it's locked"
+                ),
+                Some(_) => bail!(
+                    "the checksum of dependency `{name}` doesn't match the one in \
+                     {lock_path:?}, and the lockfile can't be updated when it's locked"
+                ),
+                None => bail!(
+                    "dependency `{name}` is missing from {lock_path:?}, and the \
+                     lockfile can't be updated when it's locked"
+                ),
+            }
+        }
+
+        let root = match source {
+            Source::Path(_) => path.clone(),
+            Source::Tarball(_) => unpack(&path, &entry.sha256)
+                .with_context(|| format!("failed to unpack dependency `{name}` from {path:?}"))?,
+        };
+        let pkg = if root.is_dir() {
+            UnresolvedPackage::parse_dir(&root)
+        } else {
+            UnresolvedPackage::parse_file(&root)
+        }
+        .with_context(|| format!("failed to parse dependency `{name}`"))?;
+        if let Source::Tarball(_) = source {
+            sources.push(path);
+        }
+        deps.push(Dep {
+            name: name.as_str(),
+            root,
+            pkg,
+        });
+        new_lock.insert(name.clone(), entry);
+    }
+
+    for i in order(&deps)? {
+        let files = push_dep(resolve, &deps[i])?;
+        if let Source::Path(_) = manifest[deps[i].name] {
+            sources.extend(files);
+        }
+    }
+
+    if mode == LockMode::Locked {
+        if let Some(name) = lock.keys().find(|name| !manifest.contains_key(*name)) {
+            bail!(
+                "dependency `{name}` in {lock_path:?} is no longer in {manifest_path:?}, \
+                 and the lockfile can't be updated when it's locked"
+            );
+        }
+    } else if mode == LockMode::Update && new_lock != lock {
+        std::fs::write(&lock_path, write_lockfile(&new_lock))
+            .with_context(|| format!("failed to write {lock_path:?}"))?;
+    }
+    Ok(sources)
+}
+
+/// A dependency listed in the manifest.
+struct Dep<'a> {
+    name: &'a str,
+    /// The directory or file of its package.
+    root: PathBuf,
+    pkg: UnresolvedPackage,
+}
+
+/// Returns the indices of `deps` in the order they need to be pushed, where
+/// each one comes after any others it uses.
+fn order(deps: &[Dep<'_>]) -> Result<Vec<usize>> {
+    fn visit(
+        deps: &[Dep<'_>],
+        i: usize,
+        visiting: &mut Vec<usize>,
+        order: &mut Vec<usize>,
+    ) -> Result<()> {
+        if order.contains(&i) {
+            return Ok(());
+        }
+        if let Some(pos) = visiting.iter().position(|j| *j == i) {
+            let cycle = visiting[pos..]
+                .iter()
+                .chain(Some(&i))
+                .map(|j| format!("`{}`", deps[*j].name))
+                .collect::<Vec<_>>();
+            bail!(
+                "dependencies use each other in a cycle: {}",
+                cycle.join(" -> ")
+            );
+        }
+        visiting.push(i);
+        for used in deps[i].pkg.foreign_deps.keys() {
+            let j = deps.iter().position(|d| {
+                d.pkg.name.namespace == used.namespace && d.pkg.name.name == used.name
+            });
+            if let Some(j) = j {
+                visit(deps, j, visiting, order)?;
+            }
+        }
+        visiting.pop();
+        order.push(i);
+        Ok(())
+    }
+
+    let mut order = Vec::new();
+    for i in 0..deps.len() {
+        visit(deps, i, &mut Vec::new(), &mut order)?;
+    }
+    Ok(order)
+}
+
+/// Pushes `dep` into `resolve`, returning the files read.
+///
+/// A package which has already been pushed, such as one which two
+/// dependencies share, is skipped, but it's an error for it to be at a
+/// different version.
+fn push_dep(resolve: &mut Resolve, dep: &Dep<'_>) -> Result<Vec<PathBuf>> {
+    let Dep { name, root, pkg } = dep;
+    let existing = resolve
+        .packages
+        .iter()
+        .find(|(_, p)| p.name.namespace == pkg.name.namespace && p.name.name == pkg.name.name);
+    if let Some((_, existing)) = existing {
+        if existing.name.version == pkg.name.version {
+            return Ok(Vec::new());
+        }
+        bail!(
+            "dependency `{name}` is package `{}`, which conflicts with `{}` \
+             required by another dependency",
+            pkg.name,
+            existing.name,
+        );
+    }
+
+    let files = if root.is_dir() {
+        resolve
+            .push_dir(root)
+            .with_context(|| format!("failed to resolve dependency `{name}`"))?
+            .1
+    } else {
+        resolve
+            .push(UnresolvedPackage::parse_file(root)?)
+            .with_context(|| format!("failed to resolve dependency `{name}`"))?;
+        vec![root.clone()]
+    };
+    Ok(files)
+}
+
+/// Fails if `resolve` contains more than one version of the same package.
+fn check_versions(resolve: &Resolve) -> Result<()> {
+    let mut seen = BTreeMap::new();
+    for (_, pkg) in resolve.packages.iter() {
+        let key = (pkg.name.namespace.as_str(), pkg.name.name.as_str());
+        if let Some(prev) = seen.insert(key, &pkg.name) {
+            if prev.version != pkg.name.version {
+                bail!(
+                    "conflicting versions of package `{}:{}` are required: `{prev}` and `{}`",
+                    key.0,
+                    key.1,
+                    pkg.name,
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+fn read_manifest(path: &Path) -> Result<BTreeMap<String, Source>> {
+    let table = read_table(path)?;
+    let mut manifest = BTreeMap::new();
+    for (name, value) in table {
+        let source = parse_source(&value)
+            .with_context(|| format!("invalid dependency `{name}` in {path:?}"))?;
+        manifest.insert(name, source);
+    }
+    Ok(manifest)
+}
+
+fn read_lockfile(path: &Path) -> Result<BTreeMap<String, Locked>> {
+    let table = read_table(path)?;
+    let mut lock = BTreeMap::new();
+    for (name, value) in table {
+        let entry =
+            parse_locked(&value).with_context(|| format!("invalid entry `{name}` in {path:?}"))?;
+        lock.insert(name, entry);
+    }
+    Ok(lock)
+}
+
+fn parse_locked(value: &Value) -> Result<Locked> {
+    let source = parse_source(value)?;
+    let sha256 = match value.get("sha256") {
+        Some(Value::String(s)) => s.clone(),
+        _ => bail!("missing `sha256`"),
+    };
+    Ok(Locked { source, sha256 })
+}
+
+fn read_table(path: &Path) -> Result<Table> {
+    let contents =
+        std::fs::read_to_string(path).with_context(|| format!("failed to read {path:?}"))?;
+    toml::from_str(&contents).with_context(|| format!("failed to parse {path:?}"))
+}
+
+/// Parses the `path` or `tarball` key of a dependency's table.
+fn parse_source(value: &Value) -> Result<Source> {
+    let table = match value {
+        Value::Table(table) => table,
+        _ => bail!("must be a table, such as `{{ path = \"../wit\" }}`"),
+    };
+    for key in table.keys() {
+        if !["path", "tarball", "sha256"].contains(&key.as_str()) {
+            bail!("unknown key `{key}`");
+        }
+    }
+    match (table.get("path"), table.get("tarball")) {
+        (Some(Value::String(path)), None) => Ok(Source::Path(path.into())),
+        (None, Some(Value::String(path))) => Ok(Source::Tarball(path.into())),
+        (Some(_), Some(_)) => bail!("only one of `path` and `tarball` may be given"),
+        (None, None) => bail!("either `path` or `tarball` must be given"),
+        _ => bail!("`path` and `tarball` must be strings"),
+    }
+}
+
+fn write_lockfile(lock: &BTreeMap<String, Locked>) -> String {
+    let mut contents = format!(
+        "# This file is written by `wit-bindgen` to record the checksum of each\n\
+         # dependency in `{MANIFEST}`. It's not meant to be edited by hand.\n"
+    );
+    for (name, entry) in lock {
+        let path = entry.source.path().to_string_lossy().replace('\\', "/");
+        contents.push('\n');
+        writeln!(contents, "[{}]", toml_key(name)).unwrap();
+        writeln!(contents, "{} = {}", entry.source.key(), Value::from(path)).unwrap();
+        writeln!(contents, "sha256 = \"{}\"", entry.sha256).unwrap();
+    }
+    contents
+}
+
+/// Returns `name` as a TOML key, quoting it unless it's a bare key.
+fn toml_key(name: &str) -> String {
+    let bare = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if bare {
+        name.to_string()
+    } else {
+        Value::from(name).to_string()
+    }
+}
+
+/// Returns the hex-encoded SHA-256 checksum of the dependency at `path`.
+///
+/// For a directory this covers the relative path and contents of every WIT
+/// file within it, so that it doesn't depend on where it is or on other
+/// files.
+fn checksum(source: &Source, path: &Path) -> Result<String> {
+    let mut hasher = Sha256::new();
+    match source {
+        Source::Path(_) if path.is_dir() => {
+            for file in wit_files(path)? {
+                let contents =
+                    std::fs::read(&file).with_context(|| format!("failed to read {file:?}"))?;
+                let relative = file.strip_prefix(path).unwrap();
+                let relative = relative.to_string_lossy().replace('\\', "/");
+                hasher.update((relative.len() as u64).to_le_bytes());
+                hasher.update(relative.as_bytes());
+                hasher.update((contents.len() as u64).to_le_bytes());
+                hasher.update(&contents);
+            }
+        }
+        Source::Path(_) | Source::Tarball(_) => {
+            let contents =
+                std::fs::read(path).with_context(|| format!("failed to read {path:?}"))?;
+            hasher.update(&contents);
+        }
+    }
+    let mut hex = String::new();
+    for byte in hasher.finalize() {
+        write!(hex, "{byte:02x}").unwrap();
+    }
+    Ok(hex)
+}
+
+/// Returns every `*.wit` file within `dir`, recursively, in sorted order.
+fn wit_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut entries = std::fs::read_dir(dir)
+        .with_context(|| format!("failed to read directory {dir:?}"))?
+        .map(|e| Ok(e?.path()))
+        .collect::<Result<Vec<_>>>()?;
+    entries.sort();
+    for path in entries {
+        if path.is_dir() {
+            files.extend(wit_files(&path)?);
+        } else if path.extension().and_then(|e| e.to_str()) == Some("wit") {
+            files.push(path);
+        }
+    }
+    Ok(files)
+}
+
+/// Unpacks the tarball at `path` with the checksum `sha256`, returning the
+/// directory of the WIT package within it.
+///
+/// Tarballs are unpacked into the system's temporary directory, named by
+/// their checksum so that they're only unpacked once.
+fn unpack(path: &Path, sha256: &str) -> Result<PathBuf> {
+    let dir = std::env::temp_dir().join("wit-bindgen-deps").join(sha256);
+    if !dir.is_dir() {
+        let bytes = std::fs::read(path).with_context(|| format!("failed to read {path:?}"))?;
+        // Unpack next to the final location first so that an interrupted
+        // unpack isn't mistaken for a complete one.
+        let tmp = dir.with_extension(format!("tmp{}", std::process::id()));
+        drop(std::fs::remove_dir_all(&tmp));
+        std::fs::create_dir_all(&tmp).with_context(|| format!("failed to create {tmp:?}"))?;
+        if bytes.starts_with(&[0x1f, 0x8b]) {
+            tar::Archive::new(flate2::read::GzDecoder::new(&bytes[..])).unpack(&tmp)?;
+        } else {
+            tar::Archive::new(&bytes[..]).unpack(&tmp)?;
+        }
+        if let Err(e) = std::fs::rename(&tmp, &dir) {
+            drop(std::fs::remove_dir_all(&tmp));
+            // Another process may have unpacked the same tarball meanwhile.
+            if !dir.is_dir() {
+                return Err(e).with_context(|| format!("failed to create {dir:?}"));
+            }
+        }
+    }
+
+    // Archives commonly wrap their contents in a single directory, so look
+    // through those to the package.
+    let mut root = dir;
+    loop {
+        let entries = std::fs::read_dir(&root)?
+            .map(|e| Ok(e?.path()))
+            .collect::<Result<Vec<_>>>()?;
+        let has_wit = entries
+            .iter()
+            .any(|p| p.extension().and_then(|e| e.to_str()) == Some("wit"));
+        match &entries[..] {
+            [only] if !has_wit && only.is_dir() => root = only.clone(),
+            _ if has_wit => return Ok(root),
+            _ => return Err(anyhow!("tarball doesn't contain a WIT package")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns an empty directory for the test `name`.
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir()
+            .join(format!("wit-bindgen-deps-test-{}", std::process::id()))
+            .join(name);
+        drop(std::fs::remove_dir_all(&dir));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn write(path: &Path, contents: &str) {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, contents).unwrap();
+    }
+
+    const ROOT: &str = "package a:root\n\nworld w {\n  import b:logging/log\n}\n";
+    const LOGGING: &str = "package b:logging\n\ninterface log {\n  log: func(msg: string)\n}\n";
+
+    #[test]
+    fn path() {
+        let dir = test_dir("path");
+        write(&dir.join("logging/wit/log.wit"), LOGGING);
+        write(&dir.join("root/wit/root.wit"), ROOT);
+        write(
+            &dir.join("root/wit/deps.toml"),
+            "logging = { path = \"../../logging/wit\" }\n",
+        );
+
+        let wit = dir.join("root/wit");
+        let mut resolve = Resolve::default();
+        let (pkg, sources) = push_path(&mut resolve, &wit, LockMode::Update).unwrap();
+        assert_eq!(resolve.packages[pkg].name.name, "root");
+        assert!(sources.iter().any(|p| p.ends_with("logging/wit/log.wit")));
+        let lock = std::fs::read_to_string(wit.join(LOCKFILE)).unwrap();
+        assert!(lock.contains("[logging]\npath = \"../../logging/wit\"\nsha256 = \""));
+
+        // The lockfile is up to date, so it can be used locked.
+        push_path(&mut Resolve::default(), &wit, LockMode::Locked).unwrap();
+
+        // Changes are an error when locked, are ignored when only reading,
+        // and update the lockfile otherwise.
+        write(
+            &dir.join("logging/wit/log.wit"),
+            &LOGGING.replace("msg", "message"),
+        );
+        let err = push_path(&mut Resolve::default(), &wit, LockMode::Locked).unwrap_err();
+        assert!(format!("{err:?}").contains("checksum of dependency `logging`"));
+        push_path(&mut Resolve::default(), &wit, LockMode::ReadOnly).unwrap();
+        assert_eq!(std::fs::read_to_string(wit.join(LOCKFILE)).unwrap(), lock);
+        push_path(&mut Resolve::default(), &wit, LockMode::Update).unwrap();
+        assert_ne!(std::fs::read_to_string(wit.join(LOCKFILE)).unwrap(), lock);
+        push_path(&mut Resolve::default(), &wit, LockMode::Locked).unwrap();
+    }
+
+    #[test]
+    fn tarball() {
+        let dir = test_dir("tarball");
+        let mut builder = tar::Builder::new(Vec::new());
+        let mut header = tar::Header::new_gnu();
+        header.set_size(LOGGING.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, "logging-1.0/log.wit", LOGGING.as_bytes())
+            .unwrap();
+        std::fs::write(dir.join("logging.tar"), builder.into_inner().unwrap()).unwrap();
+        write(&dir.join("root.wit"), ROOT);
+        write(
+            &dir.join("deps.toml"),
+            "logging = { tarball = \"logging.tar\" }\n",
+        );
+
+        let mut resolve = Resolve::default();
+        let (_, sources) =
+            push_path(&mut resolve, &dir.join("root.wit"), LockMode::Update).unwrap();
+        assert!(sources.contains(&dir.join("logging.tar")));
+        assert!(resolve
+            .packages
+            .iter()
+            .any(|(_, p)| p.name.name == "logging"));
+    }
+
+    #[test]
+    fn conflicts() {
+        let dir = test_dir("conflicts");
+        write(
+            &dir.join("v1/log.wit"),
+            &LOGGING.replace("b:logging", "b:logging@1.0.0"),
+        );
+        write(
+            &dir.join("v2/log.wit"),
+            &LOGGING.replace("b:logging", "b:logging@2.0.0"),
+        );
+        write(&dir.join("root.wit"), ROOT);
+        write(
+            &dir.join("deps.toml"),
+            "a = { path = \"v1\" }\nb = { path = \"v2\" }\n",
+        );
+
+        let err = push_path(
+            &mut Resolve::default(),
+            &dir.join("root.wit"),
+            LockMode::Update,
+        )
+        .unwrap_err();
+        assert!(format!("{err:?}").contains("conflicts with `b:logging@1.0.0`"));
+
+        write(
+            &dir.join("deps.toml"),
+            "a = { path = \"v1\", url = \"x\" }\n",
+        );
+        let err = push_path(
+            &mut Resolve::default(),
+            &dir.join("root.wit"),
+            LockMode::Update,
+        )
+        .unwrap_err();
+        assert!(format!("{err:?}").contains("unknown key `url`"));
+    }
+}
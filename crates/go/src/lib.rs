@@ -9,56 +9,39 @@ use wit_bindgen_c::{
 };
 use wit_bindgen_core::wit_parser::{InterfaceId, Resolve, TypeOwner, WorldId};
 use wit_bindgen_core::{
-    uwriteln,
+    check_types, reject_async_types, uwriteln,
     wit_parser::{Field, Function, SizeAlign, Type, TypeDefKind, TypeId, WorldKey},
-    Files, InterfaceGenerator as _, Source, WorldGenerator,
+    Diagnostic, FileInfo, Files, InterfaceGenerator as _, Names, Profile, Renames, Source, Style,
+    Types, WorldGenerator,
 };
 
-// a list of Go keywords
-const GOKEYWORDS: [&str; 25] = [
-    "break",
-    "default",
-    "func",
-    "interface",
-    "select",
-    "case",
-    "defer",
-    "go",
-    "map",
-    "struct",
-    "chan",
-    "else",
-    "goto",
-    "package",
-    "switch",
-    "const",
-    "fallthrough",
-    "if",
-    "range",
-    "type",
-    "continue",
-    "for",
-    "import",
-    "return",
-    "var",
-];
+const PRODUCER: &str = "wit-bindgen-go";
 
 fn avoid_keyword(s: &str) -> String {
-    if GOKEYWORDS.contains(&s) {
-        format!("{s}_")
-    } else {
-        s.into()
-    }
+    Profile::GO.escape(s.to_string())
 }
 
 #[derive(Default, Debug, Clone)]
 #[cfg_attr(feature = "clap", derive(clap::Args))]
-pub struct Opts {}
+pub struct Opts {
+    /// Whether or not to generate a stub implementation of the exported
+    /// functions, `{world}_stub.go`, in a `main` package which expects the
+    /// bindings in the `gen` directory of a module named after the world
+    #[cfg_attr(feature = "clap", arg(long))]
+    pub generate_stub: bool,
+    /// Overrides the Go identifier used for an item, in the form `PATH=NAME`,
+    /// for example `a:b/iface#ty.field=renamed`. Types and functions are
+    /// still prefixed with the name of their interface. May be specified
+    /// multiple times.
+    #[cfg_attr(feature = "clap", arg(long = "rename", value_name = "PATH=NAME"))]
+    pub rename: Vec<String>,
+}
 
 impl Opts {
     pub fn build(&self) -> Box<dyn WorldGenerator> {
         Box::new(TinyGo {
-            _opts: self.clone(),
+            opts: self.clone(),
+            src: Source::new(Style::GO),
             ..TinyGo::default()
         })
     }
@@ -66,14 +49,22 @@ impl Opts {
 
 #[derive(Default)]
 pub struct TinyGo {
-    _opts: Opts,
+    opts: Opts,
     src: Source,
     world: String,
     needs_result_option: bool,
     needs_import_unsafe: bool,
     needs_fmt_import: bool,
     sizes: SizeAlign,
+    types: Types,
     interface_names: HashMap<InterfaceId, WorldKey>,
+    names: Names,
+    // The names the C bindings, which are generated without renames, use.
+    c_names: Names,
+    // The methods of the stub implementation of the interface currently being
+    // generated, and the name and methods of each finished one.
+    stub: Source,
+    stub_interfaces: Vec<(String, Source)>,
 }
 
 impl TinyGo {
@@ -97,10 +88,17 @@ impl TinyGo {
 }
 
 impl WorldGenerator for TinyGo {
-    fn preprocess(&mut self, resolve: &Resolve, world: WorldId) {
+    fn preprocess(&mut self, resolve: &Resolve, world: WorldId) -> Result<(), Diagnostic> {
+        check_types(resolve, world, reject_async_types("TinyGo"))?;
+        self.names = Names::new(Profile::GO, Renames::parse(&self.opts.rename)?);
+        self.names.assign(resolve, world)?;
+        self.c_names = Names::new(Profile::C, Renames::default());
+        self.c_names.assign(resolve, world)?;
         let name = &resolve.worlds[world].name;
         self.world = name.to_string();
         self.sizes.fill(resolve);
+        self.types.analyze(resolve);
+        Ok(())
     }
 
     fn import_interface(
@@ -109,7 +107,7 @@ impl WorldGenerator for TinyGo {
         name: &WorldKey,
         id: InterfaceId,
         _files: &mut Files,
-    ) {
+    ) -> Result<(), Diagnostic> {
         let name_raw = &resolve.name_world_key(name);
         self.src
             .push_str(&format!("// Import functions from {name_raw}\n"));
@@ -118,7 +116,7 @@ impl WorldGenerator for TinyGo {
         let binding = Some(name);
         let mut gen = self.interface(resolve, &binding, true);
         gen.interface = Some(id);
-        gen.types(id);
+        gen.types(id)?;
 
         for (_name, func) in resolve.interfaces[id].functions.iter() {
             gen.import(resolve, func);
@@ -127,6 +125,7 @@ impl WorldGenerator for TinyGo {
 
         let src = mem::take(&mut gen.src);
         self.src.push_str(&src);
+        Ok(())
     }
 
     fn import_funcs(
@@ -135,7 +134,7 @@ impl WorldGenerator for TinyGo {
         world: WorldId,
         funcs: &[(&str, &Function)],
         _files: &mut Files,
-    ) {
+    ) -> Result<(), Diagnostic> {
         let name = &resolve.worlds[world].name;
         self.src
             .push_str(&format!("// Import functions from {name}\n"));
@@ -148,6 +147,7 @@ impl WorldGenerator for TinyGo {
 
         let src = mem::take(&mut gen.src);
         self.src.push_str(&src);
+        Ok(())
     }
 
     fn export_interface(
@@ -156,7 +156,7 @@ impl WorldGenerator for TinyGo {
         name: &WorldKey,
         id: InterfaceId,
         _files: &mut Files,
-    ) {
+    ) -> Result<(), Diagnostic> {
         self.interface_names.insert(id, name.clone());
         let name_raw = &resolve.name_world_key(name);
         self.src
@@ -165,7 +165,7 @@ impl WorldGenerator for TinyGo {
         let binding = Some(name);
         let mut gen = self.interface(resolve, &binding, false);
         gen.interface = Some(id);
-        gen.types(id);
+        gen.types(id)?;
 
         for (_name, func) in resolve.interfaces[id].functions.iter() {
             gen.export(resolve, func);
@@ -175,6 +175,7 @@ impl WorldGenerator for TinyGo {
 
         let src = mem::take(&mut gen.src);
         self.src.push_str(&src);
+        Ok(())
     }
 
     fn export_funcs(
@@ -183,7 +184,7 @@ impl WorldGenerator for TinyGo {
         world: WorldId,
         funcs: &[(&str, &Function)],
         _files: &mut Files,
-    ) {
+    ) -> Result<(), Diagnostic> {
         let name = &resolve.worlds[world].name;
         self.src
             .push_str(&format!("// Export functions from {name}\n"));
@@ -197,6 +198,7 @@ impl WorldGenerator for TinyGo {
 
         let src = mem::take(&mut gen.src);
         self.src.push_str(&src);
+        Ok(())
     }
 
     fn export_types(
@@ -205,19 +207,25 @@ impl WorldGenerator for TinyGo {
         _world: WorldId,
         types: &[(&str, TypeId)],
         _files: &mut Files,
-    ) {
+    ) -> Result<(), Diagnostic> {
         let mut gen = self.interface(resolve, &None, false);
         for (name, id) in types {
-            gen.define_type(name, *id);
+            gen.define_type(name, *id)?;
         }
         gen.finish();
         let src = mem::take(&mut gen.src);
         self.src.push_str(&src);
+        Ok(())
     }
 
-    fn finish(&mut self, resolve: &Resolve, id: WorldId, files: &mut Files) {
+    fn finish(
+        &mut self,
+        resolve: &Resolve,
+        id: WorldId,
+        files: &mut Files,
+    ) -> Result<(), Diagnostic> {
         let world = &resolve.worlds[id];
-        let mut header = Source::default();
+        let mut header = Source::new(Style::GO);
         let snake = self.world.to_snake_case();
         // add package
         header.push_str("package ");
@@ -237,17 +245,12 @@ impl WorldGenerator for TinyGo {
             header.push_str("import \"fmt\"\n\n");
         }
         let header = mem::take(&mut header);
-        let src = mem::take(&mut self.src);
-        files.push(
-            &format!("{}.go", world.name.to_kebab_case()),
-            header.as_bytes(),
-        );
-        files.push(
-            &format!("{}.go", world.name.to_kebab_case()),
-            src.as_bytes(),
-        );
+        let src = mem::replace(&mut self.src, Source::new(Style::GO));
+        let go_file = format!("{}.go", world.name.to_kebab_case());
+        files.create(&go_file, FileInfo::text(PRODUCER, "go"), header.as_bytes())?;
+        files.append(&go_file, src.as_bytes())?;
         if self.needs_result_option {
-            let mut result_option_src = Source::default();
+            let mut result_option_src = Source::new(Style::GO);
             uwriteln!(
                 result_option_src,
                 "package {snake}
@@ -359,14 +362,50 @@ impl WorldGenerator for TinyGo {
                 }}
                 "
             );
-            files.push(
+            files.create(
                 &format!("{}_types.go", world.name.to_kebab_case()),
+                FileInfo::text(PRODUCER, "go"),
                 result_option_src.as_bytes(),
-            );
+            )?;
+        }
+        if self.opts.generate_stub {
+            self.finish_stub(files)?;
         }
         let mut opts = wit_bindgen_c::Opts::default();
         opts.no_sig_flattening = true;
-        opts.build().generate(resolve, id, files)
+        opts.build()
+            .generate(resolve, id, &Default::default(), files)
+    }
+}
+
+impl TinyGo {
+    fn finish_stub(&mut self, files: &mut Files) -> Result<(), Diagnostic> {
+        let version = env!("CARGO_PKG_VERSION");
+        let snake = self.world.to_snake_case();
+        let mut src = Source::new(Style::GO);
+        uwriteln!(src, "// Generated by `wit-bindgen` {version}.");
+        uwriteln!(src, "package main\n");
+        if !self.stub_interfaces.is_empty() {
+            uwriteln!(src, "import (\n. \"{snake}/gen\"\n)\n");
+            uwriteln!(src, "func init() {{");
+            for (name, _) in self.stub_interfaces.iter() {
+                uwriteln!(src, "Set{name}({name}Impl{{}})");
+            }
+            uwriteln!(src, "}}\n");
+            // Each interface gets its own implementation type, as two of
+            // them may export functions with the same name.
+            for (name, methods) in self.stub_interfaces.iter() {
+                uwriteln!(src, "type {name}Impl struct{{}}\n");
+                src.push_str(methods);
+            }
+        }
+        uwriteln!(src, "func main() {{}}");
+        files.create(
+            &format!("{}_stub.go", self.world.to_kebab_case()),
+            FileInfo::text(PRODUCER, "go"),
+            src.as_bytes(),
+        )?;
+        Ok(())
     }
 }
 
@@ -382,30 +421,26 @@ struct InterfaceGenerator<'a> {
 }
 
 impl InterfaceGenerator<'_> {
-    fn get_func_or_type_name(&self, name: &str) -> String {
-        format!("{}{}", self.get_package_name(), name.to_upper_camel_case())
+    /// Prefixes `ident`, the name `Names` assigned to a type or function, with
+    /// the name of the package it's in.
+    fn get_func_or_type_name(&self, ident: &str) -> String {
+        format!("{}{}", self.get_package_name(), ident)
     }
 
-    fn get_c_func_name(&self, func_name: &str) -> String {
+    fn get_c_func_name(&self, func: &Function) -> String {
         let mut name = String::new();
         match self.name {
-            Some(WorldKey::Name(k)) => name.push_str(&k.to_snake_case()),
-            Some(WorldKey::Interface(id)) => {
-                if !self.in_import {
+            Some(key) => {
+                if let (WorldKey::Interface(_), false) = (key, self.in_import) {
                     name.push_str("exports_");
                 }
-                let iface = &self.resolve.interfaces[*id];
-                let pkg = &self.resolve.packages[iface.package.unwrap()];
-                name.push_str(&pkg.name.namespace.to_snake_case());
-                name.push('_');
-                name.push_str(&pkg.name.name.to_snake_case());
-                name.push('_');
-                name.push_str(&iface.name.as_ref().unwrap().to_snake_case());
+                let module = self.gen.c_names.module(self.resolve, key, self.in_import);
+                name.push_str(&module);
             }
             None => name.push_str(&self.gen.world.to_snake_case()),
         }
         name.push('_');
-        name.push_str(&func_name.to_snake_case());
+        name.push_str(&self.gen.c_names.function(self.interface, func));
         name
     }
 
@@ -418,38 +453,22 @@ impl InterfaceGenerator<'_> {
 
     fn get_package_name_with(&self, key: &WorldKey) -> String {
         let mut name = String::new();
-        match key {
-            WorldKey::Name(k) => name.push_str(&k.to_upper_camel_case()),
-            WorldKey::Interface(id) => {
-                if !self.in_import {
-                    name.push_str("Exports");
-                }
-                let iface = &self.resolve.interfaces[*id];
-                let pkg = &self.resolve.packages[iface.package.unwrap()];
-                name.push_str(&pkg.name.namespace.to_upper_camel_case());
-                name.push_str(&pkg.name.name.to_upper_camel_case());
-                name.push_str(&iface.name.as_ref().unwrap().to_upper_camel_case());
-            }
+        if let (WorldKey::Interface(_), false) = (key, self.in_import) {
+            name.push_str("Exports");
         }
+        name.push_str(&self.gen.names.module(self.resolve, key, self.in_import));
         name
     }
 
     fn get_interface_var_name(&self) -> String {
-        let mut name = String::new();
         match self.name {
-            Some(WorldKey::Name(k)) => name.push_str(&k.to_snake_case()),
-            Some(WorldKey::Interface(id)) => {
-                let iface = &self.resolve.interfaces[*id];
-                let pkg = &self.resolve.packages[iface.package.unwrap()];
-                name.push_str(&pkg.name.namespace.to_snake_case());
-                name.push('_');
-                name.push_str(&pkg.name.name.to_snake_case());
-                name.push('_');
-                name.push_str(&iface.name.as_ref().unwrap().to_snake_case());
-            }
-            None => name.push_str(&self.gen.world.to_snake_case()),
+            Some(key) => self
+                .gen
+                .names
+                .module(self.resolve, key, self.in_import)
+                .to_snake_case(),
+            None => self.gen.world.to_snake_case(),
         }
-        name
     }
 
     fn get_ty(&mut self, ty: &Type) -> String {
@@ -487,14 +506,15 @@ impl InterfaceGenerator<'_> {
                         )
                     }
                     _ => {
-                        if let Some(name) = &ty.name {
+                        if ty.name.is_some() {
                             let iface = if let TypeOwner::Interface(owner) = ty.owner {
                                 let key = &self.gen.interface_names[&owner];
                                 self.get_package_name_with(key)
                             } else {
                                 self.get_package_name()
                             };
-                            format!("{iface}{name}", name = name.to_upper_camel_case())
+                            let name = self.gen.names.type_name(self.resolve, *id);
+                            format!("{iface}{name}")
                         } else {
                             self.public_anonymous_types.insert(*id);
                             format!(
@@ -532,38 +552,17 @@ impl InterfaceGenerator<'_> {
             Type::Id(id) => {
                 let ty = &self.resolve.types[*id];
                 match &ty.name {
-                    Some(name) => match ty.owner {
-                        TypeOwner::Interface(owner) => {
-                            let key = &self.gen.interface_names[&owner];
-                            let mut ns = match &key {
-                                WorldKey::Name(name) => name.to_snake_case(),
-                                WorldKey::Interface(id) => {
-                                    let mut ns = String::new();
-                                    let iface = &self.resolve.interfaces[*id];
-                                    let pkg = &self.resolve.packages[iface.package.unwrap()];
-                                    ns.push_str(&pkg.name.namespace.to_snake_case());
-                                    ns.push('_');
-                                    ns.push_str(&pkg.name.name.to_snake_case());
-                                    ns.push('_');
-                                    ns.push_str(&iface.name.as_ref().unwrap().to_snake_case());
-                                    ns
-                                }
-                            };
-                            ns.push('_');
-                            ns.push_str(name.to_snake_case().as_str());
-                            ns.push_str("_t");
-                            ns
+                    Some(_) => match ty.owner {
+                        TypeOwner::None => {
+                            format!("{name}_t", name = self.gen.world.to_snake_case(),)
                         }
-                        TypeOwner::World(owner) => {
+                        owner => {
                             format!(
                                 "{namespace}_{name}_t",
-                                namespace = self.resolve.worlds[owner].name.to_snake_case(),
-                                name = name.to_snake_case(),
+                                namespace = self.gen.c_names.qualifier(self.resolve, owner),
+                                name = self.gen.c_names.type_name(self.resolve, *id),
                             )
                         }
-                        TypeOwner::None => {
-                            format!("{name}_t", name = self.gen.world.to_snake_case(),)
-                        }
                     },
                     None => match &ty.kind {
                         TypeDefKind::Type(t) => self.get_c_ty_without_package(t),
@@ -604,7 +603,7 @@ impl InterfaceGenerator<'_> {
             Type::String => "String".into(),
             Type::Id(id) => {
                 let ty = &self.resolve.types[*id];
-                if let Some(name) = &ty.name {
+                if ty.name.is_some() {
                     let prefix = match ty.owner {
                         TypeOwner::World(owner) => {
                             self.resolve.worlds[owner].name.to_upper_camel_case()
@@ -618,7 +617,7 @@ impl InterfaceGenerator<'_> {
                     return format!(
                         "{prefix}{name}",
                         prefix = prefix,
-                        name = name.to_upper_camel_case()
+                        name = self.gen.names.type_name(self.resolve, *id)
                     );
                 }
                 match &ty.kind {
@@ -707,8 +706,8 @@ impl InterfaceGenerator<'_> {
             Type::String => "string".into(),
             Type::Id(id) => {
                 let ty = &self.resolve.types[*id];
-                if let Some(name) = &ty.name {
-                    return name.to_snake_case();
+                if ty.name.is_some() {
+                    return self.gen.c_names.type_name(self.resolve, *id);
                 }
                 match &ty.kind {
                     TypeDefKind::Type(t) => self.get_c_ty_name(t),
@@ -920,9 +919,9 @@ impl InterfaceGenerator<'_> {
     ) -> String {
         let mut src = Source::default();
         let func_name = if in_import {
-            self.get_c_func_name(&func.name)
+            self.get_c_func_name(func)
         } else {
-            self.get_func_or_type_name(&func.name)
+            self.get_func_or_type_name(&self.gen.names.function(self.interface, func))
         };
 
         if !in_import {
@@ -951,7 +950,7 @@ impl InterfaceGenerator<'_> {
     fn get_func_signature_no_interface(&mut self, resolve: &Resolve, func: &Function) -> String {
         format!(
             "{}({}){}",
-            func.name.to_upper_camel_case(),
+            self.gen.names.function(self.interface, func),
             self.get_func_params(resolve, func),
             self.get_func_results(resolve, func)
         )
@@ -966,8 +965,8 @@ impl InterfaceGenerator<'_> {
         self.src.push_str("{\n");
     }
 
-    fn get_field_name(&mut self, field: &Field) -> String {
-        field.name.to_upper_camel_case()
+    fn get_field_name(&mut self, id: TypeId, index: usize, field: &Field) -> String {
+        self.gen.names.field(id, index, &field.name)
     }
 
     fn extract_result_ty(&self, ty: &Type) -> (Option<Type>, Option<Type>) {
@@ -1213,11 +1212,24 @@ impl InterfaceGenerator<'_> {
         let lower_src = func_bindgen.lower_src.to_string();
 
         let interface_method_decl = self.get_func_signature_no_interface(resolve, func);
+        if self.gen.opts.generate_stub {
+            // Functions without results get an empty body, the rest panic
+            // until they're implemented.
+            let body = match func.results.len() {
+                0 => "",
+                _ => "panic(\"unimplemented\")\n",
+            };
+            let interface_name = self.get_package_name();
+            uwriteln!(
+                self.gen.stub,
+                "func (i {interface_name}Impl) {interface_method_decl} {{\n{body}}}\n"
+            );
+        }
         let export_func = {
             let mut src = String::new();
             // header
             src.push_str("//export ");
-            let name = self.get_c_func_name(&func.name);
+            let name = self.get_c_func_name(func);
             src.push_str(&name);
             src.push('\n');
 
@@ -1241,7 +1253,7 @@ impl InterfaceGenerator<'_> {
             let invoke = format!(
                 "{}.{}({})",
                 &self.get_interface_var_name(),
-                &func.name.to_upper_camel_case(),
+                &self.gen.names.function(self.interface, func),
                 args.iter()
                     .enumerate()
                     .map(|(i, name)| format!(
@@ -1322,6 +1334,13 @@ impl InterfaceGenerator<'_> {
             for (_, export_func) in &self.export_funcs {
                 self.src.push_str(export_func);
             }
+
+            if self.gen.opts.generate_stub {
+                let methods = mem::take(&mut self.gen.stub);
+                self.gen
+                    .stub_interfaces
+                    .push((interface_name.clone(), methods));
+            }
         }
     }
 }
@@ -1333,16 +1352,16 @@ impl<'a> wit_bindgen_core::InterfaceGenerator<'a> for InterfaceGenerator<'a> {
 
     fn type_record(
         &mut self,
-        _id: wit_bindgen_core::wit_parser::TypeId,
-        name: &str,
+        id: wit_bindgen_core::wit_parser::TypeId,
+        _name: &str,
         record: &wit_bindgen_core::wit_parser::Record,
         _docs: &wit_bindgen_core::wit_parser::Docs,
     ) {
-        let name = self.get_func_or_type_name(name);
+        let name = self.get_func_or_type_name(&self.gen.names.type_name(self.resolve, id));
         self.src.push_str(&format!("type {name} struct {{\n",));
-        for field in record.fields.iter() {
+        for (i, field) in record.fields.iter().enumerate() {
             let ty = self.get_ty(&field.ty);
-            let name = self.get_field_name(field);
+            let name = self.get_field_name(id, i, field);
             self.src.push_str(&format!("   {name} {ty}\n",));
         }
         self.src.push_str("}\n\n");
@@ -1350,28 +1369,26 @@ impl<'a> wit_bindgen_core::InterfaceGenerator<'a> for InterfaceGenerator<'a> {
 
     fn type_flags(
         &mut self,
-        _id: wit_bindgen_core::wit_parser::TypeId,
-        name: &str,
+        id: wit_bindgen_core::wit_parser::TypeId,
+        _name: &str,
         flags: &wit_bindgen_core::wit_parser::Flags,
         _docs: &wit_bindgen_core::wit_parser::Docs,
     ) {
-        let name = self.get_func_or_type_name(name);
+        let name = self.get_func_or_type_name(&self.gen.names.type_name(self.resolve, id));
         // TODO: use flags repr to determine how many flags are needed
         self.src.push_str(&format!("type {name} uint64\n"));
         self.src.push_str("const (\n");
         for (i, flag) in flags.flags.iter().enumerate() {
+            let flag = self.gen.names.case(id, i, &flag.name).to_uppercase();
             if i == 0 {
                 self.src.push_str(&format!(
                     "   {name}_{flag} {name} = 1 << iota\n",
                     name = name,
-                    flag = flag.name.to_uppercase(),
+                    flag = flag,
                 ));
             } else {
-                self.src.push_str(&format!(
-                    "   {name}_{flag}\n",
-                    name = name,
-                    flag = flag.name.to_uppercase(),
-                ));
+                self.src
+                    .push_str(&format!("   {name}_{flag}\n", name = name, flag = flag,));
             }
         }
         self.src.push_str(")\n\n");
@@ -1379,12 +1396,12 @@ impl<'a> wit_bindgen_core::InterfaceGenerator<'a> for InterfaceGenerator<'a> {
 
     fn type_tuple(
         &mut self,
-        _id: wit_bindgen_core::wit_parser::TypeId,
-        name: &str,
+        id: wit_bindgen_core::wit_parser::TypeId,
+        _name: &str,
         tuple: &wit_bindgen_core::wit_parser::Tuple,
         _docs: &wit_bindgen_core::wit_parser::Docs,
     ) {
-        let name = self.get_func_or_type_name(name);
+        let name = self.get_func_or_type_name(&self.gen.names.type_name(self.resolve, id));
         self.src.push_str(&format!("type {name} struct {{\n",));
         for (i, case) in tuple.types.iter().enumerate() {
             let ty = self.get_ty(case);
@@ -1395,19 +1412,19 @@ impl<'a> wit_bindgen_core::InterfaceGenerator<'a> for InterfaceGenerator<'a> {
 
     fn type_variant(
         &mut self,
-        _id: wit_bindgen_core::wit_parser::TypeId,
-        name: &str,
+        id: wit_bindgen_core::wit_parser::TypeId,
+        _name: &str,
         variant: &wit_bindgen_core::wit_parser::Variant,
         _docs: &wit_bindgen_core::wit_parser::Docs,
     ) {
-        let name = self.get_func_or_type_name(name);
+        let name = self.get_func_or_type_name(&self.gen.names.type_name(self.resolve, id));
         // TODO: use variant's tag to determine how many cases are needed
         // this will help to optmize the Kind type.
         self.src.push_str(&format!("type {name}Kind int\n\n"));
         self.src.push_str("const (\n");
 
         for (i, case) in variant.cases.iter().enumerate() {
-            let case_name = case.name.to_upper_camel_case();
+            let case_name = self.gen.names.case(id, i, &case.name);
             self.print_variant_field(&name, &case_name, i);
         }
         self.src.push_str(")\n\n");
@@ -1419,8 +1436,8 @@ impl<'a> wit_bindgen_core::InterfaceGenerator<'a> for InterfaceGenerator<'a> {
 
         self.print_kind_method(&name);
 
-        for case in variant.cases.iter() {
-            let case_name = case.name.to_upper_camel_case();
+        for (i, case) in variant.cases.iter().enumerate() {
+            let case_name = self.gen.names.case(id, i, &case.name);
             if let Some(ty) = get_nonempty_type(self.resolve, case.ty.as_ref()) {
                 self.gen.needs_fmt_import = true;
                 self.print_accessor_methods(&name, &case_name, ty);
@@ -1452,12 +1469,12 @@ impl<'a> wit_bindgen_core::InterfaceGenerator<'a> for InterfaceGenerator<'a> {
 
     fn type_union(
         &mut self,
-        _id: wit_bindgen_core::wit_parser::TypeId,
-        name: &str,
+        id: wit_bindgen_core::wit_parser::TypeId,
+        _name: &str,
         union: &wit_bindgen_core::wit_parser::Union,
         _docs: &wit_bindgen_core::wit_parser::Docs,
     ) {
-        let name = self.get_func_or_type_name(name);
+        let name = self.get_func_or_type_name(&self.gen.names.type_name(self.resolve, id));
         // TODO: use variant's tag to determine how many cases are needed
         // this will help to optmize the Kind type.
         self.src.push_str(&format!("type {name}Kind int\n\n"));
@@ -1486,19 +1503,19 @@ impl<'a> wit_bindgen_core::InterfaceGenerator<'a> for InterfaceGenerator<'a> {
 
     fn type_enum(
         &mut self,
-        _id: wit_bindgen_core::wit_parser::TypeId,
-        name: &str,
+        id: wit_bindgen_core::wit_parser::TypeId,
+        _name: &str,
         enum_: &wit_bindgen_core::wit_parser::Enum,
         _docs: &wit_bindgen_core::wit_parser::Docs,
     ) {
-        let name = self.get_func_or_type_name(name);
+        let name = self.get_func_or_type_name(&self.gen.names.type_name(self.resolve, id));
         // TODO: use variant's tag to determine how many cases are needed
         // this will help to optmize the Kind type.
         self.src.push_str(&format!("type {name}Kind int\n\n"));
         self.src.push_str("const (\n");
 
         for (i, case) in enum_.cases.iter().enumerate() {
-            let case_name = case.name.to_upper_camel_case();
+            let case_name = self.gen.names.case(id, i, &case.name);
             self.print_variant_field(&name, &case_name, i);
         }
         self.src.push_str(")\n\n");
@@ -1509,32 +1526,32 @@ impl<'a> wit_bindgen_core::InterfaceGenerator<'a> for InterfaceGenerator<'a> {
 
         self.print_kind_method(&name);
 
-        for case in enum_.cases.iter() {
-            let case_name = case.name.to_upper_camel_case();
+        for (i, case) in enum_.cases.iter().enumerate() {
+            let case_name = self.gen.names.case(id, i, &case.name);
             self.print_constructor_method_without_value(&name, &case_name);
         }
     }
 
     fn type_alias(
         &mut self,
-        _id: wit_bindgen_core::wit_parser::TypeId,
-        name: &str,
+        id: wit_bindgen_core::wit_parser::TypeId,
+        _name: &str,
         ty: &wit_bindgen_core::wit_parser::Type,
         _docs: &wit_bindgen_core::wit_parser::Docs,
     ) {
-        let name = self.get_func_or_type_name(name);
+        let name = self.get_func_or_type_name(&self.gen.names.type_name(self.resolve, id));
         let ty = self.get_ty(ty);
         self.src.push_str(&format!("type {name} = {ty}\n"));
     }
 
     fn type_list(
         &mut self,
-        _id: wit_bindgen_core::wit_parser::TypeId,
-        name: &str,
+        id: wit_bindgen_core::wit_parser::TypeId,
+        _name: &str,
         ty: &wit_bindgen_core::wit_parser::Type,
         _docs: &wit_bindgen_core::wit_parser::Docs,
     ) {
-        let name = self.get_func_or_type_name(name);
+        let name = self.get_func_or_type_name(&self.gen.names.type_name(self.resolve, id));
         let ty = self.get_ty(ty);
         self.src.push_str(&format!("type {name} = {ty}\n"));
     }
@@ -1602,6 +1619,18 @@ impl<'a, 'b> FunctionBindgen<'a, 'b> {
                 {lower_name}.len = C.size_t(len({param}))"
         );
 
+        // Plain old data has the same layout in Go and C so it can be copied
+        // over directly.
+        if self.interface.gen.types.is_plain_old_data(l) {
+            let elem_ty = self.interface.get_ty(l);
+            uwriteln!(
+                self.lower_src,
+                "copy(unsafe.Slice((*{elem_ty})(unsafe.Pointer({lower_name}.ptr)), len({param})), {param})"
+            );
+            uwriteln!(self.lower_src, "}}");
+            return;
+        }
+
         uwriteln!(self.lower_src, "for {lower_name}_i := range {param} {{");
         uwriteln!(self.lower_src,
             "{lower_name}_ptr := (*{list_ty})(unsafe.Pointer(uintptr(unsafe.Pointer({lower_name}.ptr)) +
@@ -1703,9 +1732,9 @@ impl<'a, 'b> FunctionBindgen<'a, 'b> {
                     TypeDefKind::Record(r) => {
                         let c_typedef_target = self.interface.get_c_ty(&Type::Id(*id)); // okay to unwrap because a record must have a name
                         uwriteln!(self.lower_src, "var {lower_name} {c_typedef_target}");
-                        for field in r.fields.iter() {
-                            let c_field_name = &self.get_c_field_name(field);
-                            let field_name = &self.interface.get_field_name(field);
+                        for (i, field) in r.fields.iter().enumerate() {
+                            let c_field_name = &self.get_c_field_name(*id, i, field);
+                            let field_name = &self.interface.get_field_name(*id, i, field);
 
                             self.lower_value(
                                 &format!("{param}.{field_name}"),
@@ -1787,7 +1816,7 @@ impl<'a, 'b> FunctionBindgen<'a, 'b> {
                         let ty = self.interface.get_ty(&Type::Id(*id));
                         uwriteln!(self.lower_src, "var {lower_name} {c_typedef_target}");
                         for (i, case) in v.cases.iter().enumerate() {
-                            let case_name = case.name.to_upper_camel_case();
+                            let case_name = self.interface.gen.names.case(*id, i, &case.name);
                             uwriteln!(
                                 self.lower_src,
                                 "if {param}.Kind() == {ty}Kind{case_name} {{"
@@ -1819,7 +1848,7 @@ impl<'a, 'b> FunctionBindgen<'a, 'b> {
                         let ty = self.interface.get_ty(&Type::Id(*id));
                         uwriteln!(self.lower_src, "var {lower_name} {c_typedef_target}");
                         for (i, case) in e.cases.iter().enumerate() {
-                            let case_name = case.name.to_upper_camel_case();
+                            let case_name = self.interface.gen.names.case(*id, i, &case.name);
                             uwriteln!(
                                 self.lower_src,
                                 "if {param}.Kind() == {ty}Kind{case_name} {{"
@@ -1904,9 +1933,9 @@ impl<'a, 'b> FunctionBindgen<'a, 'b> {
                             name = lift_name,
                             value = self.interface.get_ty(&Type::Id(*id)),
                         );
-                        for field in r.fields.iter() {
-                            let field_name = &self.interface.get_field_name(field);
-                            let c_field_name = &self.get_c_field_name(field);
+                        for (i, field) in r.fields.iter().enumerate() {
+                            let field_name = &self.interface.get_field_name(*id, i, field);
+                            let c_field_name = &self.get_c_field_name(*id, i, field);
                             self.lift_value(
                                 &format!("{param}.{c_field_name}"),
                                 &field.ty,
@@ -2001,25 +2030,33 @@ impl<'a, 'b> FunctionBindgen<'a, 'b> {
                         uwriteln!(self.lift_src, "var {lift_name} {list_ty}",);
                         uwriteln!(self.lift_src, "{lift_name} = make({list_ty}, {param}.len)");
                         uwriteln!(self.lift_src, "if {param}.len > 0 {{");
-                        uwriteln!(self.lift_src, "for {lift_name}_i := 0; {lift_name}_i < int({param}.len); {lift_name}_i++ {{");
-                        uwriteln!(self.lift_src, "var empty_{lift_name} {c_ty_name}");
-                        uwriteln!(
-                            self.lift_src,
-                            "{lift_name}_ptr := *(*{c_ty_name})(unsafe.Pointer(uintptr(unsafe.Pointer({param}.ptr)) +
-                            uintptr({lift_name}_i)*unsafe.Sizeof(empty_{lift_name})))"
-                        );
+                        if self.interface.gen.types.is_plain_old_data(l) {
+                            let elem_ty = self.interface.get_ty(l);
+                            uwriteln!(
+                                self.lift_src,
+                                "copy({lift_name}, unsafe.Slice((*{elem_ty})(unsafe.Pointer({param}.ptr)), {param}.len))"
+                            );
+                        } else {
+                            uwriteln!(self.lift_src, "for {lift_name}_i := 0; {lift_name}_i < int({param}.len); {lift_name}_i++ {{");
+                            uwriteln!(self.lift_src, "var empty_{lift_name} {c_ty_name}");
+                            uwriteln!(
+                                self.lift_src,
+                                "{lift_name}_ptr := *(*{c_ty_name})(unsafe.Pointer(uintptr(unsafe.Pointer({param}.ptr)) +
+                                uintptr({lift_name}_i)*unsafe.Sizeof(empty_{lift_name})))"
+                            );
 
-                        self.lift_value(
-                            &format!("{lift_name}_ptr"),
-                            l,
-                            &format!("list_{lift_name}"),
-                        );
+                            self.lift_value(
+                                &format!("{lift_name}_ptr"),
+                                l,
+                                &format!("list_{lift_name}"),
+                            );
 
-                        uwriteln!(
-                            self.lift_src,
-                            "{lift_name}[{lift_name}_i] = list_{lift_name}"
-                        );
-                        self.lift_src.push_str("}\n");
+                            uwriteln!(
+                                self.lift_src,
+                                "{lift_name}[{lift_name}_i] = list_{lift_name}"
+                            );
+                            self.lift_src.push_str("}\n");
+                        }
                         self.lift_src.push_str("}\n");
                         // TODO: don't forget to free `ret`
                     }
@@ -2037,7 +2074,7 @@ impl<'a, 'b> FunctionBindgen<'a, 'b> {
                         let name = self.interface.get_ty(&Type::Id(*id));
                         uwriteln!(self.lift_src, "var {lift_name} {name}");
                         for (i, case) in v.cases.iter().enumerate() {
-                            let case_name = case.name.to_upper_camel_case();
+                            let case_name = self.interface.gen.names.case(*id, i, &case.name);
                             self.lift_src
                                 .push_str(&format!("if {param}.tag == {i} {{\n"));
                             if let Some(ty) =
@@ -2067,7 +2104,7 @@ impl<'a, 'b> FunctionBindgen<'a, 'b> {
                         let name = self.interface.get_ty(&Type::Id(*id));
                         uwriteln!(self.lift_src, "var {lift_name} {name}");
                         for (i, case) in e.cases.iter().enumerate() {
-                            let case_name = case.name.to_upper_camel_case();
+                            let case_name = self.interface.gen.names.case(*id, i, &case.name);
                             uwriteln!(self.lift_src, "if {param} == {i} {{");
                             uwriteln!(self.lift_src, "{lift_name} = {name}{case_name}()");
                             self.lift_src.push_str("}\n");
@@ -2113,7 +2150,7 @@ impl<'a, 'b> FunctionBindgen<'a, 'b> {
         }
     }
 
-    fn get_c_field_name(&mut self, field: &Field) -> String {
-        field.name.to_snake_case()
+    fn get_c_field_name(&mut self, id: TypeId, index: usize, field: &Field) -> String {
+        self.interface.gen.c_names.field(id, index, &field.name)
     }
 }
@@ -18,9 +18,10 @@ macro_rules! codegen_test {
                 "guest-go",
                 $test.as_ref(),
                 |resolve, world, files| {
-                    wit_bindgen_go::Opts::default()
-                        .build()
-                        .generate(resolve, world, files);
+                    let mut opts = wit_bindgen_go::Opts::default();
+                    opts.generate_stub = true;
+                    opts.build()
+                        .generate(resolve, world, &Default::default(), files)
                 },
                 verify,
             )
@@ -31,6 +32,11 @@ macro_rules! codegen_test {
 test_helpers::codegen_tests!();
 
 fn verify(dir: &Path, name: &str) {
+    let stub = dir.join(format!("{}_stub.go", name.to_kebab_case()));
+    if stub.exists() {
+        verify_stub(dir, name, &stub);
+    }
+
     let name = name.to_kebab_case();
     let main = dir.join(format!("{name}.go"));
 
@@ -87,3 +93,30 @@ fn verify(dir: &Path, name: &str) {
     cmd.current_dir(dir);
     test_helpers::run_command(&mut cmd);
 }
+
+// Builds the stub as the `main` package of a module named after the world,
+// with the bindings in its `gen` directory, the layout it expects.
+fn verify_stub(dir: &Path, name: &str, stub: &Path) {
+    let snake = name.to_snake_case();
+    let module = dir.join("stub");
+    let gen = module.join("gen");
+    std::fs::create_dir_all(&gen).expect("failed to create directory");
+    for entry in std::fs::read_dir(dir).expect("failed to read directory") {
+        let path = entry.expect("failed to read directory").path();
+        if path.is_file() && path != stub {
+            std::fs::copy(&path, gen.join(path.file_name().unwrap())).expect("failed to copy file");
+        }
+    }
+    std::fs::rename(stub, module.join("main.go")).expect("failed to move file");
+    std::fs::write(module.join("go.mod"), format!("module {snake}\n\ngo 1.20"))
+        .expect("failed to write go.mod");
+
+    let mut cmd = Command::new("tinygo");
+    cmd.arg("build");
+    cmd.arg("-target=wasi");
+    cmd.arg("-o");
+    cmd.arg("go.wasm");
+    cmd.arg("main.go");
+    cmd.current_dir(module);
+    test_helpers::run_command(&mut cmd);
+}
@@ -1,10 +1,35 @@
 #![no_std]
 
 extern crate alloc;
+#[cfg(all(feature = "mock", not(target_arch = "wasm32")))]
+extern crate std;
 
 #[cfg(feature = "macros")]
 pub use wit_bindgen_rust_macro::*;
 
+#[cfg(all(feature = "mock", not(target_arch = "wasm32")))]
+pub mod mock;
+
+/// Checks that the `mock` feature is enabled, for bindings generated with the
+/// `mock_imports` option.
+#[doc(hidden)]
+#[macro_export]
+#[cfg(feature = "mock")]
+macro_rules! __require_mock_feature {
+    () => {};
+}
+
+/// Checks that the `mock` feature is enabled, for bindings generated with the
+/// `mock_imports` option.
+#[doc(hidden)]
+#[macro_export]
+#[cfg(not(feature = "mock"))]
+macro_rules! __require_mock_feature {
+    () => {
+        compile_error!("the `mock_imports` option requires the `mock` feature of `wit-bindgen`");
+    };
+}
+
 // Re-export `bitflags` so that we can reference it from macros.
 #[doc(hidden)]
 pub use bitflags;
@@ -42,9 +67,11 @@ pub mod rt {
     }
 
     use super::alloc::alloc::Layout;
+    use core::fmt;
+    use core::marker::PhantomData;
 
     // Re-export things from liballoc for convenient use.
-    pub use super::alloc::{alloc, string, vec};
+    pub use super::alloc::{alloc, boxed, string, vec};
 
     #[cfg(feature = "realloc")]
     #[no_mangle]
@@ -124,4 +151,82 @@ pub mod rt {
         (AsF32 as_f32 f32 <=> f32)
         (AsF64 as_f64 f64 <=> f64)
     }
+
+    /// A handle to a component model `future` which will eventually produce a
+    /// value of type `T`.
+    ///
+    /// Futures can't yet be passed to or from functions, so for now this is
+    /// only a typed wrapper around the raw handle.
+    #[repr(transparent)]
+    pub struct Future<T> {
+        handle: u32,
+        _marker: PhantomData<T>,
+    }
+
+    impl<T> Future<T> {
+        /// Creates a future from a raw handle.
+        ///
+        /// # Safety
+        ///
+        /// The caller must own `handle` and it must refer to a future whose
+        /// payload is `T`.
+        pub unsafe fn from_handle(handle: u32) -> Future<T> {
+            Future {
+                handle,
+                _marker: PhantomData,
+            }
+        }
+
+        /// Returns the raw handle of this future.
+        pub fn handle(&self) -> u32 {
+            self.handle
+        }
+    }
+
+    impl<T> fmt::Debug for Future<T> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_struct("Future")
+                .field("handle", &self.handle)
+                .finish()
+        }
+    }
+
+    /// A handle to a component model `stream` producing elements of type `T`
+    /// and finishing with a value of type `E`.
+    ///
+    /// Like [`Future`] this is currently only a typed wrapper around the raw
+    /// handle.
+    #[repr(transparent)]
+    pub struct Stream<T, E> {
+        handle: u32,
+        _marker: PhantomData<(T, E)>,
+    }
+
+    impl<T, E> Stream<T, E> {
+        /// Creates a stream from a raw handle.
+        ///
+        /// # Safety
+        ///
+        /// The caller must own `handle` and it must refer to a stream whose
+        /// element and end types are `T` and `E`.
+        pub unsafe fn from_handle(handle: u32) -> Stream<T, E> {
+            Stream {
+                handle,
+                _marker: PhantomData,
+            }
+        }
+
+        /// Returns the raw handle of this stream.
+        pub fn handle(&self) -> u32 {
+            self.handle
+        }
+    }
+
+    impl<T, E> fmt::Debug for Stream<T, E> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_struct("Stream")
+                .field("handle", &self.handle)
+                .finish()
+        }
+    }
 }
@@ -0,0 +1,86 @@
+//! Support for unit testing guests natively, with mocks of their imports.
+//!
+//! This requires the `mock` feature, and isn't available on wasm.
+//!
+//! Bindings generated with the `mock_imports` option call into a mock
+//! installed at runtime when they aren't compiled for wasm, instead of into
+//! the host. Each interface with imported functions gets a `Mock` trait, a
+//! `set_mock` function which installs one for the current thread, and a
+//! `RecordingMock` which records the calls made through it in [`Calls`].
+
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+use core::fmt::Debug;
+
+#[doc(hidden)]
+pub use std::thread_local;
+
+/// A call made to a mocked import.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Call {
+    /// The path of the function, such as `a:b/iface#func`.
+    pub function: &'static str,
+    /// The arguments, formatted with `Debug`.
+    pub args: Vec<String>,
+}
+
+/// The calls made through a recording mock.
+///
+/// Clones share the same calls, so one can be kept to inspect them after the
+/// mock is installed.
+#[derive(Debug, Clone, Default)]
+pub struct Calls(Rc<RefCell<Vec<Call>>>);
+
+impl Calls {
+    /// Records a call to `function` with `args`.
+    pub fn record(&self, function: &'static str, args: &[&dyn Debug]) {
+        let args = args.iter().map(|arg| format!("{arg:?}")).collect();
+        self.0.borrow_mut().push(Call { function, args });
+    }
+
+    /// Returns the calls made so far, in order.
+    pub fn get(&self) -> Vec<Call> {
+        self.0.borrow().clone()
+    }
+
+    /// Returns the calls made so far, in order, and forgets them.
+    pub fn take(&self) -> Vec<Call> {
+        core::mem::take(&mut *self.0.borrow_mut())
+    }
+}
+
+/// Where the mock for an interface is installed, in a thread local.
+#[doc(hidden)]
+pub struct Slot<T: ?Sized>(RefCell<Option<Box<T>>>);
+
+impl<T: ?Sized> Default for Slot<T> {
+    fn default() -> Slot<T> {
+        Slot::new()
+    }
+}
+
+impl<T: ?Sized> Slot<T> {
+    pub const fn new() -> Slot<T> {
+        Slot(RefCell::new(None))
+    }
+
+    pub fn set(&self, mock: Box<T>) -> Option<Box<T>> {
+        self.0.replace(Some(mock))
+    }
+
+    /// Calls `f` with the installed mock, panicking if there isn't one.
+    pub fn with<R>(&self, imports: &str, f: impl FnOnce(&mut T) -> R) -> R {
+        let mut mock = self.0.borrow_mut();
+        match mock.as_deref_mut() {
+            Some(mock) => f(mock),
+            None => panic!(
+                "no mock is installed for the functions imported by {imports}, \
+                 install one with `set_mock`"
+            ),
+        }
+    }
+}
@@ -0,0 +1,464 @@
+//! A machine-readable JSON description of a resolved world.
+//!
+//! This is intended for tooling which needs to know about a world without
+//! re-implementing `wit-parser`, and is also the format in which worlds are
+//! passed to out-of-process generators by `wit-bindgen-plugin`.
+//!
+//! Everything in a `Resolve` is encoded, not just what's reachable from the
+//! selected world, so arena indices can be used directly to refer to
+//! packages, worlds, interfaces and types. Primitive types are encoded as
+//! their WIT name, for example `"u32"`, and all other types as the index of
+//! their definition in `types`.
+
+use serde_json::{json, Map, Value};
+use wit_bindgen_core::wit_parser::abi::{AbiVariant, WasmSignature, WasmType};
+use wit_bindgen_core::wit_parser::*;
+use wit_bindgen_core::{Diagnostic, FileInfo, Files, WorldGenerator};
+
+const PRODUCER: &str = "wit-bindgen-json";
+
+/// The version of the JSON document produced by `encode`.
+///
+/// This is bumped whenever the document changes in a way which isn't
+/// backwards-compatible for consumers.
+pub const VERSION: u32 = 1;
+
+#[derive(Default, Debug, Clone)]
+#[cfg_attr(feature = "clap", derive(clap::Args))]
+pub struct Opts {
+    /// Emit the document on a single line instead of pretty-printing it.
+    #[cfg_attr(feature = "clap", arg(long))]
+    pub compact: bool,
+}
+
+impl Opts {
+    pub fn build(&self) -> Box<dyn WorldGenerator> {
+        Box::new(Json { opts: self.clone() })
+    }
+}
+
+struct Json {
+    opts: Opts,
+}
+
+impl WorldGenerator for Json {
+    fn import_interface(
+        &mut self,
+        _resolve: &Resolve,
+        _name: &WorldKey,
+        _iface: InterfaceId,
+        _files: &mut Files,
+    ) -> Result<(), Diagnostic> {
+        Ok(())
+    }
+
+    fn export_interface(
+        &mut self,
+        _resolve: &Resolve,
+        _name: &WorldKey,
+        _iface: InterfaceId,
+        _files: &mut Files,
+    ) -> Result<(), Diagnostic> {
+        Ok(())
+    }
+
+    fn import_funcs(
+        &mut self,
+        _resolve: &Resolve,
+        _world: WorldId,
+        _funcs: &[(&str, &Function)],
+        _files: &mut Files,
+    ) -> Result<(), Diagnostic> {
+        Ok(())
+    }
+
+    fn export_funcs(
+        &mut self,
+        _resolve: &Resolve,
+        _world: WorldId,
+        _funcs: &[(&str, &Function)],
+        _files: &mut Files,
+    ) -> Result<(), Diagnostic> {
+        Ok(())
+    }
+
+    fn export_types(
+        &mut self,
+        _resolve: &Resolve,
+        _world: WorldId,
+        _types: &[(&str, TypeId)],
+        _files: &mut Files,
+    ) -> Result<(), Diagnostic> {
+        Ok(())
+    }
+
+    fn finish(
+        &mut self,
+        resolve: &Resolve,
+        world: WorldId,
+        files: &mut Files,
+    ) -> Result<(), Diagnostic> {
+        let doc = encode(resolve, world);
+        let mut contents = if self.opts.compact {
+            doc.to_string()
+        } else {
+            serde_json::to_string_pretty(&doc).unwrap()
+        };
+        contents.push('\n');
+        files.create(
+            &format!("{}.json", resolve.worlds[world].name),
+            FileInfo::text(PRODUCER, "json"),
+            contents.as_bytes(),
+        )
+    }
+}
+
+/// Encodes `resolve` along with the selected `world`.
+pub fn encode(resolve: &Resolve, world: WorldId) -> Value {
+    let mut sizes = SizeAlign::default();
+    sizes.fill(resolve);
+    json!({
+        "version": VERSION,
+        "world": world.index(),
+        "packages": resolve.packages.iter().map(|(_, p)| package(p)).collect::<Vec<_>>(),
+        "worlds": resolve
+            .worlds
+            .iter()
+            .map(|(_, w)| world_def(resolve, w))
+            .collect::<Vec<_>>(),
+        "interfaces": resolve
+            .interfaces
+            .iter()
+            .map(|(_, i)| interface(resolve, i))
+            .collect::<Vec<_>>(),
+        "types": resolve
+            .types
+            .iter()
+            .map(|(id, t)| type_def(&sizes, id, t))
+            .collect::<Vec<_>>(),
+    })
+}
+
+fn package(pkg: &Package) -> Value {
+    let mut name = format!("{}:{}", pkg.name.namespace, pkg.name.name);
+    if let Some(version) = &pkg.name.version {
+        name.push_str(&format!("@{version}"));
+    }
+    json!({
+        "name": name,
+        "namespace": pkg.name.namespace,
+        "package": pkg.name.name,
+        "version": pkg.name.version.as_ref().map(|v| v.to_string()),
+        "docs": docs(&pkg.docs),
+        "interfaces": index_map(pkg.interfaces.iter().map(|(n, id)| (n, id.index()))),
+        "worlds": index_map(pkg.worlds.iter().map(|(n, id)| (n, id.index()))),
+    })
+}
+
+fn world_def(resolve: &Resolve, world: &World) -> Value {
+    let items = |items: &mut dyn Iterator<Item = (&WorldKey, &WorldItem)>, import| {
+        items
+            .map(|(key, item)| world_item(resolve, key, item, import))
+            .collect::<Vec<_>>()
+    };
+    json!({
+        "name": world.name,
+        "package": world.package.map(|p| p.index()),
+        "docs": docs(&world.docs),
+        "imports": items(&mut world.imports.iter(), true),
+        "exports": items(&mut world.exports.iter(), false),
+    })
+}
+
+fn world_item(resolve: &Resolve, key: &WorldKey, item: &WorldItem, import: bool) -> Value {
+    let name = resolve.name_world_key(key);
+    match item {
+        WorldItem::Interface(id) => json!({
+            "name": name,
+            "interface": id.index(),
+            "core": index_map(
+                resolve.interfaces[*id]
+                    .functions
+                    .iter()
+                    .map(|(n, f)| (n, core_names(resolve, Some(&name), f, import))),
+            ),
+        }),
+        WorldItem::Function(f) => json!({
+            "name": name,
+            "function": function(resolve, f),
+            "core": core_names(resolve, None, f, import),
+        }),
+        WorldItem::Type(id) => json!({ "name": name, "type": id.index() }),
+    }
+}
+
+/// Encodes the names by which `func` is imported or exported by a core
+/// module, where `interface` is the name of the interface it's in, if any.
+fn core_names(resolve: &Resolve, interface: Option<&str>, func: &Function, import: bool) -> Value {
+    if import {
+        return json!({
+            "module": interface.unwrap_or("$root"),
+            "name": func.name,
+        });
+    }
+    let name = func.core_export_name(interface);
+    let post_return = if resolve.guest_export_needs_post_return(func) {
+        Some(format!("cabi_post_{name}"))
+    } else {
+        None
+    };
+    json!({
+        "name": name,
+        "post_return": post_return,
+    })
+}
+
+fn interface(resolve: &Resolve, iface: &Interface) -> Value {
+    json!({
+        "name": iface.name,
+        "package": iface.package.map(|p| p.index()),
+        "docs": docs(&iface.docs),
+        "types": index_map(iface.types.iter().map(|(n, id)| (n, id.index()))),
+        "functions": iface
+            .functions
+            .values()
+            .map(|f| function(resolve, f))
+            .collect::<Vec<_>>(),
+    })
+}
+
+fn type_def(sizes: &SizeAlign, id: TypeId, ty: &TypeDef) -> Value {
+    let owner = match ty.owner {
+        TypeOwner::World(id) => json!({ "world": id.index() }),
+        TypeOwner::Interface(id) => json!({ "interface": id.index() }),
+        TypeOwner::None => Value::Null,
+    };
+    let kind = match &ty.kind {
+        TypeDefKind::Record(r) => json!({
+            "record": {
+                "fields": r
+                    .fields
+                    .iter()
+                    .map(|f| json!({ "name": f.name, "docs": docs(&f.docs), "type": type_ref(&f.ty) }))
+                    .collect::<Vec<_>>(),
+            }
+        }),
+        TypeDefKind::Flags(f) => json!({
+            "flags": {
+                "flags": f
+                    .flags
+                    .iter()
+                    .map(|f| json!({ "name": f.name, "docs": docs(&f.docs) }))
+                    .collect::<Vec<_>>(),
+            }
+        }),
+        TypeDefKind::Tuple(t) => json!({
+            "tuple": { "types": t.types.iter().map(type_ref).collect::<Vec<_>>() }
+        }),
+        TypeDefKind::Variant(v) => json!({
+            "variant": {
+                "cases": v
+                    .cases
+                    .iter()
+                    .map(|c| json!({
+                        "name": c.name,
+                        "docs": docs(&c.docs),
+                        "type": c.ty.as_ref().map(type_ref),
+                    }))
+                    .collect::<Vec<_>>(),
+            }
+        }),
+        TypeDefKind::Enum(e) => json!({
+            "enum": {
+                "cases": e
+                    .cases
+                    .iter()
+                    .map(|c| json!({ "name": c.name, "docs": docs(&c.docs) }))
+                    .collect::<Vec<_>>(),
+            }
+        }),
+        TypeDefKind::Option(t) => json!({ "option": type_ref(t) }),
+        TypeDefKind::Result(r) => json!({
+            "result": {
+                "ok": r.ok.as_ref().map(type_ref),
+                "err": r.err.as_ref().map(type_ref),
+            }
+        }),
+        TypeDefKind::Union(u) => json!({
+            "union": {
+                "cases": u
+                    .cases
+                    .iter()
+                    .map(|c| json!({ "docs": docs(&c.docs), "type": type_ref(&c.ty) }))
+                    .collect::<Vec<_>>(),
+            }
+        }),
+        TypeDefKind::List(t) => json!({ "list": type_ref(t) }),
+        TypeDefKind::Future(t) => json!({ "future": t.as_ref().map(type_ref) }),
+        TypeDefKind::Stream(s) => json!({
+            "stream": {
+                "element": s.element.as_ref().map(type_ref),
+                "end": s.end.as_ref().map(type_ref),
+            }
+        }),
+        TypeDefKind::Type(t) => json!({ "type": type_ref(t) }),
+        TypeDefKind::Unknown => Value::String("unknown".to_string()),
+    };
+    let ty_id = Type::Id(id);
+    json!({
+        "name": ty.name,
+        "owner": owner,
+        "docs": docs(&ty.docs),
+        "kind": kind,
+        "size": sizes.size(&ty_id),
+        "align": sizes.align(&ty_id),
+    })
+}
+
+fn function(resolve: &Resolve, func: &Function) -> Value {
+    let kind = match &func.kind {
+        FunctionKind::Freestanding => "freestanding",
+    };
+    let results = match &func.results {
+        Results::Named(results) => results
+            .iter()
+            .map(|(name, ty)| json!({ "name": name, "type": type_ref(ty) }))
+            .collect::<Vec<_>>(),
+        Results::Anon(ty) => vec![json!({ "name": null, "type": type_ref(ty) })],
+    };
+    json!({
+        "name": func.name,
+        "kind": kind,
+        "docs": docs(&func.docs),
+        "params": func
+            .params
+            .iter()
+            .map(|(name, ty)| json!({ "name": name, "type": type_ref(ty) }))
+            .collect::<Vec<_>>(),
+        "results": results,
+        "core": {
+            "import": signature(&resolve.wasm_signature(AbiVariant::GuestImport, func)),
+            "export": signature(&resolve.wasm_signature(AbiVariant::GuestExport, func)),
+        },
+    })
+}
+
+fn signature(sig: &WasmSignature) -> Value {
+    json!({
+        "params": sig.params.iter().map(|t| wasm_type(*t)).collect::<Vec<_>>(),
+        "results": sig.results.iter().map(|t| wasm_type(*t)).collect::<Vec<_>>(),
+        "indirect_params": sig.indirect_params,
+        "retptr": sig.retptr,
+    })
+}
+
+fn wasm_type(ty: WasmType) -> &'static str {
+    match ty {
+        WasmType::I32 => "i32",
+        WasmType::I64 => "i64",
+        WasmType::F32 => "f32",
+        WasmType::F64 => "f64",
+    }
+}
+
+/// Encodes a reference to `ty`.
+pub fn type_ref(ty: &Type) -> Value {
+    let name = match ty {
+        Type::Bool => "bool",
+        Type::U8 => "u8",
+        Type::U16 => "u16",
+        Type::U32 => "u32",
+        Type::U64 => "u64",
+        Type::S8 => "s8",
+        Type::S16 => "s16",
+        Type::S32 => "s32",
+        Type::S64 => "s64",
+        Type::Float32 => "float32",
+        Type::Float64 => "float64",
+        Type::Char => "char",
+        Type::String => "string",
+        Type::Id(id) => return json!(id.index()),
+    };
+    Value::String(name.to_string())
+}
+
+fn docs(docs: &Docs) -> Value {
+    json!(docs.contents)
+}
+
+fn index_map<'a, T: Into<Value>>(items: impl Iterator<Item = (&'a String, T)>) -> Value {
+    Value::Object(
+        items
+            .map(|(name, value)| (name.clone(), value.into()))
+            .collect::<Map<_, _>>(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode() {
+        let mut resolve = Resolve::default();
+        let pkg = UnresolvedPackage::parse(
+            "test.wit".as_ref(),
+            "package a:b
+
+            interface i {
+                record point { x: u32, y: float64 }
+                get: func(p: point) -> list<point>
+            }
+
+            world w {
+                import i
+                export i
+                export run: func()
+            }",
+        )
+        .unwrap();
+        let pkg = resolve.push(pkg).unwrap();
+        let world = resolve.select_world(pkg, Some("w")).unwrap();
+        let doc = super::encode(&resolve, world);
+
+        assert_eq!(doc["version"], json!(VERSION));
+        assert_eq!(doc["packages"][0]["name"], json!("a:b"));
+        let w = &doc["worlds"][doc["world"].as_u64().unwrap() as usize];
+        assert_eq!(w["name"], json!("w"));
+        assert_eq!(w["imports"][0]["name"], json!("a:b/i"));
+        assert_eq!(
+            w["imports"][0]["core"]["get"],
+            json!({ "module": "a:b/i", "name": "get" })
+        );
+        assert_eq!(
+            w["exports"][0]["core"]["get"],
+            json!({ "name": "a:b/i#get", "post_return": "cabi_post_a:b/i#get" })
+        );
+        assert_eq!(
+            w["exports"][1]["core"],
+            json!({ "name": "run", "post_return": null })
+        );
+
+        let iface = &doc["interfaces"][w["imports"][0]["interface"].as_u64().unwrap() as usize];
+        let point = iface["types"]["point"].as_u64().unwrap() as usize;
+        let point = &doc["types"][point];
+        assert_eq!(point["size"], json!(16));
+        assert_eq!(point["align"], json!(8));
+        assert_eq!(
+            point["kind"]["record"]["fields"][1]["type"],
+            json!("float64")
+        );
+
+        let get = &iface["functions"][0];
+        assert_eq!(get["name"], json!("get"));
+        assert_eq!(
+            get["core"]["import"],
+            json!({
+                "params": ["i32", "f64", "i32"],
+                "results": [],
+                "indirect_params": false,
+                "retptr": true,
+            })
+        );
+    }
+}
@@ -0,0 +1,30 @@
+use std::path::Path;
+
+macro_rules! codegen_test {
+    ($id:ident $name:tt $test:tt) => {
+        #[test]
+        fn $id() {
+            test_helpers::run_world_codegen_test(
+                "json",
+                $test.as_ref(),
+                |resolve, world, files| {
+                    wit_bindgen_json::Opts::default().build().generate(
+                        resolve,
+                        world,
+                        &Default::default(),
+                        files,
+                    )
+                },
+                verify,
+            );
+        }
+    };
+}
+
+test_helpers::codegen_tests!();
+
+fn verify(dir: &Path, name: &str) {
+    let contents = std::fs::read(dir.join(format!("{name}.json"))).unwrap();
+    let doc: serde_json::Value = serde_json::from_slice(&contents).unwrap();
+    assert_eq!(doc["version"], wit_bindgen_json::VERSION);
+}
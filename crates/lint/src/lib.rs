@@ -0,0 +1,615 @@
+//! Configurable checks over a WIT package.
+//!
+//! `lint` runs each `Rule` over every interface and world in a package and
+//! reports what it finds at the level configured for the rule. Rules catch
+//! things which are valid WIT but make for awkward bindings, such as names
+//! which turn into keywords in some languages or collide with each other in
+//! Rust.
+//!
+//! Findings refer to items by path, written like the paths used by `--rename`
+//! but always including the package: `ns:pkg/iface#item` for an item in an
+//! interface, `ns:pkg/world#item` for one defined directly in a world, and
+//! `ns:pkg/world/name#item` for one in an interface defined inline in a
+//! world. Fields and cases are written `...#ty.member`.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
+use wit_bindgen_core::wit_parser::abi::WasmType;
+use wit_bindgen_core::wit_parser::*;
+use wit_bindgen_core::{Casing, Names, Profile, Renames};
+
+mod locate;
+
+pub use locate::{Kind, Location, Locations};
+
+/// A check which can be enabled or disabled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Rule {
+    /// Functions, types, fields and cases without documentation.
+    MissingDocs,
+    /// Types which aren't used by any function in the package.
+    UnusedTypes,
+    /// Items which end up with the same identifier in Rust, which the Rust
+    /// generator resolves by adding a numeric suffix. Other generators don't
+    /// assign their identifiers with `Names`, so aren't checked.
+    NameCollisions,
+    /// Functions whose parameters or results flatten to more core wasm
+    /// values than configured, which are then passed through memory.
+    LargeSignatures,
+    /// Names which are keywords in some language, which the generator for it
+    /// escapes with a trailing underscore.
+    Keywords,
+}
+
+impl Rule {
+    pub const ALL: [Rule; 5] = [
+        Rule::MissingDocs,
+        Rule::UnusedTypes,
+        Rule::NameCollisions,
+        Rule::LargeSignatures,
+        Rule::Keywords,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Rule::MissingDocs => "missing-docs",
+            Rule::UnusedTypes => "unused-types",
+            Rule::NameCollisions => "name-collisions",
+            Rule::LargeSignatures => "large-signatures",
+            Rule::Keywords => "keywords",
+        }
+    }
+
+    pub fn default_level(&self) -> Level {
+        Level::Warn
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Rule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Rule, String> {
+        match Rule::ALL.iter().find(|r| r.name() == s) {
+            Some(rule) => Ok(*rule),
+            None => {
+                let names = Rule::ALL.iter().map(|r| r.name()).collect::<Vec<_>>();
+                Err(format!(
+                    "unknown lint rule `{s}`, expected one of: {}",
+                    names.join(", ")
+                ))
+            }
+        }
+    }
+}
+
+/// How a finding of a rule is reported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    /// The rule isn't run.
+    Allow,
+    /// Findings are reported but don't fail the lint.
+    Warn,
+    /// Findings are errors.
+    Deny,
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Level::Allow => f.write_str("allow"),
+            Level::Warn => f.write_str("warning"),
+            Level::Deny => f.write_str("error"),
+        }
+    }
+}
+
+/// Which rules are run, at which level, and their settings.
+#[derive(Debug, Clone)]
+pub struct Config {
+    levels: HashMap<Rule, Level>,
+    /// The number of core wasm values parameters may flatten to before
+    /// `large-signatures` reports them. The canonical ABI passes parameters
+    /// through memory beyond 16.
+    pub max_flat_params: usize,
+    /// The number of core wasm values results may flatten to before
+    /// `large-signatures` reports them. The canonical ABI returns results
+    /// through memory beyond 1, so this is higher by default to only report
+    /// particularly large results.
+    pub max_flat_results: usize,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            levels: HashMap::new(),
+            max_flat_params: 16,
+            max_flat_results: 16,
+        }
+    }
+}
+
+impl Config {
+    pub fn level(&self, rule: Rule) -> Level {
+        self.levels
+            .get(&rule)
+            .copied()
+            .unwrap_or(rule.default_level())
+    }
+
+    pub fn set_level(&mut self, rule: Rule, level: Level) {
+        self.levels.insert(rule, level);
+    }
+}
+
+/// Something reported by a rule.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+    pub rule: Rule,
+    pub level: Level,
+    /// The path of the item the finding is about.
+    pub path: String,
+    pub message: String,
+    /// Where the item is defined, if it could be found.
+    pub location: Option<Location>,
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}[{}]: {}", self.level, self.rule, self.message)?;
+        match &self.location {
+            Some(location) => write!(f, "\n  --> {location}"),
+            None => write!(f, "\n  --> {}", self.path),
+        }
+    }
+}
+
+/// Runs the rules enabled in `config` over the package `pkg`, returning what
+/// they found. `locations` is used to point findings at their source.
+pub fn lint(
+    resolve: &Resolve,
+    pkg: PackageId,
+    config: &Config,
+    locations: &Locations,
+) -> Vec<Finding> {
+    let mut linter = Linter {
+        resolve,
+        config,
+        locations,
+        findings: Vec::new(),
+    };
+    let package = &resolve.packages[pkg];
+    let prefix = format!("{}:{}", package.name.namespace, package.name.name);
+
+    // Every function in the package, along with the path of the interface or
+    // world it's in.
+    let mut funcs = Vec::new();
+    // Every type defined in the package, along with its path.
+    let mut types = Vec::new();
+    for (name, id) in package.interfaces.iter() {
+        let path = format!("{prefix}/{name}");
+        linter.name(Kind::Interface, &path, name, |p| p.modules);
+        items(resolve, &path, *id, &mut funcs, &mut types);
+    }
+    for (name, id) in package.worlds.iter() {
+        let path = format!("{prefix}/{name}");
+        let world = &resolve.worlds[*id];
+        for (key, item) in world.imports.iter().chain(world.exports.iter()) {
+            match (key, item) {
+                (WorldKey::Name(name), WorldItem::Interface(id)) => {
+                    let path = format!("{path}/{name}");
+                    linter.name(Kind::Interface, &path, name, |p| p.modules);
+                    items(resolve, &path, *id, &mut funcs, &mut types);
+                }
+                (_, WorldItem::Function(f)) => funcs.push((path.clone(), f)),
+                (WorldKey::Name(name), WorldItem::Type(ty)) => {
+                    types.push((format!("{path}#{name}"), *ty))
+                }
+                _ => {}
+            }
+        }
+    }
+
+    let mut live = LiveTypes::default();
+    for (path, func) in funcs.iter() {
+        live.add_func(resolve, func);
+        linter.function(&format!("{path}#{}", func.name), func);
+    }
+    let live = live.iter().collect::<HashSet<_>>();
+    for (path, id) in types.iter() {
+        linter.ty(path, *id, live.contains(id));
+    }
+
+    let type_paths = types.iter().map(|(path, _)| path.as_str()).collect();
+    for (name, id) in package.worlds.iter() {
+        linter.collisions(&format!("{prefix}/{name}"), *id, &type_paths);
+    }
+    linter.findings
+}
+
+/// The languages checked for keywords.
+const PROFILES: &[(&str, Profile)] = &[
+    ("Rust", Profile::RUST),
+    ("C", Profile::C),
+    ("Go", Profile::GO),
+    ("Java", Profile::JAVA),
+];
+
+/// The languages checked for name collisions, which are those whose
+/// generators assign identifiers with `Names`.
+const COLLISION_PROFILES: &[(&str, Profile)] = &[
+    ("Rust", Profile::RUST),
+    ("C", Profile::C),
+    ("Go", Profile::GO),
+    ("Java", Profile::JAVA),
+];
+
+struct Linter<'a> {
+    resolve: &'a Resolve,
+    config: &'a Config,
+    locations: &'a Locations,
+    findings: Vec<Finding>,
+}
+
+impl Linter<'_> {
+    fn report(&mut self, rule: Rule, kind: Kind, path: &str, message: String) {
+        let level = self.config.level(rule);
+        if level == Level::Allow {
+            return;
+        }
+        self.findings.push(Finding {
+            rule,
+            level,
+            path: path.to_string(),
+            message,
+            location: self.locations.get(kind, path).cloned(),
+        });
+    }
+
+    fn function(&mut self, path: &str, func: &Function) {
+        if func.docs.contents.is_none() {
+            let message = format!("function `{}` is undocumented", func.name);
+            self.report(Rule::MissingDocs, Kind::Function, path, message);
+        }
+        self.name(Kind::Function, path, &func.name, |p| p.functions);
+        for (name, _) in func.params.iter() {
+            self.name(Kind::Function, path, name, |p| p.fields);
+        }
+
+        let mut params = Vec::new();
+        for (_, ty) in func.params.iter() {
+            self.resolve.push_flat(ty, &mut params);
+        }
+        if params.len() > self.config.max_flat_params {
+            let message = format!(
+                "the parameters of `{}` flatten to {} core wasm values, so they're passed through memory",
+                func.name,
+                params.len()
+            );
+            self.report(Rule::LargeSignatures, Kind::Function, path, message);
+        }
+        let mut results: Vec<WasmType> = Vec::new();
+        for ty in func.results.iter_types() {
+            self.resolve.push_flat(ty, &mut results);
+        }
+        if results.len() > self.config.max_flat_results {
+            let message = format!(
+                "the results of `{}` flatten to {} core wasm values, so they're returned through memory",
+                func.name,
+                results.len()
+            );
+            self.report(Rule::LargeSignatures, Kind::Function, path, message);
+        }
+    }
+
+    fn ty(&mut self, path: &str, id: TypeId, live: bool) {
+        let resolve = self.resolve;
+        let ty = &resolve.types[id];
+        let name = ty.name.as_deref().unwrap_or("");
+
+        // Types brought in with `use` have their docs and members where
+        // they're defined, and are only checked for being used.
+        if let TypeDefKind::Type(Type::Id(other)) = ty.kind {
+            let other = &resolve.types[other];
+            if other.name == ty.name && other.owner != ty.owner {
+                if !live {
+                    let message = format!("type `{name}` is imported with `use` but never used");
+                    self.report(Rule::UnusedTypes, Kind::Type, path, message);
+                }
+                return;
+            }
+        }
+
+        if ty.docs.contents.is_none() {
+            let message = format!("type `{name}` is undocumented");
+            self.report(Rule::MissingDocs, Kind::Type, path, message);
+        }
+        if !live {
+            let message = format!("type `{name}` isn't used by any function");
+            self.report(Rule::UnusedTypes, Kind::Type, path, message);
+        }
+        self.name(Kind::Type, path, name, |p| p.types);
+
+        let (what, members): (_, Vec<(String, &Docs)>) = match &ty.kind {
+            TypeDefKind::Record(r) => (
+                "field",
+                r.fields.iter().map(|f| (f.name.clone(), &f.docs)).collect(),
+            ),
+            TypeDefKind::Variant(v) => (
+                "case",
+                v.cases.iter().map(|c| (c.name.clone(), &c.docs)).collect(),
+            ),
+            TypeDefKind::Enum(e) => (
+                "case",
+                e.cases.iter().map(|c| (c.name.clone(), &c.docs)).collect(),
+            ),
+            TypeDefKind::Flags(f) => (
+                "flag",
+                f.flags.iter().map(|f| (f.name.clone(), &f.docs)).collect(),
+            ),
+            TypeDefKind::Union(u) => (
+                "case",
+                u.cases
+                    .iter()
+                    .enumerate()
+                    .map(|(i, c)| (i.to_string(), &c.docs))
+                    .collect(),
+            ),
+            _ => return,
+        };
+        let union = matches!(ty.kind, TypeDefKind::Union(_));
+        for (member, docs) in members {
+            let path = format!("{path}.{member}");
+            if docs.contents.is_none() {
+                let message = format!("{what} `{member}` of `{name}` is undocumented");
+                self.report(Rule::MissingDocs, Kind::Member, &path, message);
+            }
+            if union {
+                continue;
+            }
+            match &ty.kind {
+                TypeDefKind::Record(_) => self.name(Kind::Member, &path, &member, |p| p.fields),
+                _ => self.name(Kind::Member, &path, &member, |p| p.cases),
+            }
+        }
+    }
+
+    /// Reports `name` if it's reserved in any language once converted to the
+    /// case `case` selects from the language's profile. `kind` and `path`
+    /// are those of the item the name belongs to.
+    fn name(&mut self, kind: Kind, path: &str, name: &str, case: fn(&Profile) -> Casing) {
+        let languages = PROFILES
+            .iter()
+            .filter(|(_, profile)| profile.is_reserved(&case(profile).convert(name)))
+            .map(|(language, _)| *language)
+            .collect::<Vec<_>>();
+        if languages.is_empty() {
+            return;
+        }
+        let message = format!(
+            "`{name}` is a keyword in {}, so it's escaped there",
+            languages.join(", ")
+        );
+        self.report(Rule::Keywords, kind, path, message);
+    }
+
+    /// Reports items in `world` which collide, where `type_paths` are the
+    /// paths of every type in the package.
+    fn collisions(&mut self, world_path: &str, world: WorldId, type_paths: &HashSet<&str>) {
+        // The identifiers each pair of colliding items map to, by language.
+        let mut collisions: Vec<((String, String), Vec<String>)> = Vec::new();
+        for (language, profile) in COLLISION_PROFILES {
+            let mut names = Names::new(*profile, Renames::default());
+            if names.assign(self.resolve, world).is_err() {
+                continue;
+            }
+            for collision in names.collisions() {
+                let path = lint_path(world_path, &collision.path);
+                let other = lint_path(world_path, &collision.other);
+                let ident = format!("`{}` in {language}", collision.ident);
+                match collisions
+                    .iter_mut()
+                    .find(|(k, _)| *k == (path.clone(), other.clone()))
+                {
+                    Some((_, idents)) => idents.push(ident),
+                    None => collisions.push(((path, other), vec![ident])),
+                }
+            }
+        }
+
+        for ((path, other), idents) in collisions {
+            let idents = idents.join(" and ");
+            let message = if path == other {
+                format!("`{path}` names two items which both become {idents}")
+            } else {
+                format!("`{other}` and `{path}` both become {idents}")
+            };
+            let kind = match path.split_once('#') {
+                None => Kind::Interface,
+                Some((_, item)) if item.contains('.') => Kind::Member,
+                Some(_) if type_paths.contains(path.as_str()) => Kind::Type,
+                Some(_) => Kind::Function,
+            };
+            self.report(Rule::NameCollisions, kind, &path, message);
+        }
+    }
+}
+
+/// Converts a path as used by `Names` to one used in findings.
+fn lint_path(world_path: &str, path: &str) -> String {
+    // Items in the world itself, and in interfaces defined inline in it,
+    // aren't qualified with a package.
+    if !path.contains(':') {
+        return match path.split_once('#') {
+            Some(_) => format!("{world_path}/{path}"),
+            None => format!("{world_path}#{path}"),
+        };
+    }
+    // Package versions aren't included in paths.
+    match path.split_once('@') {
+        Some((pkg, rest)) => match rest.find(['#', '.']) {
+            Some(i) => format!("{pkg}{}", &rest[i..]),
+            None => pkg.to_string(),
+        },
+        None => path.to_string(),
+    }
+}
+
+/// Adds the functions and types of the interface `id`, found at `path`, to
+/// `funcs` and `types`.
+fn items<'a>(
+    resolve: &'a Resolve,
+    path: &str,
+    id: InterfaceId,
+    funcs: &mut Vec<(String, &'a Function)>,
+    types: &mut Vec<(String, TypeId)>,
+) {
+    let iface = &resolve.interfaces[id];
+    for (name, ty) in iface.types.iter() {
+        types.push((format!("{path}#{name}"), *ty));
+    }
+    for func in iface.functions.values() {
+        funcs.push((path.to_string(), func));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SRC: &str = "package a:b
+
+/// Documented.
+interface i {
+  record point { x: u32, %type: u32 }
+  /// Documented.
+  type unused = u8
+  /// Documented.
+  enum color {
+    /// Documented.
+    red,
+    green,
+  }
+  point: func(p: point) -> color
+  big: func(a: tuple<u64, u64, u64, u64, u64, u64, u64, u64, u64>, b: tuple<u64, u64, u64, u64, u64, u64, u64, u64, u64>)
+}
+
+/// Documented.
+world w {
+  import i
+}
+";
+
+    fn findings(src: &str, config: &Config) -> Vec<String> {
+        let pkg = UnresolvedPackage::parse("test.wit".as_ref(), src).unwrap();
+        let mut resolve = Resolve::default();
+        let pkg = resolve.push(pkg).unwrap();
+        let mut locations = Locations::default();
+        locations.add("test.wit".as_ref(), src);
+        lint(&resolve, pkg, config, &locations)
+            .iter()
+            .map(|f| f.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn rules() {
+        let mut config = Config::default();
+        config.set_level(Rule::MissingDocs, Level::Allow);
+        assert_eq!(
+            findings(SRC, &config),
+            [
+                "warning[large-signatures]: the parameters of `big` flatten to 18 core wasm values, so they're passed through memory\n  --> test.wit:15:3",
+                "warning[keywords]: `type` is a keyword in Rust, so it's escaped there\n  --> test.wit:5:26",
+                "warning[unused-types]: type `unused` isn't used by any function\n  --> test.wit:7:8",
+            ]
+        );
+    }
+
+    #[test]
+    fn missing_docs() {
+        let mut config = Config::default();
+        for rule in Rule::ALL {
+            config.set_level(rule, Level::Allow);
+        }
+        config.set_level(Rule::MissingDocs, Level::Deny);
+        assert_eq!(
+            findings(SRC, &config),
+            [
+                "error[missing-docs]: function `point` is undocumented\n  --> test.wit:14:3",
+                "error[missing-docs]: function `big` is undocumented\n  --> test.wit:15:3",
+                "error[missing-docs]: type `point` is undocumented\n  --> test.wit:5:10",
+                "error[missing-docs]: field `x` of `point` is undocumented\n  --> test.wit:5:18",
+                "error[missing-docs]: field `type` of `point` is undocumented\n  --> test.wit:5:26",
+                "error[missing-docs]: case `green` of `color` is undocumented\n  --> test.wit:12:5",
+            ]
+        );
+    }
+
+    #[test]
+    fn name_collisions() {
+        let src = "package a:b
+
+interface i {
+  record point { x: u32, X: u32 }
+  point: func(p: point)
+}
+
+world w {
+  import i
+}
+";
+        let mut config = Config::default();
+        for rule in Rule::ALL {
+            config.set_level(rule, Level::Allow);
+        }
+        config.set_level(Rule::NameCollisions, Level::Warn);
+        assert_eq!(
+            findings(src, &config),
+            [
+                "warning[name-collisions]: `a:b/i#point.x` and `a:b/i#point.X` both become `x` in Rust and `x` in C and `X` in Go and `x` in Java\n  --> test.wit:4:26",
+                "warning[name-collisions]: `a:b/i#point` names two items which both become `ABIPoint` in Go\n  --> test.wit:4:10",
+            ]
+        );
+    }
+
+    #[test]
+    fn qualified_name_collisions() {
+        let src = "package a:b
+
+interface foo {
+  record bar-baz { x: u32 }
+}
+
+interface foo-bar {
+  record baz { x: u32 }
+}
+
+world w {
+  import foo
+  import foo-bar
+}
+";
+        let mut config = Config::default();
+        for rule in Rule::ALL {
+            config.set_level(rule, Level::Allow);
+        }
+        config.set_level(Rule::NameCollisions, Level::Warn);
+        assert_eq!(
+            findings(src, &config),
+            ["warning[name-collisions]: `a:b/foo#bar-baz` and `a:b/foo-bar#baz` both become `a_b_foo_bar_baz_t` in C and `ABFooBarBaz` in Go\n  --> test.wit:8:10"]
+        );
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!("keywords".parse(), Ok(Rule::Keywords));
+        assert!("nope".parse::<Rule>().is_err());
+    }
+}
@@ -0,0 +1,302 @@
+//! Mapping of items back to where they're defined in WIT source.
+//!
+//! `Resolve` doesn't record where items came from, so `Locations` recovers
+//! that by scanning the source files for declarations. This only needs to
+//! understand enough of WIT to know which interface, world or type it's in,
+//! and relies on the files having been successfully parsed already.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// A position in a source file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Location {
+    pub path: PathBuf,
+    /// The 1-based line.
+    pub line: usize,
+    /// The 1-based column, in characters.
+    pub column: usize,
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.path.display(), self.line, self.column)
+    }
+}
+
+/// The kind of an item, which along with its path identifies it. A type and
+/// a function in the same interface or world may share a name, and so a path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Kind {
+    /// An interface or world.
+    Interface,
+    Type,
+    Function,
+    /// A field or case of a type.
+    Member,
+}
+
+/// The locations of the items declared in a set of WIT files, by kind and
+/// path.
+///
+/// Paths are the same as those used for findings: `ns:pkg/iface` for an
+/// interface or world, `ns:pkg/iface#item` for a type or function in one,
+/// `ns:pkg/world/name` for an interface defined inline in a world, and
+/// `...#ty.member` for the fields and cases of a type. Union cases are named
+/// by their index.
+#[derive(Debug, Default)]
+pub struct Locations {
+    items: HashMap<(Kind, String), Location>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Punct(char),
+}
+
+enum Frame {
+    /// An interface or world, with items at `path#item`.
+    Items(String),
+    /// A type with fields or cases, at `path.member`.
+    Members { path: String, union: bool, n: usize },
+    /// Anything else in braces, such as a list of names in a `use`.
+    Other,
+}
+
+impl Locations {
+    /// Scans the WIT source `contents` of the file `path` for declarations.
+    pub fn add(&mut self, path: &Path, contents: &str) {
+        let tokens = tokenize(contents);
+        let ident = |i: usize| match tokens.get(i) {
+            Some((Token::Ident(s), _, _)) => Some(s.as_str()),
+            _ => None,
+        };
+        let punct =
+            |i: usize, c: char| matches!(tokens.get(i), Some((Token::Punct(p), _, _)) if *p == c);
+
+        let mut package = String::new();
+        let mut stack = Vec::new();
+        let mut pending = None;
+        // Whether the next identifier in a type's body names a member, as
+        // opposed to a type within angle brackets or parentheses.
+        let mut member = false;
+        let mut nesting = 0;
+
+        for (i, (token, line, column)) in tokens.iter().enumerate() {
+            let mut record = |kind: Kind, key: String| {
+                self.items.entry((kind, key)).or_insert_with(|| Location {
+                    path: path.to_path_buf(),
+                    line: *line,
+                    column: *column,
+                });
+            };
+            match token {
+                Token::Punct('{') => {
+                    stack.push(pending.take().unwrap_or(Frame::Other));
+                    member = true;
+                    nesting = 0;
+                }
+                Token::Punct('}') => {
+                    stack.pop();
+                    pending = None;
+                }
+                Token::Punct('<' | '(') => nesting += 1,
+                Token::Punct('>' | ')') => nesting -= 1,
+                Token::Punct(',') if nesting == 0 => member = true,
+                Token::Punct(_) => {}
+                Token::Ident(name) => match stack.last_mut() {
+                    None => {
+                        let prev = if i > 0 { ident(i - 1) } else { None };
+                        match prev {
+                            Some("package") => {
+                                if let (true, Some(pkg)) = (punct(i + 1, ':'), ident(i + 2)) {
+                                    package = format!("{name}:{pkg}");
+                                }
+                            }
+                            Some("interface" | "world") => {
+                                let key = format!("{package}/{name}");
+                                record(Kind::Interface, key.clone());
+                                pending = Some(Frame::Items(key));
+                            }
+                            _ => {}
+                        }
+                    }
+                    Some(Frame::Items(prefix)) => {
+                        let prev = if i > 0 { ident(i - 1) } else { None };
+                        match prev {
+                            Some("record" | "variant" | "enum" | "flags" | "union") => {
+                                let key = format!("{prefix}#{name}");
+                                record(Kind::Type, key.clone());
+                                pending = Some(Frame::Members {
+                                    path: key,
+                                    union: prev == Some("union"),
+                                    n: 0,
+                                });
+                            }
+                            Some("type" | "resource") => {
+                                record(Kind::Type, format!("{prefix}#{name}"))
+                            }
+                            _ if punct(i + 1, ':') => match ident(i + 2) {
+                                Some("func") => record(Kind::Function, format!("{prefix}#{name}")),
+                                Some("interface") => {
+                                    let key = format!("{prefix}/{name}");
+                                    record(Kind::Interface, key.clone());
+                                    pending = Some(Frame::Items(key));
+                                }
+                                _ => {}
+                            },
+                            _ => {}
+                        }
+                    }
+                    Some(Frame::Members { path, union, n }) => {
+                        if member && nesting == 0 {
+                            let name = if *union { n.to_string() } else { name.clone() };
+                            record(Kind::Member, format!("{path}.{name}"));
+                            *n += 1;
+                            member = false;
+                        }
+                    }
+                    Some(Frame::Other) => {}
+                },
+            }
+        }
+    }
+
+    /// Returns where the item of kind `kind` at `path` is declared, if it
+    /// was found.
+    pub fn get(&self, kind: Kind, path: &str) -> Option<&Location> {
+        if let Some(location) = self.items.get(&(kind, path.to_string())) {
+            return Some(location);
+        }
+        // Files without a `package` declaration are recorded with an empty
+        // package name.
+        let (_, rest) = path.split_once('/')?;
+        self.items.get(&(kind, format!("/{rest}")))
+    }
+}
+
+/// Splits `contents` into identifiers and punctuation along with their line
+/// and column, skipping whitespace and comments.
+fn tokenize(contents: &str) -> Vec<(Token, usize, usize)> {
+    let mut tokens = Vec::new();
+    let mut chars = contents.chars().peekable();
+    let (mut line, mut column) = (1, 1);
+    fn advance(c: char, line: &mut usize, column: &mut usize) {
+        if c == '\n' {
+            *line += 1;
+            *column = 1;
+        } else {
+            *column += 1;
+        }
+    }
+
+    while let Some(c) = chars.next() {
+        let (start_line, start_column) = (line, column);
+        advance(c, &mut line, &mut column);
+        match c {
+            '/' if chars.peek() == Some(&'/') => {
+                for c in chars.by_ref() {
+                    advance(c, &mut line, &mut column);
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                let mut depth = 0;
+                let mut prev = c;
+                for c in chars.by_ref() {
+                    advance(c, &mut line, &mut column);
+                    match (prev, c) {
+                        ('/', '*') => {
+                            depth += 1;
+                            prev = ' ';
+                            continue;
+                        }
+                        ('*', '/') => {
+                            depth -= 1;
+                            if depth == 0 {
+                                break;
+                            }
+                            prev = ' ';
+                            continue;
+                        }
+                        _ => {}
+                    }
+                    prev = c;
+                }
+            }
+            // Skip the `>` of `->` so it isn't mistaken for the end of a
+            // type's parameters.
+            '-' if chars.peek() == Some(&'>') => {
+                advance('>', &mut line, &mut column);
+                chars.next();
+            }
+            c if c.is_whitespace() => {}
+            c if c.is_alphanumeric() || c == '_' || c == '%' => {
+                let mut ident = String::new();
+                if c != '%' {
+                    ident.push(c);
+                }
+                while let Some(&c) = chars.peek() {
+                    if !(c.is_alphanumeric() || c == '_' || c == '-') {
+                        break;
+                    }
+                    ident.push(c);
+                    advance(c, &mut line, &mut column);
+                    chars.next();
+                }
+                tokens.push((Token::Ident(ident), start_line, start_column));
+            }
+            c => tokens.push((Token::Punct(c), start_line, start_column)),
+        }
+    }
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn locate() {
+        let mut locations = Locations::default();
+        locations.add(
+            "a.wit".as_ref(),
+            "package a:b
+
+/// Docs { with braces }
+interface i {
+  use other.{thing}
+  record point { x: tuple<u8, u32>, %type: u32 }
+  union u { u8, string }
+  /* a /* nested */ comment */ get: func(p: point) -> result<point, u8>
+  point: func()
+}
+
+world w {
+  import run: func()
+  export e: interface {
+    enum color { red, green }
+  }
+}
+",
+        );
+        let line = |kind, path: &str| locations.get(kind, path).map(|l| (l.line, l.column));
+        assert_eq!(line(Kind::Interface, "a:b/i"), Some((4, 11)));
+        assert_eq!(line(Kind::Type, "a:b/i#point"), Some((6, 10)));
+        assert_eq!(line(Kind::Function, "a:b/i#point"), Some((9, 3)));
+        assert_eq!(line(Kind::Member, "a:b/i#point.x"), Some((6, 18)));
+        assert_eq!(line(Kind::Member, "a:b/i#point.type"), Some((6, 37)));
+        assert_eq!(line(Kind::Member, "a:b/i#point.u32"), None);
+        assert_eq!(line(Kind::Member, "a:b/i#u.1"), Some((7, 17)));
+        assert_eq!(line(Kind::Function, "a:b/i#get"), Some((8, 32)));
+        assert_eq!(line(Kind::Type, "a:b/i#get"), None);
+        assert_eq!(line(Kind::Type, "a:b/i#thing"), None);
+        assert_eq!(line(Kind::Function, "a:b/w#run"), Some((13, 10)));
+        assert_eq!(line(Kind::Interface, "a:b/w/e"), Some((14, 10)));
+        assert_eq!(line(Kind::Member, "a:b/w/e#color.green"), Some((15, 23)));
+    }
+}
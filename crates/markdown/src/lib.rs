@@ -3,10 +3,13 @@ use pulldown_cmark::{html, Event, LinkType, Parser, Tag};
 use std::collections::HashMap;
 use std::fmt::Write;
 use wit_bindgen_core::{
-    uwriteln, wit_parser, Files, InterfaceGenerator as _, Source, WorldGenerator,
+    uwriteln, wit_parser, Diagnostic, FileInfo, Files, InterfaceGenerator as _, Source, Style,
+    WorldGenerator,
 };
 use wit_parser::*;
 
+const PRODUCER: &str = "wit-bindgen-markdown";
+
 #[derive(Default)]
 struct Markdown {
     src: Source,
@@ -30,13 +33,14 @@ pub struct Opts {
 impl Opts {
     pub fn build(&self) -> Box<dyn WorldGenerator> {
         let mut r = Markdown::default();
+        r.src = Source::new(Style::MARKDOWN);
         r.opts = self.clone();
         Box::new(r)
     }
 }
 
 impl WorldGenerator for Markdown {
-    fn preprocess(&mut self, resolve: &Resolve, world: WorldId) {
+    fn preprocess(&mut self, resolve: &Resolve, world: WorldId) -> Result<(), Diagnostic> {
         self.sizes.fill(resolve);
 
         let world = &resolve.worlds[world];
@@ -108,6 +112,7 @@ impl WorldGenerator for Markdown {
             }
         }
         gen.push_str("\n");
+        Ok(())
     }
 
     fn import_interface(
@@ -116,7 +121,7 @@ impl WorldGenerator for Markdown {
         name: &WorldKey,
         id: InterfaceId,
         _files: &mut Files,
-    ) {
+    ) -> Result<(), Diagnostic> {
         let name = resolve.name_world_key(name);
         uwriteln!(
             self.src,
@@ -128,8 +133,9 @@ impl WorldGenerator for Markdown {
         let mut gen = self.interface(resolve);
         gen.docs(&resolve.interfaces[id].docs);
         gen.push_str("\n");
-        gen.types(id);
+        gen.types(id)?;
         gen.funcs(id);
+        Ok(())
     }
 
     fn import_funcs(
@@ -138,13 +144,14 @@ impl WorldGenerator for Markdown {
         world: WorldId,
         funcs: &[(&str, &Function)],
         _files: &mut Files,
-    ) {
+    ) -> Result<(), Diagnostic> {
         let name = &resolve.worlds[world].name;
         uwriteln!(self.src, "## Imported functions to world `{name}`\n");
         let mut gen = self.interface(resolve);
         for (_, func) in funcs {
             gen.func(func);
         }
+        Ok(())
     }
 
     fn export_interface(
@@ -153,7 +160,7 @@ impl WorldGenerator for Markdown {
         name: &WorldKey,
         id: InterfaceId,
         _files: &mut Files,
-    ) {
+    ) -> Result<(), Diagnostic> {
         let name = resolve.name_world_key(name);
         uwriteln!(
             self.src,
@@ -163,8 +170,9 @@ impl WorldGenerator for Markdown {
         self.hrefs
             .insert(name.to_string(), format!("#{}", name.to_snake_case()));
         let mut gen = self.interface(resolve);
-        gen.types(id);
+        gen.types(id)?;
         gen.funcs(id);
+        Ok(())
     }
 
     fn export_funcs(
@@ -173,13 +181,14 @@ impl WorldGenerator for Markdown {
         world: WorldId,
         funcs: &[(&str, &Function)],
         _files: &mut Files,
-    ) {
+    ) -> Result<(), Diagnostic> {
         let name = &resolve.worlds[world].name;
         uwriteln!(self.src, "## Exported functions from world `{name}`\n");
         let mut gen = self.interface(resolve);
         for (_, func) in funcs {
             gen.func(func);
         }
+        Ok(())
     }
 
     fn export_types(
@@ -188,16 +197,22 @@ impl WorldGenerator for Markdown {
         world: WorldId,
         types: &[(&str, TypeId)],
         _files: &mut Files,
-    ) {
+    ) -> Result<(), Diagnostic> {
         let name = &resolve.worlds[world].name;
         uwriteln!(self.src, "## Exported types from world `{name}`\n");
         let mut gen = self.interface(resolve);
         for (name, ty) in types {
-            gen.define_type(name, *ty);
+            gen.define_type(name, *ty)?;
         }
+        Ok(())
     }
 
-    fn finish(&mut self, resolve: &Resolve, world: WorldId, files: &mut Files) {
+    fn finish(
+        &mut self,
+        resolve: &Resolve,
+        world: WorldId,
+        files: &mut Files,
+    ) -> Result<(), Diagnostic> {
         let world = &resolve.worlds[world];
         let parser = Parser::new(&self.src);
         let mut events = Vec::new();
@@ -218,12 +233,25 @@ impl WorldGenerator for Markdown {
 
         if self.opts.html_in_md {
             // Write the html output into a .md file.
-            files.push(&format!("{}.md", world.name), html_output.as_bytes());
+            files.create(
+                &format!("{}.md", world.name),
+                FileInfo::text(PRODUCER, "markdown"),
+                html_output.as_bytes(),
+            )?;
         } else {
             // Write the html output to an html file, and md output to a md file.
-            files.push(&format!("{}.md", world.name), self.src.as_bytes());
-            files.push(&format!("{}.html", world.name), html_output.as_bytes());
+            files.create(
+                &format!("{}.md", world.name),
+                FileInfo::text(PRODUCER, "markdown"),
+                self.src.as_bytes(),
+            )?;
+            files.create(
+                &format!("{}.html", world.name),
+                FileInfo::text(PRODUCER, "html"),
+                html_output.as_bytes(),
+            )?;
         }
+        Ok(())
     }
 }
 
@@ -673,4 +701,26 @@ impl<'a> wit_bindgen_core::InterfaceGenerator<'a> for InterfaceGenerator<'a> {
     fn type_builtin(&mut self, id: TypeId, name: &str, ty: &Type, docs: &Docs) {
         self.type_alias(id, name, ty, docs)
     }
+
+    fn type_future(
+        &mut self,
+        id: TypeId,
+        name: &str,
+        _payload: Option<&Type>,
+        docs: &Docs,
+    ) -> Result<(), Diagnostic> {
+        self.type_alias(id, name, &Type::Id(id), docs);
+        Ok(())
+    }
+
+    fn type_stream(
+        &mut self,
+        id: TypeId,
+        name: &str,
+        _stream: &Stream,
+        docs: &Docs,
+    ) -> Result<(), Diagnostic> {
+        self.type_alias(id, name, &Type::Id(id), docs);
+        Ok(())
+    }
 }
@@ -0,0 +1,291 @@
+//! Support for generators which run as separate executables.
+//!
+//! A plugin named `foo` is an executable called `wit-bindgen-foo` somewhere
+//! on `PATH`. It's run with any extra arguments given on the command line,
+//! receives the JSON document produced by `wit_bindgen_json::encode` on
+//! stdin, and writes a response to stdout of the form:
+//!
+//! ```json
+//! {
+//!     "version": 1,
+//!     "files": [
+//!         { "name": "foo.txt", "contents": "...", "language": "text" },
+//!         { "name": "foo.bin", "bytes": [0, 1, 2] },
+//!         { "name": "run.sh", "contents": "...", "executable": true }
+//!     ]
+//! }
+//! ```
+//!
+//! or, if bindings couldn't be generated, `{ "version": 1, "error": "..." }`.
+//! Anything the plugin writes to stderr is passed through to the user.
+
+use serde_json::Value;
+use std::io::Write;
+use std::path::{Component, Path};
+use std::process::{Command, Stdio};
+use wit_bindgen_core::wit_parser::{Function, InterfaceId, Resolve, TypeId, WorldId, WorldKey};
+use wit_bindgen_core::{Diagnostic, FileInfo, FileKind, Files, WorldGenerator};
+use wit_bindgen_json::VERSION;
+
+#[derive(Default, Debug, Clone)]
+#[cfg_attr(feature = "clap", derive(clap::Args))]
+pub struct Opts {
+    /// Name of the plugin to run; `wit-bindgen-<name>` is searched for in
+    /// `PATH`.
+    #[cfg_attr(feature = "clap", arg(long))]
+    pub name: String,
+
+    /// Extra arguments to pass to the plugin, may be specified multiple
+    /// times.
+    #[cfg_attr(feature = "clap", arg(long = "arg", value_name = "ARG"))]
+    pub args: Vec<String>,
+}
+
+impl Opts {
+    pub fn build(&self) -> Box<dyn WorldGenerator> {
+        Box::new(Plugin { opts: self.clone() })
+    }
+}
+
+struct Plugin {
+    opts: Opts,
+}
+
+impl Plugin {
+    fn program(&self) -> String {
+        format!("wit-bindgen-{}", self.opts.name)
+    }
+
+    fn run(&self, request: &[u8]) -> Result<Vec<u8>, Diagnostic> {
+        let program = self.program();
+        let mut child = Command::new(&program)
+            .args(&self.opts.args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .spawn()
+            .map_err(|e| Diagnostic::new(format!("failed to spawn `{program}`: {e}")))?;
+
+        // Write the request from another thread so a plugin which starts
+        // writing its response before it's read all of its input can't
+        // deadlock with us.
+        let mut stdin = child.stdin.take().unwrap();
+        let request = request.to_vec();
+        let writer = std::thread::spawn(move || stdin.write_all(&request));
+
+        let output = child
+            .wait_with_output()
+            .map_err(|e| Diagnostic::new(format!("failed to wait for `{program}`: {e}")))?;
+        let written = writer.join().unwrap();
+        if !output.status.success() {
+            return Err(Diagnostic::new(format!(
+                "`{program}` failed: {}",
+                output.status
+            )));
+        }
+        written.map_err(|e| Diagnostic::new(format!("failed to write to `{program}`: {e}")))?;
+        Ok(output.stdout)
+    }
+}
+
+impl WorldGenerator for Plugin {
+    fn import_interface(
+        &mut self,
+        _resolve: &Resolve,
+        _name: &WorldKey,
+        _iface: InterfaceId,
+        _files: &mut Files,
+    ) -> Result<(), Diagnostic> {
+        Ok(())
+    }
+
+    fn export_interface(
+        &mut self,
+        _resolve: &Resolve,
+        _name: &WorldKey,
+        _iface: InterfaceId,
+        _files: &mut Files,
+    ) -> Result<(), Diagnostic> {
+        Ok(())
+    }
+
+    fn import_funcs(
+        &mut self,
+        _resolve: &Resolve,
+        _world: WorldId,
+        _funcs: &[(&str, &Function)],
+        _files: &mut Files,
+    ) -> Result<(), Diagnostic> {
+        Ok(())
+    }
+
+    fn export_funcs(
+        &mut self,
+        _resolve: &Resolve,
+        _world: WorldId,
+        _funcs: &[(&str, &Function)],
+        _files: &mut Files,
+    ) -> Result<(), Diagnostic> {
+        Ok(())
+    }
+
+    fn export_types(
+        &mut self,
+        _resolve: &Resolve,
+        _world: WorldId,
+        _types: &[(&str, TypeId)],
+        _files: &mut Files,
+    ) -> Result<(), Diagnostic> {
+        Ok(())
+    }
+
+    fn finish(
+        &mut self,
+        resolve: &Resolve,
+        world: WorldId,
+        files: &mut Files,
+    ) -> Result<(), Diagnostic> {
+        let request = wit_bindgen_json::encode(resolve, world).to_string();
+        let response = self.run(request.as_bytes())?;
+        let response: Value = serde_json::from_slice(&response).map_err(|e| {
+            Diagnostic::new(format!("`{}` wrote invalid JSON: {e}", self.program()))
+        })?;
+        decode_response(&self.program(), &response, files)
+    }
+}
+
+/// Adds the files described by a plugin's `response` to `files`.
+fn decode_response(producer: &str, response: &Value, files: &mut Files) -> Result<(), Diagnostic> {
+    let invalid = |msg: &str| Diagnostic::new(format!("invalid response from `{producer}`: {msg}"));
+
+    match response.get("version").and_then(|v| v.as_u64()) {
+        Some(v) if v == u64::from(VERSION) => {}
+        Some(v) => {
+            return Err(invalid(&format!(
+                "unsupported protocol version {v}, expected {}",
+                VERSION
+            )))
+        }
+        None => return Err(invalid("missing `version`")),
+    }
+    if let Some(error) = response.get("error") {
+        let reason = match error.as_str() {
+            Some(s) => s.to_string(),
+            None => error.to_string(),
+        };
+        return Err(Diagnostic::new(reason));
+    }
+    let list = response
+        .get("files")
+        .and_then(|f| f.as_array())
+        .ok_or_else(|| invalid("missing `files`"))?;
+
+    for file in list {
+        let name = file
+            .get("name")
+            .and_then(|n| n.as_str())
+            .ok_or_else(|| invalid("file without a `name`"))?;
+        // Names are joined onto the output directory, so they mustn't be able
+        // to point outside of it.
+        let escapes = Path::new(name).components().any(|c| {
+            matches!(
+                c,
+                Component::ParentDir | Component::RootDir | Component::Prefix(_)
+            )
+        });
+        if escapes {
+            return Err(invalid(&format!(
+                "`{name}` isn't a relative path within the output directory"
+            )));
+        }
+        let (mut info, contents) = match (file.get("contents"), file.get("bytes")) {
+            (Some(contents), None) => {
+                let contents = contents
+                    .as_str()
+                    .ok_or_else(|| invalid(&format!("`contents` of `{name}` isn't a string")))?;
+                let info = FileInfo {
+                    producer: producer.to_string(),
+                    kind: FileKind::Text,
+                    language: None,
+                    executable: false,
+                };
+                (info, contents.as_bytes().to_vec())
+            }
+            (None, Some(bytes)) => {
+                let bytes = bytes
+                    .as_array()
+                    .and_then(|b| {
+                        b.iter()
+                            .map(|b| b.as_u64().and_then(|b| u8::try_from(b).ok()))
+                            .collect::<Option<Vec<u8>>>()
+                    })
+                    .ok_or_else(|| invalid(&format!("`bytes` of `{name}` isn't a byte array")))?;
+                (FileInfo::binary(producer), bytes)
+            }
+            _ => {
+                return Err(invalid(&format!(
+                    "`{name}` must have exactly one of `contents` or `bytes`"
+                )))
+            }
+        };
+        if let Some(language) = file.get("language").and_then(|l| l.as_str()) {
+            info.language = Some(language.to_string());
+        }
+        info.executable = file
+            .get("executable")
+            .and_then(|e| e.as_bool())
+            .unwrap_or(false);
+        files.create(name, info, &contents)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn response() {
+        let mut files = Files::default();
+        let response = json!({
+            "version": VERSION,
+            "files": [
+                { "name": "a.txt", "contents": "hi", "language": "text" },
+                { "name": "b.bin", "bytes": [0, 255] },
+                { "name": "c.sh", "contents": "", "executable": true },
+            ],
+        });
+        decode_response("wit-bindgen-test", &response, &mut files).unwrap();
+        assert_eq!(files.iter().count(), 3);
+        assert_eq!(
+            files.info("a.txt").unwrap().language.as_deref(),
+            Some("text")
+        );
+        assert_eq!(files.info("b.bin").unwrap().kind, FileKind::Binary);
+        assert!(files.info("c.sh").unwrap().executable);
+        assert_eq!(files.iter().find(|f| f.0 == "b.bin").unwrap().1, [0, 255]);
+
+        let mut files = Files::default();
+        let err = decode_response(
+            "wit-bindgen-test",
+            &json!({ "version": VERSION, "error": "nope" }),
+            &mut files,
+        )
+        .unwrap_err();
+        assert_eq!(err.reason, "nope");
+
+        let err =
+            decode_response("wit-bindgen-test", &json!({ "version": 99 }), &mut files).unwrap_err();
+        assert!(err.reason.contains("unsupported protocol version 99"));
+
+        for name in ["/etc/x", "../../x", "a/../../x"] {
+            let response = json!({
+                "version": VERSION,
+                "files": [{ "name": name, "contents": "" }],
+            });
+            let err = decode_response("wit-bindgen-test", &response, &mut files).unwrap_err();
+            assert!(err.reason.contains("isn't a relative path"), "{name}");
+        }
+    }
+}
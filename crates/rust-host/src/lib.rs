@@ -0,0 +1,797 @@
+use heck::*;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write as _;
+use std::mem;
+use wit_bindgen_core::{
+    check_types, reject_async_functions, reject_async_types, uwrite, uwriteln, wit_parser::*,
+    Diagnostic, FileInfo, Files, InterfaceGenerator as _, Names, Profile, Renames, Source, Style,
+    TypeInfo, Types, WorldGenerator,
+};
+use wit_bindgen_rust_lib::{FnSig, RustGenerator, TypeMode};
+
+const PRODUCER: &str = "wit-bindgen-rust-host";
+
+#[derive(Default)]
+struct RustHost {
+    types: Types,
+    src: Source,
+    opts: Opts,
+    import_modules: BTreeMap<Option<PackageName>, Vec<String>>,
+    export_modules: BTreeMap<Option<PackageName>, Vec<String>>,
+    interface_names: HashMap<InterfaceId, String>,
+    names: Names,
+    /// The traits which the host must implement for the world's imports.
+    import_traits: Vec<String>,
+    /// The body of the world's `add_to_linker`, which registers every import.
+    add_to_linker: Source,
+    /// The fields of the world's struct, for its exports.
+    export_fields: Source,
+    /// The body of the world's `new`, which looks up every export.
+    export_init: Source,
+    /// The names of the world struct's fields, in the order they're declared.
+    export_names: Vec<String>,
+    /// Accessors for exported interfaces and wrappers for exported functions.
+    export_methods: Source,
+}
+
+#[derive(Default, Debug, Clone)]
+#[cfg_attr(feature = "clap", derive(clap::Args))]
+pub struct Opts {
+    /// Overrides the Rust identifier used for an item, in the form
+    /// `PATH=NAME`, for example `a:b/iface#ty.field=renamed`. May be specified
+    /// multiple times.
+    #[cfg_attr(feature = "clap", arg(long = "rename", value_name = "PATH=NAME"))]
+    pub rename: Vec<String>,
+}
+
+impl Opts {
+    pub fn build(&self) -> Box<dyn WorldGenerator> {
+        let mut r = RustHost::new();
+        r.opts = self.clone();
+        Box::new(r)
+    }
+}
+
+impl RustHost {
+    fn new() -> RustHost {
+        RustHost {
+            src: Source::new(Style::RUST),
+            ..RustHost::default()
+        }
+    }
+
+    fn interface<'a>(
+        &'a mut self,
+        resolve: &'a Resolve,
+        in_import: bool,
+    ) -> InterfaceGenerator<'a> {
+        InterfaceGenerator {
+            current_interface: None,
+            src: Source::default(),
+            in_import,
+            gen: self,
+            resolve,
+        }
+    }
+
+    fn emit_modules(&mut self, modules: &BTreeMap<Option<PackageName>, Vec<String>>) {
+        let mut map = BTreeMap::new();
+        for (pkg, modules) in modules {
+            match pkg {
+                Some(pkg) => {
+                    let prev = map
+                        .entry(&pkg.namespace)
+                        .or_insert(BTreeMap::new())
+                        .insert(&pkg.name, modules);
+                    assert!(prev.is_none());
+                }
+                None => {
+                    for module in modules {
+                        uwriteln!(self.src, "{module}");
+                    }
+                }
+            }
+        }
+        for (ns, pkgs) in map {
+            uwriteln!(self.src, "pub mod {} {{", ns.to_snake_case());
+            for (pkg, modules) in pkgs {
+                uwriteln!(self.src, "pub mod {} {{", pkg.to_snake_case());
+                for module in modules {
+                    uwriteln!(self.src, "{module}");
+                }
+                uwriteln!(self.src, "}}");
+            }
+            uwriteln!(self.src, "}}");
+        }
+    }
+}
+
+impl WorldGenerator for RustHost {
+    fn preprocess(&mut self, resolve: &Resolve, world: WorldId) -> Result<(), Diagnostic> {
+        check_types(resolve, world, reject_async_types("Rust host"))?;
+        reject_async_functions(resolve, world, "Rust host")?;
+        let version = env!("CARGO_PKG_VERSION");
+        uwriteln!(
+            self.src,
+            "// Generated by `wit-bindgen` {version}. DO NOT EDIT!"
+        );
+        self.types.analyze(resolve);
+        self.names = Names::new(Profile::RUST, Renames::parse(&self.opts.rename)?);
+        self.names.assign(resolve, world)?;
+        Ok(())
+    }
+
+    fn import_interface(
+        &mut self,
+        resolve: &Resolve,
+        name: &WorldKey,
+        id: InterfaceId,
+        _files: &mut Files,
+    ) -> Result<(), Diagnostic> {
+        let mut gen = self.interface(resolve, true);
+        gen.current_interface = Some((id, name));
+        gen.types(id)?;
+        let funcs = resolve.interfaces[id]
+            .functions
+            .values()
+            .collect::<Vec<_>>();
+        gen.generate_add_to_linker(&resolve.name_world_key(name), &funcs);
+        gen.finish_append_submodule(name);
+
+        let path = &self.interface_names[&id];
+        self.import_traits.push(format!("{path}::Host"));
+        uwriteln!(self.add_to_linker, "{path}::add_to_linker(linker, get)?;");
+        Ok(())
+    }
+
+    fn import_funcs(
+        &mut self,
+        resolve: &Resolve,
+        world: WorldId,
+        funcs: &[(&str, &Function)],
+        _files: &mut Files,
+    ) -> Result<(), Diagnostic> {
+        let trait_name = format!(
+            "{}Imports",
+            resolve.worlds[world].name.to_upper_camel_case()
+        );
+        let mut gen = self.interface(resolve, true);
+        let funcs = funcs.iter().map(|(_, f)| *f).collect::<Vec<_>>();
+        let wraps = gen.generate_host_trait(&trait_name, &funcs);
+        let src = gen.finish();
+        self.src.push_str(&src);
+
+        uwriteln!(self.add_to_linker, "let mut inst = linker.root();");
+        self.add_to_linker.push_str(&wraps);
+        self.import_traits.push(trait_name);
+        Ok(())
+    }
+
+    fn export_interface(
+        &mut self,
+        resolve: &Resolve,
+        name: &WorldKey,
+        id: InterfaceId,
+        _files: &mut Files,
+    ) -> Result<(), Diagnostic> {
+        let mut gen = self.interface(resolve, false);
+        gen.current_interface = Some((id, name));
+        gen.types(id)?;
+        let struct_name = match name {
+            WorldKey::Name(name) => name.to_upper_camel_case(),
+            WorldKey::Interface(id) => resolve.interfaces[*id]
+                .name
+                .as_ref()
+                .unwrap()
+                .to_upper_camel_case(),
+        };
+        let funcs = resolve.interfaces[id]
+            .functions
+            .values()
+            .collect::<Vec<_>>();
+        gen.generate_exports(&struct_name, &funcs);
+        gen.finish_append_submodule(name);
+
+        let path = format!("{}::{struct_name}", self.interface_names[&id]);
+        let field = self.names.module(resolve, name, false);
+        let instance = resolve.name_world_key(name);
+        uwriteln!(self.export_fields, "{field}: {path},");
+        uwrite!(
+            self.export_init,
+            "
+                let {field} = {path}::new(
+                    &mut __exports
+                        .instance(\"{instance}\")
+                        .ok_or_else(|| {{
+                            wasmtime::Error::msg(\"exported instance `{instance}` not present\")
+                        }})?,
+                )?;
+            "
+        );
+        uwrite!(
+            self.export_methods,
+            "
+                pub fn {field}(&self) -> &{path} {{
+                    &self.{field}
+                }}
+            "
+        );
+        self.export_names.push(field);
+        Ok(())
+    }
+
+    fn export_funcs(
+        &mut self,
+        resolve: &Resolve,
+        _world: WorldId,
+        funcs: &[(&str, &Function)],
+        _files: &mut Files,
+    ) -> Result<(), Diagnostic> {
+        let mut gen = self.interface(resolve, false);
+        let mut fields = Vec::new();
+        for (_, func) in funcs {
+            fields.push(gen.generate_export_call(func));
+        }
+        let src = gen.finish();
+        self.export_methods.push_str(&src);
+        for (field, init) in fields {
+            uwriteln!(self.export_fields, "{field}: wasmtime::component::Func,");
+            self.export_init.push_str(&init);
+            self.export_names.push(field);
+        }
+        Ok(())
+    }
+
+    fn export_types(
+        &mut self,
+        resolve: &Resolve,
+        _world: WorldId,
+        types: &[(&str, TypeId)],
+        _files: &mut Files,
+    ) -> Result<(), Diagnostic> {
+        let mut gen = self.interface(resolve, true);
+        for (name, ty) in types {
+            gen.define_type(name, *ty)?;
+        }
+        let src = gen.finish();
+        self.src.push_str(&src);
+        Ok(())
+    }
+
+    fn finish(
+        &mut self,
+        resolve: &Resolve,
+        world: WorldId,
+        files: &mut Files,
+    ) -> Result<(), Diagnostic> {
+        let name = &resolve.worlds[world].name;
+        let camel = name.to_upper_camel_case();
+
+        uwriteln!(
+            self.src,
+            "/// The exports of an instance of a component implementing the `{name}` world."
+        );
+        uwriteln!(self.src, "pub struct {camel} {{");
+        self.src.push_str(&mem::take(&mut self.export_fields));
+        uwriteln!(self.src, "}}");
+
+        uwrite!(
+            self.src,
+            "
+                impl {camel} {{
+                    /// Adds the imports of the world to `linker`, which are
+                    /// implemented by the `U` that `get` returns for a store's
+                    /// data.
+                    pub fn add_to_linker<T, U>(
+                        linker: &mut wasmtime::component::Linker<T>,
+                        get: impl Fn(&mut T) -> &mut U + Send + Sync + Copy + 'static,
+                    ) -> wasmtime::Result<()>
+            "
+        );
+        if self.import_traits.is_empty() {
+            uwriteln!(self.src, "{{");
+            uwriteln!(self.src, "let _ = (linker, get);");
+        } else {
+            uwriteln!(self.src, "where U: {}", self.import_traits.join(" + "));
+            uwriteln!(self.src, "{{");
+            self.src.push_str(&mem::take(&mut self.add_to_linker));
+        }
+        uwrite!(
+            self.src,
+            "
+                    Ok(())
+                }}
+
+                /// Instantiates `component` with `linker`, returning its
+                /// exports along with the instance.
+                pub fn instantiate<T>(
+                    mut store: impl wasmtime::AsContextMut<Data = T>,
+                    component: &wasmtime::component::Component,
+                    linker: &wasmtime::component::Linker<T>,
+                ) -> wasmtime::Result<(Self, wasmtime::component::Instance)> {{
+                    let instance = linker.instantiate(&mut store, component)?;
+                    Ok((Self::new(store, &instance)?, instance))
+                }}
+
+                /// Looks up the exports of the world in `instance`.
+                pub fn new(
+                    mut store: impl wasmtime::AsContextMut,
+                    instance: &wasmtime::component::Instance,
+                ) -> wasmtime::Result<Self> {{
+            "
+        );
+        if self.export_names.is_empty() {
+            uwriteln!(self.src, "let _ = (store.as_context_mut(), instance);");
+        } else {
+            uwrite!(
+                self.src,
+                "
+                    let mut store = store.as_context_mut();
+                    let mut exports = instance.exports(&mut store);
+                    let mut __exports = exports.root();
+                "
+            );
+            self.src.push_str(&mem::take(&mut self.export_init));
+        }
+        uwriteln!(self.src, "Ok({camel} {{");
+        for field in mem::take(&mut self.export_names) {
+            uwriteln!(self.src, "{field},");
+        }
+        uwriteln!(self.src, "}})");
+        uwriteln!(self.src, "}}");
+        self.src.push_str(&mem::take(&mut self.export_methods));
+        uwriteln!(self.src, "}}");
+
+        let imports = mem::take(&mut self.import_modules);
+        self.emit_modules(&imports);
+        let exports = mem::take(&mut self.export_modules);
+        if !exports.is_empty() {
+            self.src.push_str("pub mod exports {\n");
+            self.emit_modules(&exports);
+            self.src.push_str("}\n");
+        }
+
+        let src = mem::take(&mut self.src);
+        files.create(
+            &format!("{}.rs", name.to_snake_case()),
+            FileInfo::text(PRODUCER, "rust"),
+            src.as_bytes(),
+        )?;
+        Ok(())
+    }
+}
+
+struct InterfaceGenerator<'a> {
+    src: Source,
+    current_interface: Option<(InterfaceId, &'a WorldKey)>,
+    in_import: bool,
+    gen: &'a mut RustHost,
+    resolve: &'a Resolve,
+}
+
+impl InterfaceGenerator<'_> {
+    /// Generates the `Host` trait for an imported interface, and the
+    /// `add_to_linker` function registering its functions with the instance
+    /// named `instance`.
+    fn generate_add_to_linker(&mut self, instance: &str, funcs: &[&Function]) {
+        let wraps = self.generate_host_trait("Host", funcs);
+        uwrite!(
+            self.src,
+            "
+                pub fn add_to_linker<T, U>(
+                    linker: &mut wasmtime::component::Linker<T>,
+                    get: impl Fn(&mut T) -> &mut U + Send + Sync + Copy + 'static,
+                ) -> wasmtime::Result<()>
+                where U: Host,
+                {{
+            "
+        );
+        if funcs.is_empty() {
+            // The instance is still defined so that components importing the
+            // interface only for its types can be instantiated.
+            uwriteln!(self.src, "linker.instance(\"{instance}\")?;");
+            uwriteln!(self.src, "let _ = get;");
+        } else {
+            uwriteln!(self.src, "let mut inst = linker.instance(\"{instance}\")?;");
+            self.src.push_str(&wraps);
+        }
+        uwriteln!(self.src, "Ok(())");
+        uwriteln!(self.src, "}}");
+    }
+
+    /// Generates the trait named `trait_name` with a method for each of
+    /// `funcs`, returning the statements which register them with the linker
+    /// instance `inst`.
+    fn generate_host_trait(&mut self, trait_name: &str, funcs: &[&Function]) -> String {
+        uwriteln!(self.src, "pub trait {trait_name} {{");
+        for func in funcs {
+            let sig = FnSig {
+                private: true,
+                self_arg: Some("&mut self".to_string()),
+                ..FnSig::default()
+            };
+            self.print_docs_and_params(func, TypeMode::Owned, &sig);
+            let results = self.results_ty(func);
+            uwriteln!(self.src, " -> wasmtime::Result<{results}>;");
+        }
+        uwriteln!(self.src, "}}");
+
+        let mut wraps = Source::default();
+        for func in funcs {
+            let name = self.gen.names.function(self.interface_id(), func);
+            let args = (0..func.params.len())
+                .map(|i| format!("arg{i},"))
+                .collect::<String>();
+            let params = self.params_tuple(func);
+            uwrite!(
+                wraps,
+                "
+                    inst.func_wrap(
+                        \"{}\",
+                        move |mut caller: wasmtime::StoreContextMut<'_, T>, ({args}): {params}| {{
+                            let host = get(caller.data_mut());
+                            let r = {trait_name}::{name}(host, {args});
+                ",
+                func.name,
+            );
+            match func.results.len() {
+                0 => uwriteln!(wraps, "r?;\nOk(())"),
+                1 => uwriteln!(wraps, "Ok((r?,))"),
+                _ => uwriteln!(wraps, "Ok(r?)"),
+            }
+            uwriteln!(wraps, "}},\n)?;");
+        }
+        wraps.into()
+    }
+
+    /// Generates the struct named `struct_name` holding the functions of an
+    /// exported interface, with a method calling each of them.
+    fn generate_exports(&mut self, struct_name: &str, funcs: &[&Function]) {
+        let mut fields = Vec::new();
+        let mut methods = Source::default();
+        for func in funcs {
+            let prev = mem::replace(&mut self.src, mem::take(&mut methods));
+            fields.push(self.generate_export_call(func));
+            methods = mem::replace(&mut self.src, prev);
+        }
+
+        uwriteln!(self.src, "pub struct {struct_name} {{");
+        for (field, _) in fields.iter() {
+            uwriteln!(self.src, "{field}: wasmtime::component::Func,");
+        }
+        uwriteln!(self.src, "}}");
+        uwrite!(
+            self.src,
+            "
+                impl {struct_name} {{
+                    pub fn new(
+                        __exports: &mut wasmtime::component::ExportInstance<'_, '_>,
+                    ) -> wasmtime::Result<{struct_name}> {{
+            "
+        );
+        for (_, init) in fields.iter() {
+            self.src.push_str(init);
+        }
+        uwriteln!(self.src, "Ok({struct_name} {{");
+        for (field, _) in fields.iter() {
+            uwriteln!(self.src, "{field},");
+        }
+        uwriteln!(self.src, "}})");
+        uwriteln!(self.src, "}}");
+        self.src.push_str(&methods);
+        uwriteln!(self.src, "}}");
+    }
+
+    /// Generates the `call_*` method for the exported function `func`,
+    /// returning the name of the field holding it and the statement which
+    /// looks it up in `__exports`.
+    fn generate_export_call(&mut self, func: &Function) -> (String, String) {
+        let name = self.gen.names.function(self.interface_id(), func);
+        let params = self.params_tuple(func);
+        let results = self.results_tuple(func);
+        let ret = self.results_ty(func);
+
+        self.rustdoc(&func.docs);
+        uwrite!(
+            self.src,
+            "pub fn call_{name}<S: wasmtime::AsContextMut>(&self, mut store: S, "
+        );
+        for (i, (_, ty)) in func.params.iter().enumerate() {
+            uwrite!(self.src, "arg{i}: ");
+            self.print_ty(ty, TypeMode::Owned);
+            self.src.push_str(", ");
+        }
+        let args = (0..func.params.len())
+            .map(|i| format!("arg{i},"))
+            .collect::<String>();
+        let ok = match func.results.len() {
+            1 => "ret.0",
+            _ => "ret",
+        };
+        uwrite!(
+            self.src,
+            ") -> wasmtime::Result<{ret}> {{
+                let callee = unsafe {{
+                    wasmtime::component::TypedFunc::<{params}, {results}>::new_unchecked(self.{name})
+                }};
+                let ret = callee.call(store.as_context_mut(), ({args}))?;
+                callee.post_return(store.as_context_mut())?;
+                Ok({ok})
+            }}
+            ",
+        );
+
+        let init = format!(
+            "let {name} = *__exports.typed_func::<{params}, {results}>(\"{}\")?.func();\n",
+            func.name
+        );
+        (name, init)
+    }
+
+    /// Returns the tuple of the types of `func`'s parameters.
+    fn params_tuple(&mut self, func: &Function) -> String {
+        let types = func.params.iter().map(|(_, ty)| *ty).collect::<Vec<_>>();
+        self.tuple(&types)
+    }
+
+    /// Returns the tuple of the types of `func`'s results.
+    fn results_tuple(&mut self, func: &Function) -> String {
+        let types = func.results.iter_types().copied().collect::<Vec<_>>();
+        self.tuple(&types)
+    }
+
+    /// Returns the type `func` returns in Rust: nothing, its single result, or
+    /// a tuple of its results.
+    fn results_ty(&mut self, func: &Function) -> String {
+        let types = func.results.iter_types().copied().collect::<Vec<_>>();
+        match types.as_slice() {
+            [ty] => self.ty(ty),
+            types => self.tuple(types),
+        }
+    }
+
+    fn tuple(&mut self, types: &[Type]) -> String {
+        let mut tuple = String::from("(");
+        for ty in types {
+            tuple.push_str(&self.ty(ty));
+            tuple.push(',');
+        }
+        tuple.push(')');
+        tuple
+    }
+
+    /// Returns the owned Rust type of `ty`.
+    fn ty(&mut self, ty: &Type) -> String {
+        let prev = mem::take(&mut self.src);
+        self.print_ty(ty, TypeMode::Owned);
+        mem::replace(&mut self.src, prev).into()
+    }
+
+    fn finish(&mut self) -> String {
+        mem::take(&mut self.src).into()
+    }
+
+    fn finish_append_submodule(mut self, name: &WorldKey) {
+        let module = self.finish();
+        let snake = self.gen.names.module(self.resolve, name, self.in_import);
+        let pkg = match name {
+            WorldKey::Name(_) => None,
+            WorldKey::Interface(id) => {
+                let pkg = self.resolve.interfaces[*id].package.unwrap();
+                Some(self.resolve.packages[pkg].name.clone())
+            }
+        };
+        if let Some((id, _)) = self.current_interface {
+            let mut path = String::new();
+            if !self.in_import {
+                path.push_str("exports::");
+            }
+            if let Some(name) = &pkg {
+                path.push_str(&format!(
+                    "{}::{}::",
+                    name.namespace.to_snake_case(),
+                    name.name.to_snake_case()
+                ));
+            }
+            path.push_str(&snake);
+            self.gen.interface_names.insert(id, path);
+        }
+        let module = format!(
+            "
+                #[allow(clippy::all)]
+                pub mod {snake} {{
+                    {module}
+                }}
+            ",
+        );
+        let map = if self.in_import {
+            &mut self.gen.import_modules
+        } else {
+            &mut self.gen.export_modules
+        };
+        map.entry(pkg).or_default().push(module);
+    }
+}
+
+impl<'a> RustGenerator<'a> for InterfaceGenerator<'a> {
+    fn resolve(&self) -> &'a Resolve {
+        self.resolve
+    }
+
+    fn duplicate_if_necessary(&self) -> bool {
+        false
+    }
+
+    fn names(&self) -> &Names {
+        &self.gen.names
+    }
+
+    fn interface_id(&self) -> Option<InterfaceId> {
+        self.current_interface.map(|(id, _)| id)
+    }
+
+    fn path_to_interface(&self, interface: InterfaceId) -> Option<String> {
+        let mut path = String::new();
+        if let Some((cur, name)) = self.current_interface {
+            if cur == interface {
+                return None;
+            }
+            if !self.in_import {
+                path.push_str("super::");
+            }
+            match name {
+                WorldKey::Name(_) => {
+                    path.push_str("super::");
+                }
+                WorldKey::Interface(_) => {
+                    path.push_str("super::super::super::");
+                }
+            }
+        }
+        let name = &self.gen.interface_names[&interface];
+        path.push_str(name);
+        Some(path)
+    }
+
+    fn std_feature(&self) -> bool {
+        false
+    }
+
+    fn vec_name(&self) -> &'static str {
+        "Vec"
+    }
+
+    fn string_name(&self) -> &'static str {
+        "String"
+    }
+
+    fn future_name(&self) -> &'static str {
+        unreachable!("futures are rejected in `preprocess`")
+    }
+
+    fn stream_name(&self) -> &'static str {
+        unreachable!("streams are rejected in `preprocess`")
+    }
+
+    fn push_str(&mut self, s: &str) {
+        self.src.push_str(s);
+    }
+
+    /// Flags are generated with `wasmtime::component::flags!`, which only
+    /// implements equality for them.
+    fn flags_are_ordered(&self) -> bool {
+        false
+    }
+
+    /// Every value is owned by the host: arguments of imports are lifted into
+    /// owned values, and arguments of exports are passed by value, so only
+    /// owned versions of types are generated.
+    fn info(&self, ty: TypeId) -> TypeInfo {
+        let mut info = self.gen.types.get(ty);
+        info.owned |= info.borrowed;
+        info.borrowed = false;
+        info
+    }
+
+    fn types_mut(&mut self) -> &mut Types {
+        &mut self.gen.types
+    }
+
+    fn print_borrowed_slice(&mut self, mutbl: bool, ty: &Type, lifetime: &'static str) {
+        self.print_rust_slice(mutbl, ty, lifetime);
+    }
+
+    fn print_borrowed_str(&mut self, lifetime: &'static str) {
+        self.push_str("&");
+        if lifetime != "'_" {
+            self.push_str(lifetime);
+            self.push_str(" ");
+        }
+        self.push_str("str");
+    }
+}
+
+impl<'a> wit_bindgen_core::InterfaceGenerator<'a> for InterfaceGenerator<'a> {
+    fn resolve(&self) -> &'a Resolve {
+        self.resolve
+    }
+
+    fn type_record(&mut self, id: TypeId, _name: &str, record: &Record, docs: &Docs) {
+        self.print_typedef_record(id, record, docs, true);
+    }
+
+    fn type_tuple(&mut self, id: TypeId, _name: &str, tuple: &Tuple, docs: &Docs) {
+        self.print_typedef_tuple(id, tuple, docs);
+    }
+
+    fn type_flags(&mut self, id: TypeId, _name: &str, flags: &Flags, docs: &Docs) {
+        // The `flags!` macro doesn't accept documentation for the flags
+        // themselves, so only the type is documented.
+        self.rustdoc(docs);
+        self.src.push_str("wasmtime::component::flags!(\n");
+        self.src.push_str(&format!(
+            "{} {{\n",
+            self.gen.names.type_name(self.resolve, id)
+        ));
+        for flag in flags.flags.iter() {
+            self.src.push_str(&format!(
+                "#[component(name = \"{}\")]\nconst {};\n",
+                flag.name,
+                flag.name.to_shouty_snake_case(),
+            ));
+        }
+        self.src.push_str("}\n");
+        self.src.push_str(");\n");
+    }
+
+    fn type_variant(&mut self, id: TypeId, _name: &str, variant: &Variant, docs: &Docs) {
+        self.print_typedef_variant(id, variant, docs, true);
+    }
+
+    fn type_union(&mut self, id: TypeId, _name: &str, union: &Union, docs: &Docs) {
+        self.print_typedef_union(id, union, docs, true);
+    }
+
+    fn type_option(&mut self, id: TypeId, _name: &str, payload: &Type, docs: &Docs) {
+        self.print_typedef_option(id, payload, docs);
+    }
+
+    fn type_result(&mut self, id: TypeId, _name: &str, result: &Result_, docs: &Docs) {
+        self.print_typedef_result(id, result, docs);
+    }
+
+    fn type_enum(&mut self, id: TypeId, _name: &str, enum_: &Enum, docs: &Docs) {
+        let attrs = [
+            "#[derive(wasmtime::component::ComponentType)]".to_string(),
+            "#[derive(wasmtime::component::Lift)]".to_string(),
+            "#[derive(wasmtime::component::Lower)]".to_string(),
+            "#[component(enum)]".to_string(),
+        ];
+        self.print_typedef_enum(
+            id,
+            enum_,
+            docs,
+            &attrs,
+            Box::new(|case| format!("#[component(name = \"{}\")] ", case.name)),
+        );
+    }
+
+    fn type_alias(&mut self, id: TypeId, _name: &str, ty: &Type, docs: &Docs) {
+        self.print_typedef_alias(id, ty, docs);
+    }
+
+    fn type_list(&mut self, id: TypeId, _name: &str, ty: &Type, docs: &Docs) {
+        self.print_type_list(id, ty, docs);
+    }
+
+    fn type_builtin(&mut self, id: TypeId, _name: &str, ty: &Type, docs: &Docs) {
+        self.rustdoc(docs);
+        self.src.push_str(&format!(
+            "pub type {}",
+            self.gen.names.type_name(self.resolve, id)
+        ));
+        self.src.push_str(" = ");
+        self.print_ty(ty, TypeMode::Owned);
+        self.src.push_str(";\n");
+    }
+}
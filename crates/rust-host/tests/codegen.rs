@@ -0,0 +1,32 @@
+use std::path::Path;
+
+macro_rules! codegen_test {
+    ($id:ident $name:tt $test:tt) => {
+        #[test]
+        fn $id() {
+            test_helpers::run_world_codegen_test(
+                "rust-host",
+                $test.as_ref(),
+                |resolve, world, files| {
+                    wit_bindgen_rust_host::Opts::default().build().generate(
+                        resolve,
+                        world,
+                        &Default::default(),
+                        files,
+                    )
+                },
+                verify,
+            );
+        }
+    };
+}
+
+test_helpers::codegen_tests!();
+
+fn verify(dir: &Path, name: &str) {
+    let path = dir.join(format!("{}.rs", name.replace('-', "_")));
+    let contents = std::fs::read_to_string(&path).unwrap();
+    if let Err(e) = syn::parse_file(&contents) {
+        panic!("{path:?} isn't valid Rust: {e}");
+    }
+}
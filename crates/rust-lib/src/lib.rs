@@ -1,9 +1,8 @@
-use heck::*;
 use std::collections::HashMap;
 use std::fmt::{self, Write};
 use std::iter::zip;
 use wit_bindgen_core::wit_parser::abi::{Bitcast, LiftLower, WasmType};
-use wit_bindgen_core::{wit_parser::*, TypeInfo, Types};
+use wit_bindgen_core::{wit_parser::*, Casing, Diagnostic, Names, Profile, TypeInfo, Types};
 
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub enum TypeMode {
@@ -16,6 +15,20 @@ pub trait RustGenerator<'a> {
     fn resolve(&self) -> &'a Resolve;
     fn path_to_interface(&self, interface: InterfaceId) -> Option<String>;
 
+    /// Returns the path of the type `id` if it's defined outside of the
+    /// generated bindings, instead of by them.
+    fn path_to_type(&self, id: TypeId) -> Option<String> {
+        drop(id);
+        None
+    }
+
+    /// The identifiers assigned to items in the world being generated.
+    fn names(&self) -> &Names;
+
+    /// The interface whose bindings are being generated, or `None` for
+    /// functions and types defined directly in a world.
+    fn interface_id(&self) -> Option<InterfaceId>;
+
     /// This, if enabled, will possibly cause types to get duplicate copies to
     /// get generated of each other. For example a record containing a string
     /// used both in the import and export context would get one variant
@@ -38,11 +51,30 @@ pub trait RustGenerator<'a> {
     /// Return the fully-qualified name for the `String` type to use.
     fn string_name(&self) -> &'static str;
 
+    /// Return the fully-qualified name for the type used to represent a
+    /// `future`.
+    fn future_name(&self) -> &'static str;
+
+    /// Return the fully-qualified name for the type used to represent a
+    /// `stream`.
+    fn stream_name(&self) -> &'static str;
+
     /// Return true iff the generator should use `&[u8]` instead of `&str` in bindings.
     fn use_raw_strings(&self) -> bool {
         false
     }
 
+    /// Return true iff the types generated for flags implement `PartialOrd`,
+    /// `Ord` and `Hash`, so that types containing them can derive those.
+    fn flags_are_ordered(&self) -> bool {
+        true
+    }
+
+    /// Return the extra derives and attributes to add to generated types.
+    fn type_attributes(&self) -> &TypeAttributes {
+        &NO_TYPE_ATTRIBUTES
+    }
+
     fn push_str(&mut self, s: &str);
     fn info(&self, ty: TypeId) -> TypeInfo;
     fn types_mut(&mut self) -> &mut Types;
@@ -130,11 +162,11 @@ pub trait RustGenerator<'a> {
         }
         self.push_str("fn ");
         let func_name = if sig.use_item_name {
-            func.item_name()
+            to_rust_ident(func.item_name())
         } else {
-            &func.name
+            self.names().function(self.interface_id(), func)
         };
-        self.push_str(&to_rust_ident(&func_name));
+        self.push_str(&func_name);
         if let Some(generics) = &sig.generics {
             self.push_str(generics);
         }
@@ -217,6 +249,9 @@ pub trait RustGenerator<'a> {
     }
 
     fn type_path(&self, id: TypeId, owned: bool) -> String {
+        if let Some(path) = self.path_to_type(id) {
+            return path;
+        }
         let name = if owned {
             self.result_name(id)
         } else {
@@ -327,12 +362,14 @@ pub trait RustGenerator<'a> {
                 panic!("unsupported anonymous type reference: union")
             }
             TypeDefKind::Future(ty) => {
-                self.push_str("Future<");
+                self.push_str(self.future_name());
+                self.push_str("<");
                 self.print_optional_ty(ty.as_ref(), mode);
                 self.push_str(">");
             }
             TypeDefKind::Stream(stream) => {
-                self.push_str("Stream<");
+                self.push_str(self.stream_name());
+                self.push_str("<");
                 self.print_optional_ty(stream.element.as_ref(), mode);
                 self.push_str(",");
                 self.print_optional_ty(stream.end.as_ref(), mode);
@@ -441,7 +478,7 @@ pub trait RustGenerator<'a> {
             Type::Id(id) => {
                 let ty = &self.resolve().types[*id];
                 match &ty.name {
-                    Some(name) => out.push_str(&name.to_upper_camel_case()),
+                    Some(_) => out.push_str(&self.names().type_name(self.resolve(), *id)),
                     None => match &ty.kind {
                         TypeDefKind::Option(ty) => {
                             out.push_str("Optional");
@@ -533,6 +570,69 @@ pub trait RustGenerator<'a> {
         case_names
     }
 
+    /// Prints the `#[derive]` attribute for a generated record or enum.
+    ///
+    /// Only the traits which every field or payload of `id` is known to
+    /// implement are derived, so nothing containing a handle is `Clone`.
+    fn print_derives(&mut self, id: TypeId) {
+        let info = self.info(id);
+        let mut derives = Vec::new();
+        if !info.has_handle {
+            derives.push("Clone");
+        }
+        if info.fixed_size() {
+            derives.push("Copy");
+        }
+        let ordered = !info.has_flags || self.flags_are_ordered();
+        if !info.has_handle {
+            derives.push("PartialEq");
+            if ordered {
+                derives.push("PartialOrd");
+            }
+        }
+        if info.total_eq() {
+            derives.push("Eq");
+            if ordered {
+                derives.extend(["Ord", "Hash"]);
+            }
+        }
+        self.print_derives_and_attributes(id, &derives);
+    }
+
+    /// Prints the `#[derive]` attribute for the generated type `id`, with
+    /// `defaults` along with the extra derives for it from
+    /// `type_attributes`, followed by its extra attributes.
+    ///
+    /// Extra derives which `id` can't implement, such as `Eq` for a record
+    /// containing floats, are left out.
+    fn print_derives_and_attributes(&mut self, id: TypeId, defaults: &[&str]) {
+        let info = self.info(id);
+        let path = self.names().type_path(id).map(|p| p.to_string());
+        let extra = self.type_attributes();
+        let derives_default = extra.derives_default(self.resolve(), self.names(), id);
+        let ordered = !info.has_flags || self.flags_are_ordered();
+
+        let mut derives = defaults.iter().map(|d| d.to_string()).collect::<Vec<_>>();
+        for derive in extra.derives_for(path.as_deref()) {
+            let name = derive_name(derive);
+            if derives.iter().any(|d| derive_name(d) == name)
+                || !derive_is_valid(name, &info, derives_default, ordered)
+            {
+                continue;
+            }
+            derives.push(derive.to_string());
+        }
+        let attributes = extra
+            .attributes_for(path.as_deref())
+            .map(|a| format!("{a}\n"))
+            .collect::<String>();
+
+        if !derives.is_empty() {
+            self.push_str(&format!("#[derive({})]\n", derives.join(", ")));
+        }
+        self.push_str(&attributes);
+    }
+
     fn print_typedef_record(
         &mut self,
         id: TypeId,
@@ -554,22 +654,20 @@ pub trait RustGenerator<'a> {
                 self.push_str("#[component(record)]\n");
             }
 
-            if !info.has_list {
+            if info.fixed_size() {
                 self.push_str("#[repr(C)]\n");
-                self.push_str("#[derive(Copy, Clone)]\n");
-            } else {
-                self.push_str("#[derive(Clone)]\n");
             }
+            self.print_derives(id);
             self.push_str(&format!("pub struct {}", name));
             self.print_generics(lt);
             self.push_str(" {\n");
-            for field in record.fields.iter() {
+            for (i, field) in record.fields.iter().enumerate() {
                 self.rustdoc(&field.docs);
                 if derive_component {
                     self.push_str(&format!("#[component(name = \"{}\")]\n", field.name));
                 }
                 self.push_str("pub ");
-                self.push_str(&to_rust_ident(&field.name));
+                self.push_str(&self.names().field(id, i, &field.name));
                 self.push_str(": ");
                 self.print_ty(&field.ty, mode);
                 self.push_str(",\n");
@@ -586,11 +684,11 @@ pub trait RustGenerator<'a> {
                 "fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {\n",
             );
             self.push_str(&format!("f.debug_struct(\"{}\")", name));
-            for field in record.fields.iter() {
+            for (i, field) in record.fields.iter().enumerate() {
                 self.push_str(&format!(
                     ".field(\"{}\", &self.{})",
                     field.name,
-                    to_rust_ident(&field.name)
+                    self.names().field(id, i, &field.name)
                 ));
             }
             self.push_str(".finish()\n");
@@ -645,16 +743,22 @@ pub trait RustGenerator<'a> {
     ) where
         Self: Sized,
     {
-        self.print_rust_enum(
-            id,
-            variant.cases.iter().map(|c| {
+        let cases = variant
+            .cases
+            .iter()
+            .enumerate()
+            .map(|(i, c)| {
                 (
-                    c.name.to_upper_camel_case(),
+                    self.names().case(id, i, &c.name),
                     Some(c.name.clone()),
                     &c.docs,
                     c.ty.as_ref(),
                 )
-            }),
+            })
+            .collect::<Vec<_>>();
+        self.print_rust_enum(
+            id,
+            cases,
             docs,
             if derive_component {
                 Some("variant")
@@ -698,8 +802,6 @@ pub trait RustGenerator<'a> {
         let info = self.info(id);
 
         for (name, mode) in self.modes_of(id) {
-            let name = name.to_upper_camel_case();
-
             self.rustdoc(docs);
             let lt = self.lifetime_for(&info, mode);
             if let Some(derive_component) = derive_component {
@@ -710,11 +812,7 @@ pub trait RustGenerator<'a> {
                 self.push_str("#[derive(wasmtime::component::Lower)]\n");
                 self.push_str(&format!("#[component({})]\n", derive_component));
             }
-            if !info.has_list {
-                self.push_str("#[derive(Clone, Copy)]\n");
-            } else {
-                self.push_str("#[derive(Clone)]\n");
-            }
+            self.print_derives(id);
             self.push_str(&format!("pub enum {name}"));
             self.print_generics(lt);
             self.push_str("{\n");
@@ -847,7 +945,6 @@ pub trait RustGenerator<'a> {
     fn print_typedef_enum(
         &mut self,
         id: TypeId,
-        name: &str,
         enum_: &Enum,
         docs: &Docs,
         attrs: &[String],
@@ -857,19 +954,37 @@ pub trait RustGenerator<'a> {
     {
         let info = self.info(id);
 
-        let name = name.to_upper_camel_case();
+        let name = self.names().type_name(self.resolve(), id);
+        let cases = enum_
+            .cases
+            .iter()
+            .enumerate()
+            .map(|(i, c)| self.names().case(id, i, &c.name))
+            .collect::<Vec<_>>();
         self.rustdoc(docs);
         for attr in attrs {
             self.push_str(&format!("{}\n", attr));
         }
         self.push_str("#[repr(");
         self.int_repr(enum_.tag());
-        self.push_str(")]\n#[derive(Clone, Copy, PartialEq, Eq)]\n");
-        self.push_str(&format!("pub enum {} {{\n", name.to_upper_camel_case()));
-        for case in enum_.cases.iter() {
+        self.push_str(")]\n");
+        self.print_derives_and_attributes(
+            id,
+            &[
+                "Clone",
+                "Copy",
+                "PartialEq",
+                "Eq",
+                "PartialOrd",
+                "Ord",
+                "Hash",
+            ],
+        );
+        self.push_str(&format!("pub enum {} {{\n", name));
+        for (case, case_name) in enum_.cases.iter().zip(&cases) {
             self.rustdoc(&case.docs);
             self.push_str(&case_attr(case));
-            self.push_str(&case.name.to_upper_camel_case());
+            self.push_str(case_name);
             self.push_str(",\n");
         }
         self.push_str("}\n");
@@ -883,10 +998,10 @@ pub trait RustGenerator<'a> {
 
             self.push_str("pub fn name(&self) -> &'static str {\n");
             self.push_str("match self {\n");
-            for case in enum_.cases.iter() {
+            for (case, case_name) in enum_.cases.iter().zip(&cases) {
                 self.push_str(&name);
                 self.push_str("::");
-                self.push_str(&case.name.to_upper_camel_case());
+                self.push_str(case_name);
                 self.push_str(" => \"");
                 self.push_str(case.name.as_str());
                 self.push_str("\",\n");
@@ -896,10 +1011,10 @@ pub trait RustGenerator<'a> {
 
             self.push_str("pub fn message(&self) -> &'static str {\n");
             self.push_str("match self {\n");
-            for case in enum_.cases.iter() {
+            for (case, case_name) in enum_.cases.iter().zip(&cases) {
                 self.push_str(&name);
                 self.push_str("::");
-                self.push_str(&case.name.to_upper_camel_case());
+                self.push_str(case_name);
                 self.push_str(" => \"");
                 if let Some(contents) = &case.docs.contents {
                     self.push_str(contents.trim());
@@ -946,10 +1061,7 @@ pub trait RustGenerator<'a> {
                 id,
                 TypeMode::Owned,
                 &name,
-                enum_
-                    .cases
-                    .iter()
-                    .map(|c| (c.name.to_upper_camel_case(), None)),
+                cases.into_iter().map(|c| (c, None)),
             )
         }
     }
@@ -967,6 +1079,27 @@ pub trait RustGenerator<'a> {
         }
     }
 
+    fn print_typedef_future(&mut self, id: TypeId, payload: Option<&Type>, docs: &Docs) {
+        // Futures can't be passed to functions yet, so they're never used in
+        // a borrowed context and are always printed in their owned form.
+        let name = self.result_name(id);
+        self.rustdoc(docs);
+        self.push_str(&format!("pub type {name} = {}<", self.future_name()));
+        self.print_optional_ty(payload, TypeMode::Owned);
+        self.push_str(">;\n");
+    }
+
+    fn print_typedef_stream(&mut self, id: TypeId, stream: &Stream, docs: &Docs) {
+        // Like futures, streams are always printed in their owned form.
+        let name = self.result_name(id);
+        self.rustdoc(docs);
+        self.push_str(&format!("pub type {name} = {}<", self.stream_name()));
+        self.print_optional_ty(stream.element.as_ref(), TypeMode::Owned);
+        self.push_str(",");
+        self.print_optional_ty(stream.end.as_ref(), TypeMode::Owned);
+        self.push_str(">;\n");
+    }
+
     fn print_type_list(&mut self, id: TypeId, ty: &Type, docs: &Docs) {
         let info = self.info(id);
         for (name, mode) in self.modes_of(id) {
@@ -982,11 +1115,7 @@ pub trait RustGenerator<'a> {
 
     fn param_name(&self, ty: TypeId) -> String {
         let info = self.info(ty);
-        let name = self.resolve().types[ty]
-            .name
-            .as_ref()
-            .unwrap()
-            .to_upper_camel_case();
+        let name = self.names().type_name(self.resolve(), ty);
         if self.uses_two_names(&info) {
             format!("{}Param", name)
         } else {
@@ -996,11 +1125,7 @@ pub trait RustGenerator<'a> {
 
     fn result_name(&self, ty: TypeId) -> String {
         let info = self.info(ty);
-        let name = self.resolve().types[ty]
-            .name
-            .as_ref()
-            .unwrap()
-            .to_upper_camel_case();
+        let name = self.names().type_name(self.resolve(), ty);
         if self.uses_two_names(&info) {
             format!("{}Result", name)
         } else {
@@ -1034,6 +1159,162 @@ pub trait RustGenerator<'a> {
     }
 }
 
+/// Traits to derive for, and attributes to add to, generated types on top of
+/// those the generator emits itself.
+///
+/// Each applies either to every type or to the type at a path, which is
+/// written the same way as for renames, for example `a:b/iface#ty`.
+#[derive(Debug, Clone, Default)]
+pub struct TypeAttributes {
+    derives: Vec<(Option<String>, String)>,
+    attributes: Vec<(Option<String>, String)>,
+}
+
+static NO_TYPE_ATTRIBUTES: TypeAttributes = TypeAttributes {
+    derives: Vec::new(),
+    attributes: Vec::new(),
+};
+
+impl TypeAttributes {
+    /// Parses derives written as `TRAIT` or `PATH=TRAIT`, and attributes
+    /// written as `#[ATTR]` or `PATH=#[ATTR]`.
+    pub fn parse(derives: &[String], attributes: &[String]) -> Result<TypeAttributes, Diagnostic> {
+        let mut ret = TypeAttributes::default();
+        for spec in derives {
+            let (path, derive) = match spec.split_once('=') {
+                Some((path, derive)) => (Some(path.trim().to_string()), derive.trim()),
+                None => (None, spec.trim()),
+            };
+            if derive.is_empty() {
+                return Err(Diagnostic::new(format!(
+                    "invalid derive `{spec}`, expected `[path=]trait`"
+                )));
+            }
+            ret.derives.push((path, derive.to_string()));
+        }
+        for spec in attributes {
+            let (path, attribute) = match spec.trim() {
+                s if s.starts_with("#[") => (None, s),
+                s => match s.split_once('=') {
+                    Some((path, attribute)) => (Some(path.trim().to_string()), attribute.trim()),
+                    None => (None, s),
+                },
+            };
+            if !attribute.starts_with("#[") || !attribute.ends_with(']') {
+                return Err(Diagnostic::new(format!(
+                    "invalid attribute `{spec}`, expected `[path=]#[attribute]`"
+                )));
+            }
+            ret.attributes.push((path, attribute.to_string()));
+        }
+        Ok(ret)
+    }
+
+    /// Returns an error for the first path which doesn't match a type in the
+    /// world that `names` were assigned for.
+    pub fn check(&self, names: &Names) -> Result<(), Diagnostic> {
+        for (path, _) in self.derives.iter().chain(&self.attributes) {
+            if let Some(path) = path {
+                if names.type_at(path).is_none() {
+                    return Err(Diagnostic::new(format!(
+                        "extra derive or attribute for `{path}` doesn't match any type in the world"
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether `Default` is both requested for, and derivable for, the type
+    /// `id`.
+    ///
+    /// Only records and flags can derive it, since an enum or variant would
+    /// need to choose its default case, and records only if every field has a
+    /// default too.
+    fn derives_default(&self, resolve: &Resolve, names: &Names, id: TypeId) -> bool {
+        let requested = || {
+            self.derives_for(names.type_path(id))
+                .any(|d| derive_name(d) == "Default")
+        };
+        match &resolve.types[id].kind {
+            TypeDefKind::Record(record) => {
+                requested()
+                    && record
+                        .fields
+                        .iter()
+                        .all(|f| self.has_default(resolve, names, &f.ty))
+            }
+            TypeDefKind::Flags(_) => requested(),
+            _ => false,
+        }
+    }
+
+    /// Whether the Rust type generated for `ty` implements `Default`.
+    fn has_default(&self, resolve: &Resolve, names: &Names, ty: &Type) -> bool {
+        let id = match ty {
+            Type::Id(id) => *id,
+            _ => return true,
+        };
+        match &resolve.types[id].kind {
+            TypeDefKind::Record(_) | TypeDefKind::Flags(_) => {
+                self.derives_default(resolve, names, id)
+            }
+            TypeDefKind::Tuple(t) => t.types.iter().all(|t| self.has_default(resolve, names, t)),
+            TypeDefKind::Option(_) | TypeDefKind::List(_) => true,
+            TypeDefKind::Type(t) => self.has_default(resolve, names, t),
+            _ => false,
+        }
+    }
+
+    fn derives_for<'a>(&'a self, path: Option<&'a str>) -> impl Iterator<Item = &'a str> {
+        applying_to(&self.derives, path)
+    }
+
+    fn attributes_for<'a>(&'a self, path: Option<&'a str>) -> impl Iterator<Item = &'a str> {
+        applying_to(&self.attributes, path)
+    }
+}
+
+/// Returns the entries of `list` which apply to the type at `path`.
+fn applying_to<'a>(
+    list: &'a [(Option<String>, String)],
+    path: Option<&'a str>,
+) -> impl Iterator<Item = &'a str> {
+    list.iter()
+        .filter(move |(p, _)| p.is_none() || p.as_deref() == path)
+        .map(|(_, s)| s.as_str())
+}
+
+/// Returns the name of the derived trait at `path` if it's in the standard
+/// library, so that `std::hash::Hash` and `Hash` are treated the same, and
+/// `path` itself otherwise.
+fn derive_name(path: &str) -> &str {
+    let path = path.trim_start_matches("::");
+    if path.starts_with("std::") || path.starts_with("core::") {
+        path.rsplit("::").next().unwrap()
+    } else {
+        path
+    }
+}
+
+/// Whether the standard library trait `name` can be derived for a type
+/// described by `info`, where `ordered` is whether any flags it contains
+/// can be ordered and hashed. Other traits are assumed to be derivable.
+fn derive_is_valid(name: &str, info: &TypeInfo, derives_default: bool, ordered: bool) -> bool {
+    match name {
+        "Clone" => !info.has_handle,
+        "Copy" => info.fixed_size(),
+        "PartialEq" => !info.has_handle,
+        "PartialOrd" => !info.has_handle && ordered,
+        "Eq" => info.total_eq(),
+        "Ord" | "Hash" => info.total_eq() && ordered,
+        "Default" => derives_default,
+        // Generated types always implement `Debug` already.
+        "Debug" => false,
+        _ => true,
+    }
+}
+
 #[derive(Default)]
 pub struct FnSig {
     pub async_: bool,
@@ -1088,8 +1369,8 @@ pub trait RustFunctionGenerator {
         let name = self.typename_lower(id);
         self.push_str(&name);
         self.push_str("{ ");
-        for field in record.fields.iter() {
-            let name = to_rust_ident(&field.name);
+        for (i, field) in record.fields.iter().enumerate() {
+            let name = self.rust_gen().names().field(id, i, &field.name);
             let arg = format!("{}{}", name, tmp);
             self.push_str(&name);
             self.push_str(":");
@@ -1111,8 +1392,8 @@ pub trait RustFunctionGenerator {
     ) {
         let mut result = self.typename_lift(id);
         result.push_str("{");
-        for (field, val) in ty.fields.iter().zip(operands) {
-            result.push_str(&to_rust_ident(&field.name));
+        for (i, (field, val)) in ty.fields.iter().zip(operands).enumerate() {
+            result.push_str(&self.rust_gen().names().field(id, i, &field.name));
             result.push_str(":");
             result.push_str(&val);
             result.push_str(", ");
@@ -1157,61 +1438,7 @@ pub trait RustFunctionGenerator {
 }
 
 pub fn to_rust_ident(name: &str) -> String {
-    match name {
-        // Escape Rust keywords.
-        // Source: https://doc.rust-lang.org/reference/keywords.html
-        "as" => "as_".into(),
-        "break" => "break_".into(),
-        "const" => "const_".into(),
-        "continue" => "continue_".into(),
-        "crate" => "crate_".into(),
-        "else" => "else_".into(),
-        "enum" => "enum_".into(),
-        "extern" => "extern_".into(),
-        "false" => "false_".into(),
-        "fn" => "fn_".into(),
-        "for" => "for_".into(),
-        "if" => "if_".into(),
-        "impl" => "impl_".into(),
-        "in" => "in_".into(),
-        "let" => "let_".into(),
-        "loop" => "loop_".into(),
-        "match" => "match_".into(),
-        "mod" => "mod_".into(),
-        "move" => "move_".into(),
-        "mut" => "mut_".into(),
-        "pub" => "pub_".into(),
-        "ref" => "ref_".into(),
-        "return" => "return_".into(),
-        "self" => "self_".into(),
-        "static" => "static_".into(),
-        "struct" => "struct_".into(),
-        "super" => "super_".into(),
-        "trait" => "trait_".into(),
-        "true" => "true_".into(),
-        "type" => "type_".into(),
-        "unsafe" => "unsafe_".into(),
-        "use" => "use_".into(),
-        "where" => "where_".into(),
-        "while" => "while_".into(),
-        "async" => "async_".into(),
-        "await" => "await_".into(),
-        "dyn" => "dyn_".into(),
-        "abstract" => "abstract_".into(),
-        "become" => "become_".into(),
-        "box" => "box_".into(),
-        "do" => "do_".into(),
-        "final" => "final_".into(),
-        "macro" => "macro_".into(),
-        "override" => "override_".into(),
-        "priv" => "priv_".into(),
-        "typeof" => "typeof_".into(),
-        "unsized" => "unsized_".into(),
-        "virtual" => "virtual_".into(),
-        "yield" => "yield_".into(),
-        "try" => "try_".into(),
-        s => s.to_snake_case(),
-    }
+    Profile::RUST.ident(name, Casing::Snake)
 }
 
 pub fn wasm_type(ty: WasmType) -> &'static str {
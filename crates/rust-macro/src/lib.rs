@@ -4,6 +4,8 @@ use syn::parse::{Error, Parse, ParseStream, Result};
 use syn::punctuated::Punctuated;
 use syn::{token, Token};
 use wit_bindgen_core::wit_parser::{PackageId, Resolve, UnresolvedPackage, WorldId};
+use wit_bindgen_core::{render_error, ErrorSpan, Filter};
+use wit_bindgen_deps::LockMode;
 use wit_bindgen_rust::Opts;
 
 #[proc_macro]
@@ -16,6 +18,7 @@ pub fn generate(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
 
 struct Config {
     opts: Opts,
+    filter: Filter,
     resolve: Resolve,
     world: WorldId,
     files: Vec<PathBuf>,
@@ -23,15 +26,17 @@ struct Config {
 
 enum Source {
     Path(String),
-    Inline(String),
+    Inline(syn::LitStr),
 }
 
 impl Parse for Config {
     fn parse(input: ParseStream<'_>) -> Result<Self> {
-        let call_site = Span::call_site();
         let mut opts = Opts::default();
         let mut world = None;
         let mut source = None;
+        let mut locked = false;
+        let mut only = Vec::new();
+        let mut exclude = Vec::new();
 
         if input.peek(token::Brace) {
             let content;
@@ -55,15 +60,27 @@ impl Parse for Config {
                         if source.is_some() {
                             return Err(Error::new(s.span(), "cannot specify second source"));
                         }
-                        source = Some(Source::Inline(s.value()));
+                        source = Some(Source::Inline(s));
                     }
                     Opt::UseStdFeature => opts.std_feature = true,
                     Opt::RawStrings => opts.raw_strings = true,
+                    Opt::MockImports => opts.mock_imports = true,
                     Opt::MacroExport => opts.macro_export = true,
                     Opt::DuplicateIfNecessary => opts.duplicate_if_necessary = true,
                     Opt::MacroCallPrefix(prefix) => opts.macro_call_prefix = Some(prefix.value()),
                     Opt::ExportMacroName(name) => opts.export_macro_name = Some(name.value()),
                     Opt::Skip(list) => opts.skip.extend(list.iter().map(|i| i.value())),
+                    Opt::Rename(list) => opts.rename.extend(list.iter().map(|i| i.value())),
+                    Opt::Only(list) => only.extend(list.iter().map(|i| i.value())),
+                    Opt::Exclude(list) => exclude.extend(list.iter().map(|i| i.value())),
+                    Opt::AdditionalDerives(list) => opts
+                        .additional_derives
+                        .extend(list.iter().map(|i| i.value())),
+                    Opt::AdditionalAttributes(list) => opts
+                        .additional_attributes
+                        .extend(list.iter().map(|i| i.value())),
+                    Opt::With(list) => opts.with.extend(list.iter().map(|i| i.value())),
+                    Opt::Locked => locked = true,
                 }
             }
         } else {
@@ -72,13 +89,15 @@ impl Parse for Config {
                 source = Some(Source::Path(input.parse::<syn::LitStr>()?.value()));
             }
         }
+        let filter = Filter::new(only, exclude).map_err(|e| Error::new(Span::call_site(), e))?;
         let (resolve, pkg, files) =
-            parse_source(&source).map_err(|err| Error::new(call_site, format!("{err:?}")))?;
+            parse_source(&source, locked).map_err(|e| wit_error(&e, &source))?;
         let world = resolve
             .select_world(pkg, world.as_deref())
-            .map_err(|e| Error::new(call_site, format!("{e:?}")))?;
+            .map_err(|e| wit_error(&e, &source))?;
         Ok(Config {
             opts,
+            filter,
             resolve,
             world,
             files,
@@ -86,24 +105,28 @@ impl Parse for Config {
     }
 }
 
-fn parse_source(source: &Option<Source>) -> anyhow::Result<(Resolve, PackageId, Vec<PathBuf>)> {
+fn parse_source(
+    source: &Option<Source>,
+    locked: bool,
+) -> anyhow::Result<(Resolve, PackageId, Vec<PathBuf>)> {
     let mut resolve = Resolve::default();
     let mut files = Vec::new();
     let root = PathBuf::from(std::env::var("CARGO_MANIFEST_DIR").unwrap());
+    // Dependencies listed in a `deps.toml` next to the WIT are pushed first.
+    // Compiling never updates their lockfile, which only the CLI does.
+    let mode = if locked {
+        LockMode::Locked
+    } else {
+        LockMode::ReadOnly
+    };
     let mut parse = |path: &Path| -> anyhow::Result<_> {
-        if path.is_dir() {
-            let (pkg, sources) = resolve.push_dir(&path)?;
-            files = sources;
-            Ok(pkg)
-        } else {
-            let pkg = UnresolvedPackage::parse_file(path)?;
-            files.extend(pkg.source_files().map(|s| s.to_owned()));
-            resolve.push(pkg)
-        }
+        let (pkg, sources) = wit_bindgen_deps::push_path(&mut resolve, path, mode)?;
+        files = sources;
+        Ok(pkg)
     };
     let pkg = match source {
         Some(Source::Inline(s)) => {
-            resolve.push(UnresolvedPackage::parse("macro-input".as_ref(), &s)?)?
+            resolve.push(UnresolvedPackage::parse(INLINE.as_ref(), &s.value())?)?
         }
         Some(Source::Path(s)) => parse(&root.join(&s))?,
         None => parse(&root.join("wit"))?,
@@ -112,23 +135,67 @@ fn parse_source(source: &Option<Source>) -> anyhow::Result<(Resolve, PackageId,
     Ok((resolve, pkg, files))
 }
 
+/// The path WIT from the `inline` option is parsed as, which errors in it are
+/// reported at.
+const INLINE: &str = "macro-input";
+
+/// Converts an error from loading WIT into a compile error.
+///
+/// Errors in inline WIT point at where they are in the string literal, as
+/// precisely as the compiler allows, and everything else points at the macro.
+fn wit_error(err: &anyhow::Error, source: &Option<Source>) -> Error {
+    if let (Some(span), Some(Source::Inline(lit))) = (ErrorSpan::find(err), source) {
+        if span.path == Path::new(INLINE) {
+            return match lit_subspan(lit, &span) {
+                Some(subspan) => Error::new(subspan, span.message),
+                None => Error::new(lit.span(), render_error(err)),
+            };
+        }
+    }
+    Error::new(Span::call_site(), render_error(err))
+}
+
+/// Returns the span of the part of `lit` which `span` is about.
+///
+/// This is `None` if the compiler doesn't support spans within literals, or if
+/// `lit` contains escapes which make offsets in its value differ from those in
+/// its source.
+fn lit_subspan(lit: &syn::LitStr, span: &ErrorSpan) -> Option<Span> {
+    let value = lit.value();
+    let range = span.range(&value)?;
+    let token = lit.token();
+    let repr = token.to_string();
+    // The value starts after the opening quote, which follows the `r` and `#`s
+    // of a raw string.
+    let start = repr.find('"')? + 1;
+    if repr.get(start..start + value.len())? != value {
+        return None;
+    }
+    token.subspan(start + range.start..start + range.end)
+}
+
 impl Config {
     fn expand(self) -> Result<TokenStream> {
         let mut files = Default::default();
         self.opts
             .build()
-            .generate(&self.resolve, self.world, &mut files);
+            .generate(&self.resolve, self.world, &self.filter, &mut files)
+            .map_err(|e| Error::new(Span::call_site(), e))?;
         let (_, src) = files.iter().next().unwrap();
         let src = std::str::from_utf8(src).unwrap();
         let mut contents = src.parse::<TokenStream>().unwrap();
 
-        // Include a dummy `include_str!` for any files we read so rustc knows that
-        // we depend on the contents of those files.
+        // Include a dummy `include_bytes!` for any files we read so rustc knows
+        // that we depend on the contents of those files. Dependencies may be
+        // tarballs, so they aren't necessarily text.
         for file in self.files.iter() {
             contents.extend(
-                format!("const _: &str = include_str!(r#\"{}\"#);\n", file.display())
-                    .parse::<TokenStream>()
-                    .unwrap(),
+                format!(
+                    "const _: &[u8] = include_bytes!(r#\"{}\"#);\n",
+                    file.display()
+                )
+                .parse::<TokenStream>()
+                .unwrap(),
             );
         }
 
@@ -139,14 +206,22 @@ impl Config {
 mod kw {
     syn::custom_keyword!(std_feature);
     syn::custom_keyword!(raw_strings);
+    syn::custom_keyword!(mock_imports);
     syn::custom_keyword!(macro_export);
     syn::custom_keyword!(macro_call_prefix);
     syn::custom_keyword!(export_macro_name);
     syn::custom_keyword!(skip);
+    syn::custom_keyword!(rename);
+    syn::custom_keyword!(only);
+    syn::custom_keyword!(exclude);
+    syn::custom_keyword!(additional_derives);
+    syn::custom_keyword!(additional_attributes);
+    syn::custom_keyword!(with);
     syn::custom_keyword!(world);
     syn::custom_keyword!(path);
     syn::custom_keyword!(inline);
     syn::custom_keyword!(duplicate_if_necessary);
+    syn::custom_keyword!(locked);
 }
 
 enum Opt {
@@ -155,11 +230,19 @@ enum Opt {
     Inline(syn::LitStr),
     UseStdFeature,
     RawStrings,
+    MockImports,
     MacroExport,
     MacroCallPrefix(syn::LitStr),
     ExportMacroName(syn::LitStr),
     Skip(Vec<syn::LitStr>),
+    Rename(Vec<syn::LitStr>),
+    Only(Vec<syn::LitStr>),
+    Exclude(Vec<syn::LitStr>),
+    AdditionalDerives(Vec<syn::LitStr>),
+    AdditionalAttributes(Vec<syn::LitStr>),
+    With(Vec<syn::LitStr>),
     DuplicateIfNecessary,
+    Locked,
 }
 
 impl Parse for Opt {
@@ -183,12 +266,18 @@ impl Parse for Opt {
         } else if l.peek(kw::raw_strings) {
             input.parse::<kw::raw_strings>()?;
             Ok(Opt::RawStrings)
+        } else if l.peek(kw::mock_imports) {
+            input.parse::<kw::mock_imports>()?;
+            Ok(Opt::MockImports)
         } else if l.peek(kw::macro_export) {
             input.parse::<kw::macro_export>()?;
             Ok(Opt::MacroExport)
         } else if l.peek(kw::duplicate_if_necessary) {
             input.parse::<kw::duplicate_if_necessary>()?;
             Ok(Opt::DuplicateIfNecessary)
+        } else if l.peek(kw::locked) {
+            input.parse::<kw::locked>()?;
+            Ok(Opt::Locked)
         } else if l.peek(kw::macro_call_prefix) {
             input.parse::<kw::macro_call_prefix>()?;
             input.parse::<Token![:]>()?;
@@ -204,6 +293,48 @@ impl Parse for Opt {
             syn::bracketed!(contents in input);
             let list = Punctuated::<_, Token![,]>::parse_terminated(&contents)?;
             Ok(Opt::Skip(list.iter().cloned().collect()))
+        } else if l.peek(kw::rename) {
+            input.parse::<kw::rename>()?;
+            input.parse::<Token![:]>()?;
+            let contents;
+            syn::bracketed!(contents in input);
+            let list = Punctuated::<_, Token![,]>::parse_terminated(&contents)?;
+            Ok(Opt::Rename(list.iter().cloned().collect()))
+        } else if l.peek(kw::only) {
+            input.parse::<kw::only>()?;
+            input.parse::<Token![:]>()?;
+            let contents;
+            syn::bracketed!(contents in input);
+            let list = Punctuated::<_, Token![,]>::parse_terminated(&contents)?;
+            Ok(Opt::Only(list.iter().cloned().collect()))
+        } else if l.peek(kw::exclude) {
+            input.parse::<kw::exclude>()?;
+            input.parse::<Token![:]>()?;
+            let contents;
+            syn::bracketed!(contents in input);
+            let list = Punctuated::<_, Token![,]>::parse_terminated(&contents)?;
+            Ok(Opt::Exclude(list.iter().cloned().collect()))
+        } else if l.peek(kw::additional_derives) {
+            input.parse::<kw::additional_derives>()?;
+            input.parse::<Token![:]>()?;
+            let contents;
+            syn::bracketed!(contents in input);
+            let list = Punctuated::<_, Token![,]>::parse_terminated(&contents)?;
+            Ok(Opt::AdditionalDerives(list.iter().cloned().collect()))
+        } else if l.peek(kw::additional_attributes) {
+            input.parse::<kw::additional_attributes>()?;
+            input.parse::<Token![:]>()?;
+            let contents;
+            syn::bracketed!(contents in input);
+            let list = Punctuated::<_, Token![,]>::parse_terminated(&contents)?;
+            Ok(Opt::AdditionalAttributes(list.iter().cloned().collect()))
+        } else if l.peek(kw::with) {
+            input.parse::<kw::with>()?;
+            input.parse::<Token![:]>()?;
+            let contents;
+            syn::bracketed!(contents in input);
+            let list = Punctuated::<_, Token![,]>::parse_terminated(&contents)?;
+            Ok(Opt::With(list.iter().cloned().collect()))
         } else {
             Err(l.error())
         }
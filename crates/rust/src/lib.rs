@@ -1,19 +1,20 @@
 use heck::*;
 use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fmt::Write as _;
-use std::io::{Read, Write};
 use std::mem;
-use std::process::{Command, Stdio};
 use wit_bindgen_core::wit_parser::abi::{AbiVariant, Bindgen, Instruction, LiftLower, WasmType};
 use wit_bindgen_core::{
-    uwrite, uwriteln, wit_parser::*, Files, InterfaceGenerator as _, Source, TypeInfo, Types,
+    reject_async_functions, uwrite, uwriteln, wit_parser::*, Diagnostic, FileInfo, Files,
+    InterfaceGenerator as _, Names, Profile, Renames, Source, Style, TypeInfo, Types,
     WorldGenerator,
 };
 use wit_bindgen_rust_lib::{
-    int_repr, to_rust_ident, wasm_type, FnSig, RustFlagsRepr, RustFunctionGenerator, RustGenerator,
-    TypeMode,
+    int_repr, wasm_type, FnSig, RustFlagsRepr, RustFunctionGenerator, RustGenerator,
+    TypeAttributes, TypeMode,
 };
 
+const PRODUCER: &str = "wit-bindgen-rust";
+
 #[derive(Default)]
 struct RustWasm {
     types: Types,
@@ -24,12 +25,16 @@ struct RustWasm {
     export_modules: BTreeMap<Option<PackageName>, Vec<String>>,
     skip: HashSet<String>,
     interface_names: HashMap<InterfaceId, String>,
+    names: Names,
+    type_attributes: TypeAttributes,
+    with: ExternalPaths,
+    stub: Source,
 }
 
 #[derive(Default, Debug, Clone)]
 #[cfg_attr(feature = "clap", derive(clap::Args))]
 pub struct Opts {
-    /// Whether or not `rustfmt` is executed to format generated code.
+    /// Deprecated and has no effect: generated code is always formatted.
     #[cfg_attr(feature = "clap", arg(long))]
     pub rustfmt: bool,
 
@@ -66,6 +71,10 @@ pub struct Opts {
     pub export_macro_name: Option<String>,
 
     /// Names of functions to skip generating bindings for.
+    ///
+    /// Unlike the `--exclude` option shared by all generators, this keeps the
+    /// functions in the world, including the component type embedded in the
+    /// bindings, and only leaves out their glue code.
     #[cfg_attr(feature = "clap", arg(long))]
     pub skip: Vec<String>,
 
@@ -74,6 +83,65 @@ pub struct Opts {
     /// export.
     #[cfg_attr(feature = "clap", arg(long))]
     pub duplicate_if_necessary: bool,
+
+    /// Overrides the Rust identifier used for an item, in the form
+    /// `PATH=NAME`, for example `a:b/iface#ty.field=renamed`. May be specified
+    /// multiple times.
+    #[cfg_attr(feature = "clap", arg(long = "rename", value_name = "PATH=NAME"))]
+    pub rename: Vec<String>,
+
+    /// Additional traits to derive for generated types, in the form `TRAIT`
+    /// for every type or `PATH=TRAIT` for one, for example
+    /// `a:b/iface#ty=serde::Serialize`. May be specified multiple times.
+    ///
+    /// Traits from the standard library are only derived for types which
+    /// can implement them, so for example `Eq` is left out for records
+    /// containing floats.
+    #[cfg_attr(
+        feature = "clap",
+        arg(long = "additional-derive", value_name = "[PATH=]TRAIT")
+    )]
+    pub additional_derives: Vec<String>,
+
+    /// Additional attributes to add to generated types, in the form `#[ATTR]`
+    /// for every type or `PATH=#[ATTR]` for one. May be specified multiple
+    /// times.
+    #[cfg_attr(
+        feature = "clap",
+        arg(long = "additional-attribute", value_name = "[PATH=]#[ATTR]")
+    )]
+    pub additional_attributes: Vec<String>,
+
+    /// Uses existing Rust code for an interface or type instead of generating
+    /// it, in the form `KEY=PATH`, for example `a:b/types=shared::types` or
+    /// `a:b/types#point=shared::Point`. May be specified multiple times.
+    ///
+    /// This allows several crates to share the types generated once by
+    /// another crate. The paths must be absolute, such as `crate::...` or
+    /// starting with the name of a crate, and the types at them must have been
+    /// generated in their owned form, which is the case for a world that only
+    /// imports the interface defining them.
+    #[cfg_attr(feature = "clap", arg(long = "with", value_name = "KEY=PATH"))]
+    pub with: Vec<String>,
+
+    /// Whether or not to generate a stub implementation of the exports,
+    /// `{world}_stub.rs`, as a starting point for a component.
+    ///
+    /// The stub expects the bindings to be reachable through
+    /// `--macro-call-prefix`, or to be in the same module otherwise.
+    #[cfg_attr(feature = "clap", arg(long))]
+    pub generate_stub: bool,
+
+    /// Whether or not imports call into mocks installed at runtime when the
+    /// bindings aren't compiled for wasm, so that a guest can be unit tested
+    /// natively.
+    ///
+    /// Each interface with imported functions gets a `Mock` trait, a
+    /// `set_mock` function to install one for the current thread, and a
+    /// `RecordingMock` which records the calls made through it. This requires
+    /// the `mock` feature of `wit-bindgen`, and `std` on those targets.
+    #[cfg_attr(feature = "clap", arg(long))]
+    pub mock_imports: bool,
 }
 
 impl Opts {
@@ -85,9 +153,76 @@ impl Opts {
     }
 }
 
+/// The existing Rust code used for interfaces and types with `Opts::with`.
+#[derive(Default)]
+struct ExternalPaths {
+    interfaces: HashMap<InterfaceId, String>,
+    types: HashMap<TypeId, String>,
+}
+
+impl ExternalPaths {
+    fn new(
+        with: &[String],
+        resolve: &Resolve,
+        world: WorldId,
+        names: &Names,
+    ) -> Result<ExternalPaths, Diagnostic> {
+        let mut ret = ExternalPaths::default();
+        let world = &resolve.worlds[world];
+        for spec in with {
+            let (key, path) = match spec.split_once('=') {
+                Some((key, path)) if !path.trim().is_empty() => (key.trim(), path.trim()),
+                _ => {
+                    return Err(Diagnostic::new(format!(
+                        "invalid mapping `{spec}`, expected `key=path`"
+                    )))
+                }
+            };
+            if let Some(id) = names.type_at(key) {
+                ret.types.insert(id, path.to_string());
+                continue;
+            }
+            let interface = world
+                .imports
+                .iter()
+                .chain(&world.exports)
+                .find_map(|(name, item)| match item {
+                    WorldItem::Interface(id) if resolve.name_world_key(name) == key => Some(*id),
+                    _ => None,
+                });
+            match interface {
+                Some(id) => {
+                    ret.interfaces.insert(id, path.to_string());
+                }
+                None => {
+                    return Err(Diagnostic::new(format!(
+                        "`{key}` doesn't match any interface or type in the world"
+                    )))
+                }
+            }
+        }
+        Ok(ret)
+    }
+
+    /// Whether the type `id` is defined by existing code, either itself or as
+    /// part of its interface.
+    fn is_mapped(&self, resolve: &Resolve, id: TypeId) -> bool {
+        if self.types.contains_key(&id) {
+            return true;
+        }
+        match resolve.types[id].owner {
+            TypeOwner::Interface(i) => self.interfaces.contains_key(&i),
+            _ => false,
+        }
+    }
+}
+
 impl RustWasm {
     fn new() -> RustWasm {
-        RustWasm::default()
+        RustWasm {
+            src: Source::new(Style::RUST),
+            ..RustWasm::default()
+        }
     }
 
     fn interface<'a>(
@@ -109,6 +244,9 @@ impl RustWasm {
             resolve,
             return_pointer_area_size: 0,
             return_pointer_area_align: 0,
+            stub: false,
+            mock_methods: Source::default(),
+            recording_methods: Source::default(),
         }
     }
 
@@ -145,13 +283,23 @@ impl RustWasm {
 }
 
 impl WorldGenerator for RustWasm {
-    fn preprocess(&mut self, resolve: &Resolve, _world: WorldId) {
+    fn preprocess(&mut self, resolve: &Resolve, world: WorldId) -> Result<(), Diagnostic> {
+        reject_async_functions(resolve, world, "Rust")?;
         let version = env!("CARGO_PKG_VERSION");
         uwriteln!(
             self.src,
             "// Generated by `wit-bindgen` {version}. DO NOT EDIT!"
         );
         self.types.analyze(resolve);
+        self.names = Names::new(Profile::RUST, Renames::parse(&self.opts.rename)?);
+        self.names.assign(resolve, world)?;
+        self.type_attributes = TypeAttributes::parse(
+            &self.opts.additional_derives,
+            &self.opts.additional_attributes,
+        )?;
+        self.type_attributes.check(&self.names)?;
+        self.with = ExternalPaths::new(&self.opts.with, resolve, world, &self.names)?;
+        Ok(())
     }
 
     fn import_interface(
@@ -160,17 +308,19 @@ impl WorldGenerator for RustWasm {
         name: &WorldKey,
         id: InterfaceId,
         _files: &mut Files,
-    ) {
+    ) -> Result<(), Diagnostic> {
         let wasm_import_module = resolve.name_world_key(name);
         let mut gen = self.interface(Some(&wasm_import_module), resolve, true);
         gen.current_interface = Some((id, name));
-        gen.types(id);
+        gen.types(id)?;
 
         for (_, func) in resolve.interfaces[id].functions.iter() {
             gen.generate_guest_import(func);
         }
+        gen.finish_mocks();
 
         gen.finish_append_submodule(name);
+        Ok(())
     }
 
     fn import_funcs(
@@ -179,15 +329,17 @@ impl WorldGenerator for RustWasm {
         _world: WorldId,
         funcs: &[(&str, &Function)],
         _files: &mut Files,
-    ) {
+    ) -> Result<(), Diagnostic> {
         let mut gen = self.interface(Some("$root"), resolve, true);
 
         for (_, func) in funcs {
             gen.generate_guest_import(func);
         }
+        gen.finish_mocks();
 
         let src = gen.finish();
         self.src.push_str(&src);
+        Ok(())
     }
 
     fn export_interface(
@@ -196,10 +348,10 @@ impl WorldGenerator for RustWasm {
         name: &WorldKey,
         id: InterfaceId,
         _files: &mut Files,
-    ) {
+    ) -> Result<(), Diagnostic> {
         let mut gen = self.interface(None, resolve, false);
         gen.current_interface = Some((id, name));
-        gen.types(id);
+        gen.types(id)?;
         let trait_name = match name {
             WorldKey::Name(name) => name.to_upper_camel_case(),
             WorldKey::Interface(id) => resolve.interfaces[*id]
@@ -214,6 +366,17 @@ impl WorldGenerator for RustWasm {
             resolve.interfaces[id].functions.values(),
         );
         gen.finish_append_submodule(name);
+
+        if self.opts.generate_stub {
+            let trait_path = format!("{}::{trait_name}", self.interface_names[&id]);
+            let mut gen = self.interface(None, resolve, false);
+            gen.current_interface = Some((id, name));
+            gen.stub = true;
+            gen.generate_stub(&trait_path, resolve.interfaces[id].functions.values());
+            let src = gen.finish();
+            self.stub.push_str(&src);
+        }
+        Ok(())
     }
 
     fn export_funcs(
@@ -222,12 +385,21 @@ impl WorldGenerator for RustWasm {
         world: WorldId,
         funcs: &[(&str, &Function)],
         _files: &mut Files,
-    ) {
+    ) -> Result<(), Diagnostic> {
         let trait_name = &resolve.worlds[world].name.to_upper_camel_case();
         let mut gen = self.interface(None, resolve, false);
         gen.generate_exports(&trait_name, None, funcs.iter().map(|f| f.1));
         let src = gen.finish();
         self.src.push_str(&src);
+
+        if self.opts.generate_stub {
+            let mut gen = self.interface(None, resolve, false);
+            gen.stub = true;
+            gen.generate_stub(trait_name, funcs.iter().map(|f| f.1));
+            let src = gen.finish();
+            self.stub.push_str(&src);
+        }
+        Ok(())
     }
 
     fn export_types(
@@ -236,16 +408,24 @@ impl WorldGenerator for RustWasm {
         _world: WorldId,
         types: &[(&str, TypeId)],
         _files: &mut Files,
-    ) {
+    ) -> Result<(), Diagnostic> {
         let mut gen = self.interface(None, resolve, false);
         for (name, ty) in types {
-            gen.define_type(name, *ty);
+            if !gen.gen.with.is_mapped(resolve, *ty) {
+                gen.define_type(name, *ty)?;
+            }
         }
         let src = gen.finish();
         self.src.push_str(&src);
+        Ok(())
     }
 
-    fn finish(&mut self, resolve: &Resolve, world: WorldId, files: &mut Files) {
+    fn finish(
+        &mut self,
+        resolve: &Resolve,
+        world: WorldId,
+        files: &mut Files,
+    ) -> Result<(), Diagnostic> {
         let name = &resolve.worlds[world].name;
         if !self.exports.is_empty() {
             let macro_name = if let Some(name) = self.opts.export_macro_name.as_ref() {
@@ -317,7 +497,7 @@ impl WorldGenerator for RustWasm {
             wit_component::StringEncoding::UTF8,
             Some(&producers),
         )
-        .unwrap();
+        .map_err(|e| Diagnostic::new(format!("failed to encode component type: {e:?}")))?;
 
         self.src.push_str("#[doc(hidden)]\n");
         self.src.push_str(&format!(
@@ -335,33 +515,49 @@ impl WorldGenerator for RustWasm {
         ",
         );
 
-        let mut src = mem::take(&mut self.src);
-        if self.opts.rustfmt {
-            let mut child = Command::new("rustfmt")
-                .arg("--edition=2018")
-                .stdin(Stdio::piped())
-                .stdout(Stdio::piped())
-                .spawn()
-                .expect("failed to spawn `rustfmt`");
-            child
-                .stdin
-                .take()
-                .unwrap()
-                .write_all(src.as_bytes())
-                .unwrap();
-            src.as_mut_string().truncate(0);
-            child
-                .stdout
-                .take()
-                .unwrap()
-                .read_to_string(src.as_mut_string())
-                .unwrap();
-            let status = child.wait().unwrap();
-            assert!(status.success());
+        let src = mem::take(&mut self.src);
+        let module_name = name.to_snake_case();
+        files.create(
+            &format!("{module_name}.rs"),
+            FileInfo::text(PRODUCER, "rust"),
+            src.as_bytes(),
+        )?;
+
+        if self.opts.generate_stub {
+            self.finish_stub(name, files)?;
         }
+        Ok(())
+    }
+}
 
-        let module_name = name.to_snake_case();
-        files.push(&format!("{module_name}.rs"), src.as_bytes());
+impl RustWasm {
+    /// Writes `{world}_stub.rs`, which implements every export with a
+    /// placeholder body.
+    fn finish_stub(&mut self, world: &str, files: &mut Files) -> Result<(), Diagnostic> {
+        let version = env!("CARGO_PKG_VERSION");
+        let mut src = Source::new(Style::RUST);
+        uwriteln!(src, "// Generated by `wit-bindgen` {version}.");
+        if let Some(prefix) = &self.opts.macro_call_prefix {
+            uwriteln!(src, "#[allow(unused_imports)]");
+            uwriteln!(src, "use {prefix}*;");
+        }
+        let macro_name = match &self.opts.export_macro_name {
+            Some(name) => name.to_snake_case(),
+            None => format!("export_{}", world.to_snake_case()),
+        };
+        if !self.stub.is_empty() {
+            uwriteln!(src, "struct Component;");
+        }
+        if !self.exports.is_empty() {
+            uwriteln!(src, "{macro_name}!(Component);");
+        }
+        src.push_str(&mem::take(&mut self.stub));
+        files.create(
+            &format!("{}_stub.rs", world.to_snake_case()),
+            FileInfo::text(PRODUCER, "rust"),
+            src.as_bytes(),
+        )?;
+        Ok(())
     }
 }
 
@@ -375,6 +571,13 @@ struct InterfaceGenerator<'a> {
     resolve: &'a Resolve,
     return_pointer_area_size: usize,
     return_pointer_area_align: usize,
+    /// Whether code is being generated for the stub, outside of the bindings,
+    /// where interfaces are referred to by their full path.
+    stub: bool,
+    /// The methods of the `Mock` trait and of its implementation for
+    /// `RecordingMock`, for `Opts::mock_imports`.
+    mock_methods: Source,
+    recording_methods: Source,
 }
 
 impl InterfaceGenerator<'_> {
@@ -401,6 +604,26 @@ impl InterfaceGenerator<'_> {
         }
     }
 
+    /// Implements the trait at `trait_path` for the stub's `Component` type,
+    /// leaving functions without results empty and the rest unimplemented.
+    fn generate_stub<'a>(&mut self, trait_path: &str, funcs: impl Iterator<Item = &'a Function>) {
+        uwriteln!(self.src, "#[allow(unused_variables)]");
+        uwriteln!(self.src, "impl {trait_path} for Component {{");
+        for func in funcs {
+            if self.gen.skip.contains(&func.name) {
+                continue;
+            }
+            let mut sig = FnSig::default();
+            sig.private = true;
+            self.print_signature(func, TypeMode::Owned, &sig);
+            match func.results.len() {
+                0 => self.src.push_str(" {}\n"),
+                _ => self.src.push_str(" {\nunimplemented!()\n}\n"),
+            }
+        }
+        uwriteln!(self.src, "}}");
+    }
+
     fn finish(&mut self) -> String {
         if self.return_pointer_area_align > 0 {
             uwrite!(
@@ -423,12 +646,7 @@ impl InterfaceGenerator<'_> {
 
     fn finish_append_submodule(mut self, name: &WorldKey) {
         let module = self.finish();
-        let snake = match name {
-            WorldKey::Name(name) => to_rust_ident(name),
-            WorldKey::Interface(id) => {
-                to_rust_ident(self.resolve.interfaces[*id].name.as_ref().unwrap())
-            }
-        };
+        let snake = self.gen.names.module(self.resolve, name, self.in_import);
         let mut path_to_root = String::from("super::");
         let pkg = match name {
             WorldKey::Name(_) => None,
@@ -485,6 +703,10 @@ impl InterfaceGenerator<'_> {
         match &func.kind {
             FunctionKind::Freestanding => {}
         }
+        if self.gen.opts.mock_imports {
+            self.generate_guest_import_mock(func, &sig, param_mode);
+            self.src.push_str("#[cfg(target_arch = \"wasm32\")]\n");
+        }
         self.src.push_str("#[allow(clippy::all)]\n");
         let params = self.print_signature(func, param_mode, &sig);
         self.src.push_str("{\n");
@@ -534,6 +756,136 @@ impl InterfaceGenerator<'_> {
         }
     }
 
+    /// Generates the version of the import `func` used when the bindings
+    /// aren't compiled for wasm, which calls the installed mock, along with
+    /// its methods for `finish_mocks`.
+    fn generate_guest_import_mock(&mut self, func: &Function, sig: &FnSig, param_mode: TypeMode) {
+        let name = self.gen.names.function(self.interface_id(), func);
+        let path = match self.current_interface {
+            Some((_, key)) => format!("{}#{}", self.resolve.name_world_key(key), func.name),
+            None => func.name.clone(),
+        };
+
+        self.src
+            .push_str("#[cfg(not(target_arch = \"wasm32\"))]\n#[allow(clippy::all)]\n");
+        let params = self.print_signature(func, param_mode, sig);
+        let args = params.join(", ");
+        uwriteln!(
+            self.src,
+            "{{\nwith_mock(|__mock| __mock.{name}({args}))\n}}"
+        );
+
+        let method_sig = FnSig {
+            private: true,
+            self_arg: Some("&mut self".to_string()),
+            ..FnSig::default()
+        };
+        let prev = mem::take(&mut self.src);
+        self.print_signature(func, param_mode, &method_sig);
+        let method = String::from(mem::replace(&mut self.src, prev));
+        uwriteln!(
+            self.mock_methods,
+            "{method} {{\nunimplemented!(\"`{path}` isn't mocked\")\n}}"
+        );
+        let record = params
+            .iter()
+            .map(|p| format!("&{p}"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        uwriteln!(
+            self.recording_methods,
+            "{method} {{
+                self.calls.record(\"{path}\", &[{record}]);
+                self.inner.{name}({args})
+            }}"
+        );
+    }
+
+    /// Generates the `Mock` trait for the imports generated so far, along with
+    /// the functions to install one and `RecordingMock`, if any were
+    /// generated with `Opts::mock_imports`.
+    fn finish_mocks(&mut self) {
+        if self.mock_methods.is_empty() {
+            return;
+        }
+        let mock_methods = String::from(mem::take(&mut self.mock_methods));
+        let recording_methods = String::from(mem::take(&mut self.recording_methods));
+        let desc = match self.current_interface {
+            Some((_, key)) => format!("`{}`", self.resolve.name_world_key(key)),
+            None => "the world".to_string(),
+        };
+        uwrite!(
+            self.src,
+            "
+                /// The implementation of the functions imported by {desc}
+                /// which is used when not compiled for wasm, such as in unit
+                /// tests.
+                ///
+                /// Functions which aren't implemented panic when called.
+                #[cfg(not(target_arch = \"wasm32\"))]
+                #[allow(unused_variables)]
+                pub trait Mock {{
+                    {mock_methods}
+                }}
+
+                #[cfg(not(target_arch = \"wasm32\"))]
+                impl Mock for () {{}}
+
+                #[cfg(not(target_arch = \"wasm32\"))]
+                wit_bindgen::__require_mock_feature!();
+
+                #[cfg(not(target_arch = \"wasm32\"))]
+                wit_bindgen::mock::thread_local! {{
+                    static MOCK: wit_bindgen::mock::Slot<dyn Mock> = wit_bindgen::mock::Slot::new();
+                }}
+
+                /// Installs `mock` as the implementation of the functions
+                /// imported by {desc} on the current thread, returning the
+                /// one it replaces.
+                #[cfg(not(target_arch = \"wasm32\"))]
+                pub fn set_mock(
+                    mock: impl Mock + 'static,
+                ) -> Option<wit_bindgen::rt::boxed::Box<dyn Mock>> {{
+                    MOCK.with(|slot| slot.set(wit_bindgen::rt::boxed::Box::new(mock)))
+                }}
+
+                #[cfg(not(target_arch = \"wasm32\"))]
+                fn with_mock<R>(f: impl FnOnce(&mut (dyn Mock + 'static)) -> R) -> R {{
+                    MOCK.with(|slot| slot.with(\"{desc}\", f))
+                }}
+
+                /// A `Mock` which records the calls made to it before
+                /// forwarding them to `inner`.
+                #[cfg(not(target_arch = \"wasm32\"))]
+                pub struct RecordingMock<M> {{
+                    pub inner: M,
+                    calls: wit_bindgen::mock::Calls,
+                }}
+
+                #[cfg(not(target_arch = \"wasm32\"))]
+                impl<M: Mock> RecordingMock<M> {{
+                    pub fn new(inner: M) -> RecordingMock<M> {{
+                        RecordingMock {{
+                            inner,
+                            calls: wit_bindgen::mock::Calls::default(),
+                        }}
+                    }}
+
+                    /// Returns the calls made so far, which keeps being
+                    /// updated after this mock is installed.
+                    pub fn calls(&self) -> wit_bindgen::mock::Calls {{
+                        self.calls.clone()
+                    }}
+                }}
+
+                #[cfg(not(target_arch = \"wasm32\"))]
+                impl<M: Mock> Mock for RecordingMock<M> {{
+                    {recording_methods}
+                }}
+            "
+        );
+    }
+
     fn generate_guest_export(
         &mut self,
         func: &Function,
@@ -627,17 +979,18 @@ impl InterfaceGenerator<'_> {
             .clone()
             .unwrap_or(String::new());
         match interface_name {
-            Some(WorldKey::Name(name)) => {
-                prefix.push_str(&format!("exports::{}::", name.to_snake_case()));
+            Some(key @ WorldKey::Name(_)) => {
+                let module = self.gen.names.module(self.resolve, key, false);
+                prefix.push_str(&format!("exports::{module}::"));
             }
-            Some(WorldKey::Interface(id)) => {
+            Some(key @ WorldKey::Interface(id)) => {
                 let iface = &self.resolve.interfaces[*id];
                 let pkg = &self.resolve.packages[iface.package.unwrap()];
+                let module = self.gen.names.module(self.resolve, key, false);
                 prefix.push_str(&format!(
-                    "exports::{}::{}::{}::",
+                    "exports::{}::{}::{module}::",
                     pkg.name.namespace.to_snake_case(),
                     pkg.name.name.to_snake_case(),
-                    iface.name.as_ref().unwrap().to_snake_case()
                 ));
             }
             None => {}
@@ -726,8 +1079,30 @@ impl<'a> RustGenerator<'a> for InterfaceGenerator<'a> {
         self.gen.opts.duplicate_if_necessary
     }
 
+    fn names(&self) -> &Names {
+        &self.gen.names
+    }
+
+    fn type_attributes(&self) -> &TypeAttributes {
+        &self.gen.type_attributes
+    }
+
+    fn interface_id(&self) -> Option<InterfaceId> {
+        self.current_interface.map(|(id, _)| id)
+    }
+
+    fn path_to_type(&self, id: TypeId) -> Option<String> {
+        self.gen.with.types.get(&id).cloned()
+    }
+
     fn path_to_interface(&self, interface: InterfaceId) -> Option<String> {
+        if let Some(path) = self.gen.with.interfaces.get(&interface) {
+            return Some(path.clone());
+        }
         let mut path = String::new();
+        if self.stub {
+            return Some(self.gen.interface_names[&interface].clone());
+        }
         if let Some((cur, name)) = self.current_interface {
             if cur == interface {
                 return None;
@@ -765,12 +1140,26 @@ impl<'a> RustGenerator<'a> for InterfaceGenerator<'a> {
         "wit_bindgen::rt::string::String"
     }
 
+    fn future_name(&self) -> &'static str {
+        "wit_bindgen::rt::Future"
+    }
+
+    fn stream_name(&self) -> &'static str {
+        "wit_bindgen::rt::Stream"
+    }
+
     fn push_str(&mut self, s: &str) {
         self.src.push_str(s);
     }
 
     fn info(&self, ty: TypeId) -> TypeInfo {
-        self.gen.types.get(ty)
+        let mut info = self.gen.types.get(ty);
+        // Types defined elsewhere are only available in their owned form.
+        if self.gen.with.is_mapped(self.resolve, ty) {
+            info.owned |= info.borrowed;
+            info.borrowed = false;
+        }
+        info
     }
 
     fn types_mut(&mut self) -> &mut Types {
@@ -800,6 +1189,15 @@ impl<'a> wit_bindgen_core::InterfaceGenerator<'a> for InterfaceGenerator<'a> {
         self.resolve
     }
 
+    fn types(&mut self, iface: InterfaceId) -> Result<(), Diagnostic> {
+        for (name, id) in self.resolve.interfaces[iface].types.iter() {
+            if !self.gen.with.is_mapped(self.resolve, *id) {
+                self.define_type(name, *id)?;
+            }
+        }
+        Ok(())
+    }
+
     fn type_record(&mut self, id: TypeId, _name: &str, record: &Record, docs: &Docs) {
         self.print_typedef_record(id, record, docs, false);
     }
@@ -808,13 +1206,26 @@ impl<'a> wit_bindgen_core::InterfaceGenerator<'a> for InterfaceGenerator<'a> {
         self.print_typedef_tuple(id, tuple, docs);
     }
 
-    fn type_flags(&mut self, _id: TypeId, name: &str, flags: &Flags, docs: &Docs) {
+    fn type_flags(&mut self, id: TypeId, _name: &str, flags: &Flags, docs: &Docs) {
         self.src.push_str("wit_bindgen::bitflags::bitflags! {\n");
         self.rustdoc(docs);
         let repr = RustFlagsRepr::new(flags);
+        self.print_derives_and_attributes(
+            id,
+            &[
+                "PartialEq",
+                "Eq",
+                "PartialOrd",
+                "Ord",
+                "Hash",
+                "Debug",
+                "Clone",
+                "Copy",
+            ],
+        );
         self.src.push_str(&format!(
-            "#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Clone, Copy)]\npub struct {}: {repr} {{\n",
-            name.to_upper_camel_case(),
+            "pub struct {}: {repr} {{\n",
+            self.gen.names.type_name(self.resolve, id),
         ));
         for (i, flag) in flags.flags.iter().enumerate() {
             self.rustdoc(&flag.docs);
@@ -844,8 +1255,8 @@ impl<'a> wit_bindgen_core::InterfaceGenerator<'a> for InterfaceGenerator<'a> {
         self.print_typedef_result(id, result, docs);
     }
 
-    fn type_enum(&mut self, id: TypeId, name: &str, enum_: &Enum, docs: &Docs) {
-        self.print_typedef_enum(id, name, enum_, docs, &[], Box::new(|_| String::new()));
+    fn type_enum(&mut self, id: TypeId, _name: &str, enum_: &Enum, docs: &Docs) {
+        self.print_typedef_enum(id, enum_, docs, &[], Box::new(|_| String::new()));
     }
 
     fn type_alias(&mut self, id: TypeId, _name: &str, ty: &Type, docs: &Docs) {
@@ -856,14 +1267,38 @@ impl<'a> wit_bindgen_core::InterfaceGenerator<'a> for InterfaceGenerator<'a> {
         self.print_type_list(id, ty, docs);
     }
 
-    fn type_builtin(&mut self, _id: TypeId, name: &str, ty: &Type, docs: &Docs) {
+    fn type_builtin(&mut self, id: TypeId, _name: &str, ty: &Type, docs: &Docs) {
         self.rustdoc(docs);
-        self.src
-            .push_str(&format!("pub type {}", name.to_upper_camel_case()));
+        self.src.push_str(&format!(
+            "pub type {}",
+            self.gen.names.type_name(self.resolve, id)
+        ));
         self.src.push_str(" = ");
         self.print_ty(ty, TypeMode::Owned);
         self.src.push_str(";\n");
     }
+
+    fn type_future(
+        &mut self,
+        id: TypeId,
+        _name: &str,
+        payload: Option<&Type>,
+        docs: &Docs,
+    ) -> Result<(), Diagnostic> {
+        self.print_typedef_future(id, payload, docs);
+        Ok(())
+    }
+
+    fn type_stream(
+        &mut self,
+        id: TypeId,
+        _name: &str,
+        stream: &Stream,
+        docs: &Docs,
+    ) -> Result<(), Diagnostic> {
+        self.print_typedef_stream(id, stream, docs);
+        Ok(())
+    }
 }
 
 struct FunctionBindgen<'a, 'b> {
@@ -1033,8 +1468,8 @@ impl Bindgen for FunctionBindgen<'_, '_> {
         &self.gen.sizes
     }
 
-    fn is_list_canonical(&self, resolve: &Resolve, ty: &Type) -> bool {
-        resolve.all_bits_valid(ty)
+    fn is_list_canonical(&self, _resolve: &Resolve, ty: &Type) -> bool {
+        self.gen.gen.types.is_plain_old_data(ty)
     }
 
     fn emit(
@@ -1187,8 +1622,8 @@ impl Bindgen for FunctionBindgen<'_, '_> {
                 let op0 = &operands[0];
                 self.push_str(&format!("match {op0} {{\n"));
                 let name = self.typename_lower(*ty);
-                for (case, block) in variant.cases.iter().zip(blocks) {
-                    let case_name = case.name.to_upper_camel_case();
+                for (i, (case, block)) in variant.cases.iter().zip(blocks).enumerate() {
+                    let case_name = self.gen.names().case(*ty, i, &case.name);
                     self.push_str(&format!("{name}::{case_name}"));
                     if case.ty.is_some() {
                         self.push_str(&format!("(e) => {block},\n"));
@@ -1199,9 +1634,7 @@ impl Bindgen for FunctionBindgen<'_, '_> {
                 self.push_str("};\n");
             }
 
-            Instruction::VariantLift {
-                name, variant, ty, ..
-            } => {
+            Instruction::VariantLift { variant, ty, .. } => {
                 let mut result = String::new();
                 result.push_str("{");
 
@@ -1218,7 +1651,7 @@ impl Bindgen for FunctionBindgen<'_, '_> {
                     result.push_str("#[cfg(not(debug_assertions))]");
                     result.push_str("{");
                     result.push_str("::core::mem::transmute::<_, ");
-                    result.push_str(&name.to_upper_camel_case());
+                    result.push_str(&self.typename_lift(*ty));
                     result.push_str(">(");
                     result.push_str(op0);
                     result.push_str(" as ");
@@ -1240,7 +1673,7 @@ impl Bindgen for FunctionBindgen<'_, '_> {
                     } else {
                         String::new()
                     };
-                    let case = case.name.to_upper_camel_case();
+                    let case = self.gen.names().case(*ty, i, &case.name);
                     if i == variant.cases.len() - 1 {
                         result.push_str("#[cfg(debug_assertions)]");
                         result.push_str(&format!("{pat} => {name}::{case}{block},\n"));
@@ -1382,7 +1815,7 @@ impl Bindgen for FunctionBindgen<'_, '_> {
                 let mut result = format!("match {} {{\n", operands[0]);
                 let name = self.gen.type_path(*ty, true);
                 for (i, case) in enum_.cases.iter().enumerate() {
-                    let case = case.name.to_upper_camel_case();
+                    let case = self.gen.names().case(*ty, i, &case.name);
                     result.push_str(&format!("{name}::{case} => {i},\n"));
                 }
                 result.push_str("}");
@@ -1401,7 +1834,7 @@ impl Bindgen for FunctionBindgen<'_, '_> {
                 result.push_str(" {\n");
                 let name = self.gen.type_path(*ty, true);
                 for (i, case) in enum_.cases.iter().enumerate() {
-                    let case = case.name.to_upper_camel_case();
+                    let case = self.gen.names().case(*ty, i, &case.name);
                     result.push_str(&format!("{i} => {name}::{case},\n"));
                 }
                 result.push_str("_ => panic!(\"invalid enum discriminant\"),\n");
@@ -1612,7 +2045,8 @@ impl Bindgen for FunctionBindgen<'_, '_> {
                 self.let_results(func.results.len(), results);
                 match &func.kind {
                     FunctionKind::Freestanding => {
-                        self.push_str(&format!("T::{}", to_rust_ident(&func.name)));
+                        let name = self.gen.names().function(self.gen.interface_id(), func);
+                        self.push_str(&format!("T::{name}"));
                     }
                 }
                 self.push_str("(");
@@ -168,3 +168,311 @@ mod skip {
 
     export_baz!(Component);
 }
+
+// This is a static compilation test to check that `only` and `exclude` remove
+// items from the world, so nothing needs to be implemented for them.
+mod filter {
+    wit_bindgen::generate!({
+        inline: "
+            package my:inline
+
+            interface types {
+                record point { x: u32, y: u32 }
+                record wrapper { p: point }
+            }
+
+            world baz {
+                import types
+                import host: interface {
+                    use types.{point, wrapper}
+                    get: func() -> point
+                    wrap: func(w: wrapper)
+                }
+                export exports: interface {
+                    foo: func()
+                    bar: func()
+                }
+            }
+        ",
+        only: ["host", "exports"],
+        exclude: ["host#wrap", "exports#foo"],
+    });
+
+    #[allow(dead_code)]
+    fn get() -> my::inline::types::Point {
+        host::get()
+    }
+
+    struct Component;
+
+    impl exports::exports::Exports for Component {
+        fn bar() {}
+    }
+
+    export_baz!(Component);
+}
+
+// This is a static compilation test to check that items can be renamed, and
+// that identifiers which collide after renaming get distinct names.
+mod rename {
+    wit_bindgen::generate!({
+        inline: "
+            package my:inline
+
+            interface types {
+                record point { x: u32, y: u32 }
+                record coord { type: u32 }
+                enum color { red, green }
+            }
+
+            world baz {
+                import types
+                export exports: interface {
+                    use types.{point}
+                    draw: func(p: point)
+                }
+            }
+        ",
+        rename: [
+            "my:inline/types#point=Coord",
+            "my:inline/types#point.x=horizontal",
+            "my:inline/types#color.red=Crimson",
+            "exports#draw=paint",
+        ],
+    });
+
+    #[allow(dead_code)]
+    fn types(p: my::inline::types::Coord, c: my::inline::types::Coord2) -> u32 {
+        let _ = my::inline::types::Color::Crimson;
+        p.horizontal + p.y + c.type_
+    }
+
+    struct Component;
+
+    impl exports::exports::Exports for Component {
+        fn paint(_p: my::inline::types::Coord) {}
+    }
+
+    export_baz!(Component);
+}
+
+// This is a static compilation test to check that additional derives are only
+// added to types which can implement them, and that attributes are added.
+mod additional_derives {
+    wit_bindgen::generate!({
+        inline: "
+            package my:inline
+
+            interface types {
+                record point { x: u32, y: u32 }
+                record sample { value: float32 }
+                enum color { red, green }
+                flags perms { read, write }
+            }
+
+            world baz {
+                import types
+            }
+        ",
+        additional_derives: [
+            "Hash",
+            "Default",
+            "my:inline/types#sample=std::cmp::Eq",
+        ],
+        additional_attributes: [
+            "#[must_use]",
+            "my:inline/types#point=#[doc(alias = \"coord\")]",
+        ],
+    });
+
+    use my::inline::types::{Color, Perms, Point, Sample};
+
+    fn hash<T: std::hash::Hash>(_: &T) {}
+
+    #[allow(dead_code)]
+    fn types(p: Point, c: Color, f: Perms) -> Sample {
+        hash(&p);
+        hash(&c);
+        hash(&f);
+        let _ = Point::default();
+        Sample::default()
+    }
+}
+
+// This is a static compilation test to check that interfaces and types can be
+// used from other bindings instead of being generated again.
+mod with {
+    mod shared {
+        wit_bindgen::generate!({
+            inline: "
+                package my:inline
+
+                interface types {
+                    record point { name: string, x: u32 }
+                    enum color { red, green }
+                }
+
+                interface other {
+                    record pair { a: u32, b: u32 }
+                }
+
+                world shared {
+                    import types
+                    import other
+                }
+            ",
+        });
+    }
+
+    wit_bindgen::generate!({
+        inline: "
+            package my:inline
+
+            interface types {
+                record point { name: string, x: u32 }
+                enum color { red, green }
+            }
+
+            interface other {
+                record pair { a: u32, b: u32 }
+                record unmapped { p: pair }
+            }
+
+            world baz {
+                import types
+                import other
+                export exports: interface {
+                    use types.{point, color}
+                    draw: func(p: point, c: color) -> point
+                }
+            }
+        ",
+        with: [
+            "my:inline/types=crate::with::shared::my::inline::types",
+            "my:inline/other#pair=crate::with::shared::my::inline::other::Pair",
+        ],
+    });
+
+    use shared::my::inline::{other::Pair, types::Point};
+
+    #[allow(dead_code)]
+    fn types(u: my::inline::other::Unmapped) -> Pair {
+        u.p
+    }
+
+    struct Component;
+
+    impl exports::exports::Exports for Component {
+        fn draw(p: Point, _c: shared::my::inline::types::Color) -> Point {
+            p
+        }
+    }
+
+    export_baz!(Component);
+}
+
+// Imports call into mocks when not compiled for wasm, so this runs natively.
+mod mock_imports {
+    wit_bindgen::generate!({
+        inline: "
+            package my:inline
+
+            interface logging {
+                record entry { level: u8, message: string }
+                log: func(e: entry)
+                count: func(prefix: string) -> u32
+            }
+
+            world baz {
+                import logging
+                import now: func() -> u64
+            }
+        ",
+        mock_imports,
+    });
+
+    use my::inline::logging::{self, Entry};
+
+    struct Logs;
+
+    impl logging::Mock for Logs {
+        fn count(&mut self, prefix: &str) -> u32 {
+            prefix.len() as u32
+        }
+    }
+
+    #[test]
+    fn recording() {
+        let mock = logging::RecordingMock::new(Logs);
+        let calls = mock.calls();
+        logging::set_mock(mock);
+
+        logging::log(Entry {
+            level: 1,
+            message: "hello",
+        });
+        assert_eq!(logging::count("abc"), 3);
+
+        let calls = calls.take();
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[0].function, "my:inline/logging#log");
+        assert_eq!(calls[1].function, "my:inline/logging#count");
+        assert_eq!(calls[1].args, ["\"abc\""]);
+    }
+
+    #[test]
+    #[should_panic(expected = "`my:inline/logging#count` isn't mocked")]
+    fn unimplemented() {
+        logging::set_mock(());
+        logging::count("abc");
+    }
+
+    #[test]
+    fn world_imports() {
+        struct Clock;
+
+        impl Mock for Clock {
+            fn now(&mut self) -> u64 {
+                42
+            }
+        }
+
+        set_mock(Clock);
+        assert_eq!(now(), 42);
+    }
+}
+
+// This is a static compilation test to check that `future` and `stream` types
+// can be defined, including inside records and variants which therefore can't
+// derive `Clone`, even though they can't be passed to functions yet.
+mod futures_and_streams {
+    wit_bindgen::generate!({
+        inline: "
+            package my:inline
+
+            interface types {
+                type f = future<u32>
+                type s = stream<u8>
+                record r { f: future<u32> }
+                variant v { s(stream<u8>), none }
+            }
+
+            world baz {
+                import types
+            }
+        ",
+    });
+
+    #[allow(dead_code)]
+    fn handles(f: &my::inline::types::F, s: &my::inline::types::S) -> (u32, u32) {
+        (f.handle(), s.handle())
+    }
+
+    #[allow(dead_code)]
+    fn containers(r: &my::inline::types::R, v: &my::inline::types::V) -> u32 {
+        match v {
+            my::inline::types::V::S(s) => r.f.handle() + s.handle(),
+            my::inline::types::V::None => r.f.handle(),
+        }
+    }
+}
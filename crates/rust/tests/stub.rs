@@ -0,0 +1,79 @@
+//! Checks that the `{world}_stub.rs` generated with `generate_stub` compiles
+//! against the bindings generated alongside it.
+
+use heck::*;
+use std::path::Path;
+use std::process::Command;
+
+macro_rules! stub_test {
+    ($id:ident $name:tt) => {
+        #[test]
+        fn $id() {
+            test_helpers::run_world_codegen_test(
+                "guest-rust-stub",
+                concat!(
+                    env!("CARGO_MANIFEST_DIR"),
+                    "/../../tests/codegen/",
+                    $name,
+                    ".wit"
+                )
+                .as_ref(),
+                |resolve, world, files| {
+                    let mut opts = wit_bindgen_rust::Opts::default();
+                    opts.generate_stub = true;
+                    opts.macro_call_prefix = Some("bindings::".to_string());
+                    opts.build()
+                        .generate(resolve, world, &Default::default(), files)
+                },
+                verify,
+            )
+        }
+    };
+}
+
+// These worlds export interfaces as well as functions, so the stub implements
+// traits in `exports` besides the world's own.
+stub_test!(exports_sharing_names "exports-sharing-names");
+stub_test!(issue573 "issue573");
+
+// Checks a crate with the bindings in a `bindings` module, which is where
+// the stub expects them with `--macro-call-prefix bindings::`, and the stub
+// at its root.
+fn verify(dir: &Path, name: &str) {
+    let snake = name.to_snake_case();
+    let guest_rust = Path::new(env!("CARGO_MANIFEST_DIR")).join("../guest-rust");
+    let manifest = format!(
+        "[package]
+name = \"{}\"
+version = \"0.0.0\"
+edition = \"2021\"
+publish = false
+
+[lib]
+path = \"lib.rs\"
+
+[dependencies]
+wit-bindgen = {{ path = {:?} }}
+
+[workspace]
+",
+        name.to_kebab_case(),
+        guest_rust.display().to_string(),
+    );
+    std::fs::write(dir.join("Cargo.toml"), manifest).expect("failed to write Cargo.toml");
+    let lib = format!(
+        "mod bindings {{
+    include!(\"{snake}.rs\");
+    pub(crate) use export_{snake};
+}}
+
+include!(\"{snake}_stub.rs\");
+"
+    );
+    std::fs::write(dir.join("lib.rs"), lib).expect("failed to write lib.rs");
+
+    let mut cmd = Command::new("cargo");
+    cmd.arg("check");
+    cmd.current_dir(dir);
+    test_helpers::run_command(&mut cmd);
+}
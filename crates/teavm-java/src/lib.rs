@@ -2,20 +2,23 @@ use heck::{ToLowerCamelCase, ToShoutySnakeCase, ToUpperCamelCase};
 use std::{
     collections::{HashMap, HashSet},
     fmt::Write,
-    iter, mem,
+    mem,
     ops::Deref,
 };
 use wit_bindgen_core::{
-    uwrite, uwriteln,
+    check_types, format, reject_async_types, uwrite, uwriteln,
     wit_parser::{
         abi::{AbiVariant, Bindgen, Bitcast, Instruction, LiftLower, WasmType},
         Case, Docs, Enum, Flags, FlagsRepr, Function, FunctionKind, Int, InterfaceId, Record,
         Resolve, Result_, SizeAlign, Tuple, Type, TypeDef, TypeDefKind, TypeId, TypeOwner, Union,
         Variant, WorldId, WorldKey,
     },
-    Files, InterfaceGenerator as _, Ns, WorldGenerator,
+    Casing, Diagnostic, FileInfo, Files, InterfaceGenerator as _, Names, Ns, Profile, Renames,
+    Style, WorldGenerator,
 };
 
+const PRODUCER: &str = "wit-bindgen-teavm-java";
+
 const IMPORTS: &str = "\
 import java.nio.charset.StandardCharsets;
 import java.util.ArrayList;
@@ -32,6 +35,11 @@ pub struct Opts {
     /// Whether or not to generate a stub class for exported functions
     #[cfg_attr(feature = "clap", arg(long))]
     pub generate_stub: bool,
+    /// Overrides the Java identifier used for an item, in the form
+    /// `PATH=NAME`, for example `a:b/iface#ty.field=renamed`. May be
+    /// specified multiple times.
+    #[cfg_attr(feature = "clap", arg(long = "rename", value_name = "PATH=NAME"))]
+    pub rename: Vec<String>,
 }
 
 impl Opts {
@@ -66,6 +74,7 @@ pub struct TeaVmJava {
     world_fragments: Vec<InterfaceFragment>,
     sizes: SizeAlign,
     interface_names: HashMap<InterfaceId, String>,
+    names: Names,
 }
 
 impl TeaVmJava {
@@ -73,21 +82,45 @@ impl TeaVmJava {
         format!("{}.", self.name)
     }
 
-    fn interface<'a>(&'a mut self, resolve: &'a Resolve, name: &'a str) -> InterfaceGenerator<'a> {
+    fn interface<'a>(
+        &'a mut self,
+        resolve: &'a Resolve,
+        interface: Option<InterfaceId>,
+        name: &'a str,
+    ) -> InterfaceGenerator<'a> {
         InterfaceGenerator {
             src: String::new(),
             stub: String::new(),
             gen: self,
             resolve,
+            interface,
             name,
         }
     }
 }
 
 impl WorldGenerator for TeaVmJava {
-    fn preprocess(&mut self, resolve: &Resolve, world: WorldId) {
+    fn preprocess(&mut self, resolve: &Resolve, world: WorldId) -> Result<(), Diagnostic> {
+        let mut check_async = reject_async_types("TeaVM-Java");
+        check_types(resolve, world, |ty| match &ty.kind {
+            TypeDefKind::Flags(flags)
+                if !matches!(
+                    flags.repr(),
+                    FlagsRepr::U8 | FlagsRepr::U16 | FlagsRepr::U32(1) | FlagsRepr::U32(2)
+                ) =>
+            {
+                Some(Diagnostic::unsupported(
+                    "TeaVM-Java",
+                    format_args!("a `flags` type with {} members", flags.flags.len()),
+                ))
+            }
+            _ => check_async(ty),
+        })?;
+        self.names = Names::new(Profile::JAVA, Renames::parse(&self.opts.rename)?);
+        self.names.assign(resolve, world)?;
         self.name = world_name(resolve, world);
         self.sizes.fill(resolve);
+        Ok(())
     }
 
     fn import_interface(
@@ -96,17 +129,18 @@ impl WorldGenerator for TeaVmJava {
         key: &WorldKey,
         id: InterfaceId,
         _files: &mut Files,
-    ) {
-        let name = interface_name(resolve, key, Direction::Import);
+    ) -> Result<(), Diagnostic> {
+        let name = interface_name(resolve, &self.names, key, Direction::Import);
         self.interface_names.insert(id, name.clone());
-        let mut gen = self.interface(resolve, &name);
-        gen.types(id);
+        let mut gen = self.interface(resolve, Some(id), &name);
+        gen.types(id)?;
 
         for (_, func) in resolve.interfaces[id].functions.iter() {
             gen.import(&resolve.name_world_key(key), func);
         }
 
         gen.add_interface_fragment();
+        Ok(())
     }
 
     fn import_funcs(
@@ -115,15 +149,16 @@ impl WorldGenerator for TeaVmJava {
         world: WorldId,
         funcs: &[(&str, &Function)],
         _files: &mut Files,
-    ) {
+    ) -> Result<(), Diagnostic> {
         let name = world_name(resolve, world);
-        let mut gen = self.interface(resolve, &name);
+        let mut gen = self.interface(resolve, None, &name);
 
         for (_, func) in funcs {
             gen.import("$root", func);
         }
 
         gen.add_world_fragment();
+        Ok(())
     }
 
     fn export_interface(
@@ -132,17 +167,18 @@ impl WorldGenerator for TeaVmJava {
         key: &WorldKey,
         id: InterfaceId,
         _files: &mut Files,
-    ) {
-        let name = interface_name(resolve, key, Direction::Export);
+    ) -> Result<(), Diagnostic> {
+        let name = interface_name(resolve, &self.names, key, Direction::Export);
         self.interface_names.insert(id, name.clone());
-        let mut gen = self.interface(resolve, &name);
-        gen.types(id);
+        let mut gen = self.interface(resolve, Some(id), &name);
+        gen.types(id)?;
 
         for (_, func) in resolve.interfaces[id].functions.iter() {
             gen.export(Some(&resolve.name_world_key(key)), func);
         }
 
         gen.add_interface_fragment();
+        Ok(())
     }
 
     fn export_funcs(
@@ -151,15 +187,16 @@ impl WorldGenerator for TeaVmJava {
         world: WorldId,
         funcs: &[(&str, &Function)],
         _files: &mut Files,
-    ) {
+    ) -> Result<(), Diagnostic> {
         let name = world_name(resolve, world);
-        let mut gen = self.interface(resolve, &name);
+        let mut gen = self.interface(resolve, None, &name);
 
         for (_, func) in funcs {
             gen.export(None, func);
         }
 
         gen.add_world_fragment();
+        Ok(())
     }
 
     fn export_types(
@@ -168,18 +205,24 @@ impl WorldGenerator for TeaVmJava {
         world: WorldId,
         types: &[(&str, TypeId)],
         _files: &mut Files,
-    ) {
+    ) -> Result<(), Diagnostic> {
         let name = world_name(resolve, world);
-        let mut gen = self.interface(resolve, &name);
+        let mut gen = self.interface(resolve, None, &name);
 
         for (ty_name, ty) in types {
-            gen.define_type(ty_name, *ty);
+            gen.define_type(ty_name, *ty)?;
         }
 
         gen.add_world_fragment();
+        Ok(())
     }
 
-    fn finish(&mut self, resolve: &Resolve, id: WorldId, files: &mut Files) {
+    fn finish(
+        &mut self,
+        resolve: &Resolve,
+        id: WorldId,
+        files: &mut Files,
+    ) -> Result<(), Diagnostic> {
         let name = world_name(resolve, id);
         let (package, name) = split_qualified_name(&name);
 
@@ -221,7 +264,7 @@ impl WorldGenerator for TeaVmJava {
             wit_component::StringEncoding::UTF8,
             Some(&producers),
         )
-        .unwrap();
+        .map_err(|e| Diagnostic::new(format!("failed to encode component type: {e:?}")))?;
 
         let component_type = component_type
             .into_iter()
@@ -358,7 +401,11 @@ impl WorldGenerator for TeaVmJava {
         src.push_str("}\n");
 
         let directory = package.replace('.', "/");
-        files.push(&format!("{directory}/{name}.java"), indent(&src).as_bytes());
+        files.create(
+            &format!("{directory}/{name}.java"),
+            FileInfo::text(PRODUCER, "java"),
+            format(&src, Style::JAVA).as_bytes(),
+        )?;
 
         let generate_stub =
             |package: &str, name, fragments: &[InterfaceFragment], files: &mut Files| {
@@ -381,10 +428,11 @@ impl WorldGenerator for TeaVmJava {
                 );
 
                 let directory = package.replace('.', "/");
-                files.push(
+                files.create(
                     &format!("{directory}/{name}.java"),
-                    indent(&body).as_bytes(),
-                );
+                    FileInfo::text(PRODUCER, "java"),
+                    format(&body, Style::JAVA).as_bytes(),
+                )
             };
 
         if self.opts.generate_stub {
@@ -393,7 +441,7 @@ impl WorldGenerator for TeaVmJava {
                 format!("{name}Impl"),
                 &self.world_fragments,
                 files,
-            );
+            )?;
         }
 
         for (name, fragments) in &self.interface_fragments {
@@ -420,15 +468,18 @@ impl WorldGenerator for TeaVmJava {
             );
 
             let directory = package.replace('.', "/");
-            files.push(
+            files.create(
                 &format!("{directory}/{name}.java"),
-                indent(&body).as_bytes(),
-            );
+                FileInfo::text(PRODUCER, "java"),
+                format(&body, Style::JAVA).as_bytes(),
+            )?;
 
             if self.opts.generate_stub {
-                generate_stub(&package, format!("{name}Impl"), fragments, files);
+                generate_stub(&package, format!("{name}Impl"), fragments, files)?;
             }
         }
+
+        Ok(())
     }
 }
 
@@ -437,6 +488,7 @@ struct InterfaceGenerator<'a> {
     stub: String,
     gen: &'a mut TeaVmJava,
     resolve: &'a Resolve,
+    interface: Option<InterfaceId>,
     name: &'a str,
 }
 
@@ -632,12 +684,16 @@ impl InterfaceGenerator<'_> {
 
         if self.gen.opts.generate_stub {
             let sig = self.sig_string(func, true);
+            let body = match func.results.len() {
+                0 => "",
+                _ => r#"throw new RuntimeException("todo");"#,
+            };
 
             uwrite!(
                 self.stub,
                 r#"
                 {sig} {{
-                    throw new RuntimeException("todo");
+                    {body}
                 }}
                 "#
             );
@@ -707,11 +763,11 @@ impl InterfaceGenerator<'_> {
                         format!("{}Result<{ok}, {err}>", self.gen.qualifier())
                     }
                     _ => {
-                        if let Some(name) = &ty.name {
+                        if ty.name.is_some() {
                             format!(
                                 "{}{}",
                                 self.qualifier(qualifier, ty),
-                                name.to_upper_camel_case()
+                                self.gen.names.type_name(self.resolve, *id)
                             )
                         } else {
                             unreachable!()
@@ -779,8 +835,17 @@ impl InterfaceGenerator<'_> {
         }
     }
 
+    /// Returns the tag constant for the `index`th case of the variant or
+    /// union `id`, from which the case's constructor and accessor are named.
+    fn case_tag(&self, id: TypeId, index: usize, name: &str) -> String {
+        match &self.resolve.types[id].kind {
+            TypeDefKind::Union(_) => name.to_shouty_snake_case(),
+            _ => self.gen.names.case(id, index, name),
+        }
+    }
+
     fn sig_string(&mut self, func: &Function, qualifier: bool) -> String {
-        let name = func.name.to_java_ident();
+        let name = self.gen.names.function(self.interface, func);
 
         let result_type = match func.results.len() {
             0 => "void".into(),
@@ -821,19 +886,20 @@ impl<'a> wit_bindgen_core::InterfaceGenerator<'a> for InterfaceGenerator<'a> {
         self.resolve
     }
 
-    fn type_record(&mut self, _id: TypeId, name: &str, record: &Record, docs: &Docs) {
+    fn type_record(&mut self, id: TypeId, _name: &str, record: &Record, docs: &Docs) {
         self.print_docs(docs);
 
-        let name = name.to_upper_camel_case();
+        let name = self.gen.names.type_name(self.resolve, id);
 
         let parameters = record
             .fields
             .iter()
-            .map(|field| {
+            .enumerate()
+            .map(|(i, field)| {
                 format!(
                     "{} {}",
                     self.type_name(&field.ty),
-                    field.name.to_java_ident()
+                    self.gen.names.field(id, i, &field.name)
                 )
             })
             .collect::<Vec<_>>()
@@ -842,8 +908,9 @@ impl<'a> wit_bindgen_core::InterfaceGenerator<'a> for InterfaceGenerator<'a> {
         let assignments = record
             .fields
             .iter()
-            .map(|field| {
-                let name = field.name.to_java_ident();
+            .enumerate()
+            .map(|(i, field)| {
+                let name = self.gen.names.field(id, i, &field.name);
                 format!("this.{name} = {name};")
             })
             .collect::<Vec<_>>()
@@ -855,11 +922,12 @@ impl<'a> wit_bindgen_core::InterfaceGenerator<'a> for InterfaceGenerator<'a> {
             record
                 .fields
                 .iter()
-                .map(|field| {
+                .enumerate()
+                .map(|(i, field)| {
                     format!(
                         "public final {} {};",
                         self.type_name(&field.ty),
-                        field.name.to_java_ident()
+                        self.gen.names.field(id, i, &field.name)
                     )
                 })
                 .collect::<Vec<_>>()
@@ -880,17 +948,18 @@ impl<'a> wit_bindgen_core::InterfaceGenerator<'a> for InterfaceGenerator<'a> {
         );
     }
 
-    fn type_flags(&mut self, _id: TypeId, name: &str, flags: &Flags, docs: &Docs) {
+    fn type_flags(&mut self, id: TypeId, _name: &str, flags: &Flags, docs: &Docs) {
         self.print_docs(docs);
 
-        let name = name.to_upper_camel_case();
+        let name = self.gen.names.type_name(self.resolve, id);
 
         let ty = match flags.repr() {
             FlagsRepr::U8 => "byte",
             FlagsRepr::U16 => "short",
             FlagsRepr::U32(1) => "int",
             FlagsRepr::U32(2) => "long",
-            repr => todo!("flags {repr:?}"),
+            // Rejected in `preprocess`.
+            repr => unreachable!("flags {repr:?}"),
         };
 
         let flags = flags
@@ -898,7 +967,7 @@ impl<'a> wit_bindgen_core::InterfaceGenerator<'a> for InterfaceGenerator<'a> {
             .iter()
             .enumerate()
             .map(|(i, flag)| {
-                let flag_name = flag.name.to_shouty_snake_case();
+                let flag_name = self.gen.names.case(id, i, &flag.name);
                 let suffix = if matches!(flags.repr(), FlagsRepr::U32(2)) {
                     "L"
                 } else {
@@ -931,18 +1000,19 @@ impl<'a> wit_bindgen_core::InterfaceGenerator<'a> for InterfaceGenerator<'a> {
         self.type_name(&Type::Id(id));
     }
 
-    fn type_variant(&mut self, _id: TypeId, name: &str, variant: &Variant, docs: &Docs) {
+    fn type_variant(&mut self, id: TypeId, _name: &str, variant: &Variant, docs: &Docs) {
         self.print_docs(docs);
 
-        let name = name.to_upper_camel_case();
+        let name = self.gen.names.type_name(self.resolve, id);
         let tag_type = int_type(variant.tag());
 
         let constructors = variant
             .cases
             .iter()
-            .map(|case| {
-                let case_name = case.name.to_java_ident();
-                let tag = case.name.to_shouty_snake_case();
+            .enumerate()
+            .map(|(i, case)| {
+                let tag = self.case_tag(id, i, &case.name);
+                let case_name = tag.to_java_ident();
                 let (parameter, argument) = if let Some(ty) = self.non_empty_type(case.ty.as_ref())
                 {
                     (
@@ -966,10 +1036,11 @@ impl<'a> wit_bindgen_core::InterfaceGenerator<'a> for InterfaceGenerator<'a> {
         let accessors = variant
             .cases
             .iter()
-            .filter_map(|case| {
+            .enumerate()
+            .filter_map(|(i, case)| {
                 self.non_empty_type(case.ty.as_ref()).map(|ty| {
-                    let case_name = case.name.to_upper_camel_case();
-                    let tag = case.name.to_shouty_snake_case();
+                    let tag = self.case_tag(id, i, &case.name);
+                    let case_name = tag.to_upper_camel_case();
                     let ty = self.type_name(ty);
                     format!(
                         r#"public {ty} get{case_name}() {{
@@ -991,7 +1062,7 @@ impl<'a> wit_bindgen_core::InterfaceGenerator<'a> for InterfaceGenerator<'a> {
             .iter()
             .enumerate()
             .map(|(i, case)| {
-                let tag = case.name.to_shouty_snake_case();
+                let tag = self.case_tag(id, i, &case.name);
                 format!("public static final {tag_type} {tag} = {i};")
             })
             .collect::<Vec<_>>()
@@ -1045,15 +1116,16 @@ impl<'a> wit_bindgen_core::InterfaceGenerator<'a> for InterfaceGenerator<'a> {
         )
     }
 
-    fn type_enum(&mut self, _id: TypeId, name: &str, enum_: &Enum, docs: &Docs) {
+    fn type_enum(&mut self, id: TypeId, _name: &str, enum_: &Enum, docs: &Docs) {
         self.print_docs(docs);
 
-        let name = name.to_upper_camel_case();
+        let name = self.gen.names.type_name(self.resolve, id);
 
         let cases = enum_
             .cases
             .iter()
-            .map(|case| case.name.to_shouty_snake_case())
+            .enumerate()
+            .map(|(i, case)| self.gen.names.case(id, i, &case.name))
             .collect::<Vec<_>>()
             .join(", ");
 
@@ -1385,10 +1457,11 @@ impl Bindgen for FunctionBindgen<'_, '_> {
                 }
             },
 
-            Instruction::RecordLower { record, .. } => {
+            Instruction::RecordLower { record, ty, .. } => {
                 let op = &operands[0];
-                for field in record.fields.iter() {
-                    results.push(format!("({op}).{}", field.name.to_java_ident()));
+                for (i, field) in record.fields.iter().enumerate() {
+                    let name = self.gen.gen.names.field(*ty, i, &field.name);
+                    results.push(format!("({op}).{name}"));
                 }
             }
             Instruction::RecordLift { ty, .. } | Instruction::TupleLift { ty, .. } => {
@@ -1416,29 +1489,46 @@ impl Bindgen for FunctionBindgen<'_, '_> {
 
             Instruction::VariantLower {
                 variant,
+                ty,
                 results: lowered_types,
                 ..
-            } => self.lower_variant(
-                &variant
+            } => {
+                let cases = variant
                     .cases
                     .iter()
-                    .map(|case| (case.name.deref(), case.ty))
-                    .collect::<Vec<_>>(),
-                lowered_types,
-                &operands[0],
-                results,
-            ),
+                    .enumerate()
+                    .map(|(i, case)| (self.gen.case_tag(*ty, i, &case.name), case.ty))
+                    .collect::<Vec<_>>();
 
-            Instruction::VariantLift { variant, ty, .. } => self.lift_variant(
-                &Type::Id(*ty),
-                &variant
+                self.lower_variant(
+                    &cases
+                        .iter()
+                        .map(|(name, ty)| (name.deref(), *ty))
+                        .collect::<Vec<_>>(),
+                    lowered_types,
+                    &operands[0],
+                    results,
+                )
+            }
+
+            Instruction::VariantLift { variant, ty, .. } => {
+                let cases = variant
                     .cases
                     .iter()
-                    .map(|case| (case.name.deref(), case.ty))
-                    .collect::<Vec<_>>(),
-                &operands[0],
-                results,
-            ),
+                    .enumerate()
+                    .map(|(i, case)| (self.gen.case_tag(*ty, i, &case.name), case.ty))
+                    .collect::<Vec<_>>();
+
+                self.lift_variant(
+                    &Type::Id(*ty),
+                    &cases
+                        .iter()
+                        .map(|(name, ty)| (name.deref(), *ty))
+                        .collect::<Vec<_>>(),
+                    &operands[0],
+                    results,
+                )
+            }
 
             Instruction::UnionLower {
                 union,
@@ -1860,7 +1950,7 @@ impl Bindgen for FunctionBindgen<'_, '_> {
                 };
 
                 let module = self.gen.name;
-                let name = func.name.to_java_ident();
+                let name = self.gen.gen.names.function(self.gen.interface, func);
 
                 let args = operands.join(", ");
 
@@ -2177,34 +2267,6 @@ fn list_element_info(ty: &Type) -> (usize, &'static str) {
     }
 }
 
-fn indent(code: &str) -> String {
-    let mut indented = String::with_capacity(code.len());
-    let mut indent = 0;
-    let mut was_empty = false;
-    for line in code.lines() {
-        let trimmed = line.trim();
-        if trimmed.is_empty() {
-            if was_empty {
-                continue;
-            }
-            was_empty = true;
-        } else {
-            was_empty = false;
-        }
-
-        if trimmed.starts_with('}') {
-            indent -= 1;
-        }
-        indented.extend(iter::repeat(' ').take(indent * 4));
-        indented.push_str(trimmed);
-        if trimmed.ends_with('{') {
-            indent += 1;
-        }
-        indented.push('\n');
-    }
-    indented
-}
-
 fn is_primitive(ty: &Type) -> bool {
     matches!(
         ty,
@@ -2228,7 +2290,12 @@ fn world_name(resolve: &Resolve, world: WorldId) -> String {
     )
 }
 
-fn interface_name(resolve: &Resolve, name: &WorldKey, direction: Direction) -> String {
+fn interface_name(
+    resolve: &Resolve,
+    names: &Names,
+    name: &WorldKey,
+    direction: Direction,
+) -> String {
     let pkg = match name {
         WorldKey::Name(_) => None,
         WorldKey::Interface(id) => {
@@ -2237,11 +2304,7 @@ fn interface_name(resolve: &Resolve, name: &WorldKey, direction: Direction) -> S
         }
     };
 
-    let name = match name {
-        WorldKey::Name(name) => name,
-        WorldKey::Interface(id) => resolve.interfaces[*id].name.as_ref().unwrap(),
-    }
-    .to_upper_camel_case();
+    let name = names.module(resolve, name, matches!(direction, Direction::Import));
 
     format!(
         "wit.{}.{}{name}",
@@ -2282,17 +2345,6 @@ trait ToJavaIdent: ToOwned {
 
 impl ToJavaIdent for str {
     fn to_java_ident(&self) -> String {
-        // Escape Java keywords
-        // Source: https://docs.oracle.com/javase/tutorial/java/nutsandbolts/_keywords.html
-        match self {
-            "abstract" | "continue" | "for" | "new" | "switch" | "assert" | "default" | "goto"
-            | "package" | "synchronized" | "boolean" | "do" | "if" | "private" | "this"
-            | "break" | "double" | "implements" | "protected" | "throw" | "byte" | "else"
-            | "import" | "public" | "throws" | "case" | "enum" | "instanceof" | "return"
-            | "transient" | "catch" | "extends" | "int" | "short" | "try" | "char" | "final"
-            | "interface" | "static" | "void" | "class" | "finally" | "long" | "strictfp"
-            | "volatile" | "const" | "float" | "native" | "super" | "while" => format!("{self}_"),
-            _ => self.to_lower_camel_case(),
-        }
+        Profile::JAVA.ident(self, Casing::LowerCamel)
     }
 }
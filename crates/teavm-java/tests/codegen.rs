@@ -12,9 +12,10 @@ macro_rules! codegen_test {
                 |resolve, world, files| {
                     wit_bindgen_teavm_java::Opts {
                         generate_stub: true,
+                        ..Default::default()
                     }
                     .build()
-                    .generate(resolve, world, files)
+                    .generate(resolve, world, &Default::default(), files)
                 },
                 verify,
             )
@@ -6,7 +6,7 @@ use std::fs;
 use std::path::{Path, PathBuf};
 use std::process::Command;
 use wasm_encoder::{Encode, Section};
-use wit_bindgen_core::Files;
+use wit_bindgen_core::{Diagnostic, Files};
 use wit_component::StringEncoding;
 use wit_parser::{Resolve, UnresolvedPackage, WorldId};
 
@@ -60,7 +60,7 @@ stderr ---
 pub fn run_world_codegen_test(
     gen_name: &str,
     wit_path: &Path,
-    generate: fn(&Resolve, WorldId, &mut Files),
+    generate: fn(&Resolve, WorldId, &mut Files) -> Result<(), Diagnostic>,
     verify: fn(&Path, &str),
 ) {
     let (resolve, world) = parse_wit(wit_path);
@@ -71,7 +71,7 @@ pub fn run_world_codegen_test(
     let dir = test_directory("codegen", &gen_name, &world_name);
 
     let mut files = Default::default();
-    generate(&resolve, world, &mut files);
+    generate(&resolve, world, &mut files).unwrap();
     for (file, contents) in files.iter() {
         let dst = dir.join(file);
         std::fs::create_dir_all(dst.parent().unwrap()).unwrap();
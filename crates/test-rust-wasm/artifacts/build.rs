@@ -1,6 +1,8 @@
 use std::fs;
 use std::path::PathBuf;
 use std::process::Command;
+use wit_bindgen_core::Files;
+use wit_parser::Resolve;
 
 fn main() {
     std::env::remove_var("CARGO_ENCODED_RUSTFLAGS");
@@ -57,4 +59,25 @@ fn main() {
         ",
     );
     std::fs::write(out_dir.join("wasms.rs"), src).unwrap();
+
+    // Host bindings for the runtime tests which use `wit-bindgen-rust-host`
+    // rather than `wasmtime::component::bindgen!`, so that its output is
+    // compiled against `wasmtime`.
+    let hosts = out_dir.join("hosts");
+    fs::create_dir_all(&hosts).unwrap();
+    for name in ["smoke", "records"] {
+        let dir = PathBuf::from("../../../tests/runtime").join(name);
+        let mut resolve = Resolve::new();
+        let (pkg, _) = resolve.push_dir(&dir).unwrap();
+        let world = resolve.select_world(pkg, None).unwrap();
+        let mut files = Files::default();
+        wit_bindgen_rust_host::Opts::default()
+            .build()
+            .generate(&resolve, world, &Default::default(), &mut files)
+            .unwrap();
+        for (file, contents) in files.iter() {
+            fs::write(hosts.join(file), contents).unwrap();
+        }
+        println!("cargo:rerun-if-changed={}", dir.display());
+    }
 }
@@ -1 +1,13 @@
 include!(concat!(env!("OUT_DIR"), "/wasms.rs"));
+
+/// Bindings generated by `wit-bindgen-rust-host` for the runtime tests which
+/// use them instead of `wasmtime::component::bindgen!`.
+pub mod hosts {
+    pub mod smoke {
+        include!(concat!(env!("OUT_DIR"), "/hosts/smoke.rs"));
+    }
+
+    pub mod records {
+        include!(concat!(env!("OUT_DIR"), "/hosts/records.rs"));
+    }
+}
@@ -0,0 +1,5 @@
+pub fn edits_pub(old:&[&str], new:&[&str]) -> usize {
+  let e = edits(old,new);
+  let mut rebuilt=Vec::new(); let mut c=0; let mut oi=0;
+  for x in &e { match *x { Edit::Equal(i,j)=>{assert_eq!(old[i],new[j]); assert_eq!(i,oi); oi+=1; rebuilt.push(old[i]);} Edit::Delete(i,_)=>{assert_eq!(i,oi); oi+=1; c+=1} Edit::Insert(_,j)=>{c+=1; rebuilt.push(new[j]);} } }
+  assert_eq!(rebuilt,new); assert_eq!(oi, old.len()); c }
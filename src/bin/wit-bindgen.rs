@@ -1,9 +1,24 @@
 use anyhow::{bail, Context, Result};
 use clap::Parser;
-use std::path::PathBuf;
+use std::collections::{BTreeSet, HashSet};
+use std::io::Read;
+use std::path::{Path, PathBuf};
 use std::str;
-use wit_bindgen_core::{wit_parser, Files, WorldGenerator};
-use wit_parser::{Resolve, UnresolvedPackage};
+use std::time::{Duration, SystemTime};
+use wit_bindgen_core::{
+    render_error, unified_diff, wit_parser, FileKind, Files, Filter, WorldGenerator,
+};
+use wit_bindgen_deps::LockMode;
+use wit_component::DecodedWasm;
+use wit_parser::{Resolve, WorldId};
+
+mod build;
+#[cfg(feature = "compat")]
+mod compat;
+mod component;
+#[cfg(feature = "lint")]
+mod lint;
+mod new;
 
 /// Helper for passing VERSION to opt.
 /// If CARGO_VERSION_INFO is set, use it, otherwise use CARGO_PKG_VERSION.
@@ -30,6 +45,16 @@ enum Opt {
         #[clap(flatten)]
         args: Common,
     },
+    /// Generates bindings for Rust hosts embedding components with
+    /// `wasmtime`: traits to implement the imports, wrappers to call the
+    /// exports and functions adding the imports to a linker.
+    #[cfg(feature = "rust-host")]
+    RustHost {
+        #[clap(flatten)]
+        opts: wit_bindgen_rust_host::Opts,
+        #[clap(flatten)]
+        args: Common,
+    },
     /// Generates bindings for C/CPP guest modules.
     #[cfg(feature = "c")]
     C {
@@ -55,15 +80,82 @@ enum Opt {
         #[clap(flatten)]
         args: Common,
     },
+    /// Generates a JSON document describing a world, its interfaces, types
+    /// and functions, along with their canonical ABI details.
+    #[cfg(feature = "json")]
+    Json {
+        #[clap(flatten)]
+        opts: wit_bindgen_json::Opts,
+        #[clap(flatten)]
+        args: Common,
+    },
+    /// Generates a Markdown file describing the canonical ABI of each function
+    /// in a world: core signatures, memory layouts and lift/lower
+    /// instructions.
+    #[cfg(feature = "abi")]
+    Abi {
+        #[clap(flatten)]
+        opts: wit_bindgen_abi::Opts,
+        #[clap(flatten)]
+        args: Common,
+    },
+    /// Runs an external `wit-bindgen-<name>` generator, passing it the
+    /// resolved world as JSON on stdin.
+    #[cfg(feature = "plugin")]
+    Plugin {
+        #[clap(flatten)]
+        opts: wit_bindgen_plugin::Opts,
+        #[clap(flatten)]
+        args: Common,
+    },
+    /// Generates bindings for every target listed in a `wit-bindgen.toml`
+    /// configuration file.
+    Build(build::Build),
+    /// Turns a core wasm module into a component, using the world embedded in
+    /// it by the bindings generators or given with `--wit`.
+    Component(component::Component),
+    /// Compares two versions of a world, reporting each change along with
+    /// whether it breaks the canonical ABI or source compatibility. Fails if
+    /// any change is breaking.
+    #[cfg(feature = "compat")]
+    Compat(compat::Compat),
+    /// Runs configurable checks over a WIT package, such as for missing
+    /// documentation or names which are keywords in some languages. Fails if
+    /// any check reports an error.
+    #[cfg(feature = "lint")]
+    Lint(lint::Lint),
+    /// Creates a project implementing a world in one of the guest languages,
+    /// with the bindings, a stub implementation of the exports, a build
+    /// manifest and a README describing how to turn it into a component.
+    New(new::New),
 }
 
-#[derive(Debug, Parser)]
+impl Opt {
+    /// Returns whether this runs a bindings generator, as opposed to one of
+    /// the other subcommands.
+    fn is_generator(&self) -> bool {
+        match self {
+            Opt::Build(_) | Opt::Component(_) | Opt::New(_) => false,
+            #[cfg(feature = "compat")]
+            Opt::Compat(_) => false,
+            #[cfg(feature = "lint")]
+            Opt::Lint(_) => false,
+            _ => true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Parser)]
 struct Common {
     /// Where to place output files
     #[clap(long = "out-dir")]
     out_dir: Option<PathBuf>,
 
     /// WIT document to generate bindings for.
+    ///
+    /// This can also be a wasm binary: either a component, a WIT package
+    /// encoded as a component, or a core module carrying the
+    /// `component-type` custom sections emitted by the guest generators.
     #[clap(value_name = "DOCUMENT", index = 1)]
     wit: PathBuf,
 
@@ -78,80 +170,536 @@ struct Common {
     /// they're up-to-date with the source files.
     #[clap(long)]
     check: bool,
+
+    /// How `--check` reports files which aren't up to date: `human` prints a
+    /// unified diff of each one, `json` prints a single JSON document
+    /// describing them for use by other tools.
+    #[clap(long, value_enum, default_value = "human", requires = "check")]
+    check_format: CheckFormat,
+
+    /// Only generate bindings for interfaces, functions and types matching
+    /// this pattern, such as `wasi:io/streams` or `wasi:io/streams#read`. `*`
+    /// matches any sequence of characters. May be specified multiple times.
+    #[clap(long, value_name = "PATTERN")]
+    only: Vec<String>,
+
+    /// Don't generate bindings for interfaces, functions and types matching
+    /// this pattern, removing them from the world. Takes precedence over
+    /// `--only`. May be specified multiple times.
+    #[clap(long, value_name = "PATTERN")]
+    exclude: Vec<String>,
+
+    /// Write a Make-compatible dependency file to this path, listing every WIT
+    /// file read as a dependency of every file generated.
+    #[clap(long, value_name = "PATH")]
+    depfile: Option<PathBuf>,
+
+    /// Print a manifest listing each generated file along with its kind,
+    /// language and the generator which produced it.
+    #[clap(long)]
+    manifest: bool,
+
+    /// Keep running, regenerating bindings whenever the WIT input or any of
+    /// the files it was resolved from changes. Only files whose contents
+    /// changed are rewritten.
+    #[clap(long, conflicts_with = "check")]
+    watch: bool,
+
+    /// Fail if the lockfile of the dependencies listed in `deps.toml` is
+    /// missing or out of date, rather than updating it. It's never updated
+    /// by `--check`.
+    #[clap(long)]
+    locked: bool,
 }
 
-fn main() -> Result<()> {
-    let mut files = Files::default();
-    let (generator, opt) = match Opt::parse() {
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum CheckFormat {
+    Human,
+    Json,
+}
+
+/// A file found to be out of date by `--check`.
+struct Outdated {
+    path: PathBuf,
+    status: &'static str,
+    diff: Option<String>,
+}
+
+fn main() {
+    let result = match Opt::parse() {
+        Opt::Build(build) => build.run(),
+        Opt::Component(component) => component.run(),
+        #[cfg(feature = "compat")]
+        Opt::Compat(compat) => compat.run(),
+        #[cfg(feature = "lint")]
+        Opt::Lint(lint) => lint.run(),
+        Opt::New(new) => new.run(),
+        opt => run(opt),
+    };
+    if let Err(e) = result {
+        eprintln!("Error: {}", render_error(&e));
+        std::process::exit(1);
+    }
+}
+
+/// The output of a generator.
+struct Generated {
+    files: Files,
+    opts: Common,
+    /// The files the world was read from.
+    sources: Vec<PathBuf>,
+}
+
+/// Returns the generator selected by `opt`, along with the options common to
+/// all generators.
+fn generator(opt: &Opt) -> (Box<dyn WorldGenerator>, &Common) {
+    match opt {
         #[cfg(feature = "markdown")]
         Opt::Markdown { opts, args } => (opts.build(), args),
         #[cfg(feature = "c")]
         Opt::C { opts, args } => (opts.build(), args),
         #[cfg(feature = "rust")]
-        Opt::Rust { opts, args } => (opts.build(), args),
+        Opt::Rust { opts, args } => (opts.clone().build(), args),
+        #[cfg(feature = "rust-host")]
+        Opt::RustHost { opts, args } => (opts.build(), args),
         #[cfg(feature = "teavm-java")]
         Opt::TeavmJava { opts, args } => (opts.build(), args),
         #[cfg(feature = "go")]
         Opt::TinyGo { opts, args } => (opts.build(), args),
-    };
+        #[cfg(feature = "json")]
+        Opt::Json { opts, args } => (opts.build(), args),
+        #[cfg(feature = "abi")]
+        Opt::Abi { opts, args } => (opts.build(), args),
+        #[cfg(feature = "plugin")]
+        Opt::Plugin { opts, args } => (opts.build(), args),
+        opt => unreachable!("{opt:?} isn't a generator"),
+    }
+}
+
+/// Runs the generator selected by `opt`, returning the files it generated.
+fn generate(opt: Opt) -> Result<Generated> {
+    let mut files = Files::default();
+    let (generator, opt) = generator(&opt);
+    let sources = gen_world(generator, opt, &mut files)?;
+    Ok(Generated {
+        files,
+        opts: opt.clone(),
+        sources,
+    })
+}
+
+fn run(opt: Opt) -> Result<()> {
+    if generator(&opt).1.watch {
+        return watch(&opt);
+    }
+    let generated = generate(opt)?;
+    if generated.opts.check {
+        let outdated = check(&generated.files, &generated.opts)?;
+        return report(&outdated, generated.opts.check_format);
+    }
+    write(&generated)
+}
+
+/// Regenerates bindings whenever one of the files they're generated from
+/// changes, until interrupted.
+///
+/// Errors, such as from WIT which doesn't parse, are reported and then the
+/// files are watched for the next change.
+fn watch(opt: &Opt) -> Result<()> {
+    let mut sources = Vec::new();
+    loop {
+        let (generator, args) = generator(opt);
+        let mut files = Files::default();
+        match gen_world(generator, args, &mut files) {
+            Ok(new_sources) => {
+                sources = new_sources;
+                write(&Generated {
+                    files,
+                    opts: args.clone(),
+                    sources: sources.clone(),
+                })?;
+            }
+            Err(e) => eprintln!("Error: {}", render_error(&e)),
+        }
+        println!("Watching for changes...");
+
+        let snapshot = modified_times(&args.wit, &sources);
+        while modified_times(&args.wit, &sources) == snapshot {
+            std::thread::sleep(Duration::from_millis(250));
+        }
+    }
+}
+
+/// Returns the modification time of each of `sources`, and of `path` along
+/// with everything within it if it's a directory.
+///
+/// The sources of the last successful run are included since they may be
+/// outside of `path`, such as for dependencies. Everything within `path` is
+/// included as well so that changes fixing an error, or adding files, are
+/// noticed.
+fn modified_times(path: &Path, sources: &[PathBuf]) -> Vec<(PathBuf, Option<SystemTime>)> {
+    fn visit(path: &Path, times: &mut Vec<(PathBuf, Option<SystemTime>)>) {
+        let modified = std::fs::metadata(path).and_then(|m| m.modified()).ok();
+        times.push((path.to_path_buf(), modified));
+        if let Ok(entries) = std::fs::read_dir(path) {
+            let mut paths = entries
+                .filter_map(|e| e.ok().map(|e| e.path()))
+                .collect::<Vec<_>>();
+            paths.sort();
+            for path in paths {
+                visit(&path, times);
+            }
+        }
+    }
 
-    gen_world(generator, &opt, &mut files)?;
+    let mut times = Vec::new();
+    visit(path, &mut times);
+    for source in sources {
+        let modified = std::fs::metadata(source).and_then(|m| m.modified()).ok();
+        times.push((source.clone(), modified));
+    }
+    times
+}
 
+/// Writes generated files to disk, along with a depfile if one was requested.
+fn write(generated: &Generated) -> Result<()> {
+    let Generated {
+        files,
+        opts: opt,
+        sources,
+    } = generated;
+    let mut outputs = Vec::new();
     for (name, contents) in files.iter() {
+        let info = files.info(name).unwrap();
         let dst = match &opt.out_dir {
             Some(path) => path.join(name),
             None => name.into(),
         };
-        println!("Generating {:?}", dst);
-
-        if opt.check {
-            let prev = std::fs::read(&dst).with_context(|| format!("failed to read {:?}", dst))?;
-            if prev != contents {
-                // The contents differ. If it looks like textual contents, do a
-                // line-by-line comparison so that we can tell users what the
-                // problem is directly.
-                if let (Ok(utf8_prev), Ok(utf8_contents)) =
-                    (str::from_utf8(&prev), str::from_utf8(contents))
-                {
-                    if !utf8_prev
-                        .chars()
-                        .any(|c| c.is_control() && !matches!(c, '\n' | '\r' | '\t'))
-                        && utf8_prev.lines().eq(utf8_contents.lines())
-                    {
-                        bail!("{} differs only in line endings (CRLF vs. LF). If this is a text file, configure git to mark the file as `text eol=lf`.", dst.display());
-                    }
-                }
-                // The contents are binary or there are other differences; just
-                // issue a generic error.
-                bail!("not up to date: {}", dst.display());
-            }
+        // When watching, files which haven't changed are left alone so tools
+        // watching them in turn don't rebuild needlessly.
+        if opt.watch && std::fs::read(&dst).ok().as_deref() == Some(contents) {
+            outputs.push(dst);
             continue;
         }
+        println!("Generating {:?}", dst);
 
         if let Some(parent) = dst.parent() {
             std::fs::create_dir_all(parent)
                 .with_context(|| format!("failed to create {:?}", parent))?;
         }
         std::fs::write(&dst, contents).with_context(|| format!("failed to write {:?}", dst))?;
+        if info.executable {
+            set_executable(&dst)?;
+        }
+        outputs.push(dst);
+    }
+
+    if let Some(depfile) = &opt.depfile {
+        write_depfile(depfile, &outputs, sources)?;
+    }
+
+    if opt.manifest {
+        for (name, _) in files.iter() {
+            let info = files.info(name).unwrap();
+            println!(
+                "{name}\t{}\t{}\t{}{}",
+                info.kind,
+                info.language.as_deref().unwrap_or("-"),
+                info.producer,
+                if info.executable { "\texecutable" } else { "" },
+            );
+        }
     }
 
     Ok(())
 }
 
+/// Writes a Make-compatible dependency file at `path` stating that `outputs`
+/// depend on `sources`.
+///
+/// Like `gcc -MP`, an empty rule is added for each source so that make
+/// doesn't fail if one of them is later removed.
+fn write_depfile(path: &Path, outputs: &[PathBuf], sources: &[PathBuf]) -> Result<()> {
+    fn escape(path: &Path) -> String {
+        let mut escaped = String::new();
+        for c in path.display().to_string().chars() {
+            match c {
+                ' ' | '#' => escaped.push('\\'),
+                '$' => escaped.push('$'),
+                _ => {}
+            }
+            escaped.push(c);
+        }
+        escaped
+    }
+
+    let mut contents = String::new();
+    let targets = outputs.iter().map(|p| escape(p)).collect::<Vec<_>>();
+    contents.push_str(&targets.join(" "));
+    contents.push(':');
+    for source in sources {
+        contents.push_str(" \\\n  ");
+        contents.push_str(&escape(source));
+    }
+    contents.push('\n');
+    for source in sources {
+        contents.push('\n');
+        contents.push_str(&escape(source));
+        contents.push_str(":\n");
+    }
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create {:?}", parent))?;
+    }
+    std::fs::write(path, contents).with_context(|| format!("failed to write {:?}", path))
+}
+
+/// Compares the generated `files` with those on disk, returning every file
+/// which is out of date.
+fn check(files: &Files, opt: &Common) -> Result<Vec<Outdated>> {
+    let mut outdated = Vec::new();
+    let mut dirs = BTreeSet::new();
+    let mut generated = HashSet::new();
+    for (name, contents) in files.iter() {
+        let info = files.info(name).unwrap();
+        let dst = match &opt.out_dir {
+            Some(path) => path.join(name),
+            None => name.into(),
+        };
+        if let Some(parent) = dst.parent() {
+            dirs.insert(parent.to_path_buf());
+        }
+        generated.insert(dst.clone());
+
+        let prev = match std::fs::read(&dst) {
+            Ok(prev) => prev,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                let diff = match (info.kind, str::from_utf8(contents)) {
+                    (FileKind::Text, Ok(contents)) => Some(unified_diff(
+                        "",
+                        contents,
+                        "/dev/null",
+                        &format!("b/{}", dst.display()),
+                    )),
+                    _ => None,
+                };
+                outdated.push(Outdated {
+                    path: dst,
+                    status: "missing",
+                    diff,
+                });
+                continue;
+            }
+            Err(e) => return Err(e).with_context(|| format!("failed to read {:?}", dst)),
+        };
+        if prev == contents {
+            continue;
+        }
+        let text = match (info.kind, str::from_utf8(&prev), str::from_utf8(contents)) {
+            (FileKind::Text, Ok(prev), Ok(contents)) => Some((prev, contents)),
+            _ => None,
+        };
+        let (status, diff) = match text {
+            None => ("binary", None),
+            // If the only differences are in line endings there's no useful
+            // diff to show, so point out what the problem is directly.
+            Some((prev, contents)) if prev.lines().eq(contents.lines()) => ("line-endings", None),
+            Some((prev, contents)) => {
+                let name = dst.display();
+                let diff = unified_diff(prev, contents, &format!("a/{name}"), &format!("b/{name}"));
+                ("modified", Some(diff))
+            }
+        };
+        outdated.push(Outdated {
+            path: dst,
+            status,
+            diff,
+        });
+    }
+
+    // Look for files left over from a previous run which are no longer
+    // generated. Only the directories files were generated into are searched,
+    // and only files with the header `wit-bindgen` writes are considered.
+    for dir in dirs {
+        let dir = if dir.as_os_str().is_empty() {
+            PathBuf::from(".")
+        } else {
+            dir
+        };
+        let entries = match std::fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(_) => continue,
+        };
+        let mut stale = Vec::new();
+        for entry in entries {
+            let path = entry?.path();
+            let relative = path.strip_prefix(".").unwrap_or(&path).to_path_buf();
+            if !path.is_file() || generated.contains(&path) || generated.contains(&relative) {
+                continue;
+            }
+            if is_generated(&path) {
+                stale.push(relative);
+            }
+        }
+        stale.sort();
+        for path in stale {
+            outdated.push(Outdated {
+                path,
+                status: "stale",
+                diff: None,
+            });
+        }
+    }
+
+    Ok(outdated)
+}
+
+/// Prints the files found to be out of date by `check` in the given format,
+/// failing if there are any.
+fn report(outdated: &[Outdated], format: CheckFormat) -> Result<()> {
+    match format {
+        CheckFormat::Human => {
+            for file in outdated.iter() {
+                let path = file.path.display();
+                match file.status {
+                    "missing" => println!("would create: {path}"),
+                    "stale" => println!("no longer generated: {path}"),
+                    "binary" => println!("not up to date: {path} (binary file)"),
+                    "line-endings" => println!("{path} differs only in line endings (CRLF vs. LF). If this is a text file, configure git to mark the file as `text eol=lf`."),
+                    _ => println!("not up to date: {path}"),
+                }
+                if let Some(diff) = &file.diff {
+                    print!("{diff}");
+                }
+            }
+        }
+        CheckFormat::Json => {
+            let files = outdated
+                .iter()
+                .map(|file| {
+                    serde_json::json!({
+                        "path": file.path.display().to_string(),
+                        "status": file.status,
+                        "diff": file.diff,
+                    })
+                })
+                .collect::<Vec<_>>();
+            let doc = serde_json::json!({
+                "up-to-date": outdated.is_empty(),
+                "files": files,
+            });
+            println!("{}", serde_json::to_string_pretty(&doc)?);
+        }
+    }
+
+    if !outdated.is_empty() {
+        bail!("{} generated file(s) are not up to date", outdated.len());
+    }
+    Ok(())
+}
+
+/// Returns whether `path` looks like it was written by `wit-bindgen`, based on
+/// the header at the top of the file.
+fn is_generated(path: &std::path::Path) -> bool {
+    let mut header = [0; 256];
+    let n = match std::fs::File::open(path).and_then(|mut f| f.read(&mut header)) {
+        Ok(n) => n,
+        Err(_) => return false,
+    };
+    String::from_utf8_lossy(&header[..n])
+        .lines()
+        .take(3)
+        .any(|line| line.contains("Generated by `wit-bindgen`"))
+}
+
+#[cfg(unix)]
+fn set_executable(path: &std::path::Path) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let mut perms = std::fs::metadata(path)
+        .with_context(|| format!("failed to read metadata of {:?}", path))?
+        .permissions();
+    perms.set_mode(perms.mode() | 0o111);
+    std::fs::set_permissions(path, perms)
+        .with_context(|| format!("failed to set permissions of {:?}", path))
+}
+
+#[cfg(not(unix))]
+fn set_executable(_path: &std::path::Path) -> Result<()> {
+    Ok(())
+}
+
 fn gen_world(
     mut generator: Box<dyn WorldGenerator>,
     opts: &Common,
     files: &mut Files,
-) -> Result<()> {
-    let mut resolve = Resolve::default();
-    let pkg = if opts.wit.is_dir() {
-        resolve.push_dir(&opts.wit)?.0
+) -> Result<Vec<PathBuf>> {
+    let mode = if opts.locked {
+        LockMode::Locked
+    } else if opts.check {
+        LockMode::ReadOnly
     } else {
-        resolve.push(UnresolvedPackage::parse_file(&opts.wit)?)?
+        LockMode::Update
     };
-    let world = resolve.select_world(pkg, opts.world.as_deref())?;
-    generator.generate(&resolve, world, files);
-    Ok(())
+    let (resolve, world, sources) = load_world(&opts.wit, opts.world.as_deref(), mode)?;
+    let filter = Filter::new(opts.only.clone(), opts.exclude.clone())?;
+    generator.generate(&resolve, world, &filter, files)?;
+    Ok(sources)
+}
+
+/// Loads `world` from the WIT or wasm input at `path`, along with the list of
+/// files it was read from.
+///
+/// Dependencies listed in a `deps.toml` next to WIT input are loaded first,
+/// treating their lockfile according to `mode`.
+fn load_world(
+    path: &Path,
+    world: Option<&str>,
+    mode: LockMode,
+) -> Result<(Resolve, WorldId, Vec<PathBuf>)> {
+    if path.is_file() {
+        let bytes = std::fs::read(path).with_context(|| format!("failed to read {:?}", path))?;
+        if bytes.starts_with(b"\0asm") {
+            let (resolve, world) = decode_wasm(&bytes, world)
+                .with_context(|| format!("failed to decode a world from {:?}", path))?;
+            return Ok((resolve, world, vec![path.to_path_buf()]));
+        }
+    }
+
+    let mut resolve = Resolve::default();
+    let (pkg, sources) = wit_bindgen_deps::push_path(&mut resolve, path, mode)?;
+    let world = resolve.select_world(pkg, world)?;
+    Ok((resolve, world, sources))
+}
+
+/// Decodes the world described by a wasm binary.
+fn decode_wasm(bytes: &[u8], world: Option<&str>) -> Result<(Resolve, WorldId)> {
+    // Components are told apart from core modules by the layer field which
+    // follows the version in the preamble.
+    if bytes.get(6..8) != Some(&[1, 0]) {
+        if world.is_some() {
+            bail!("`--world` can't be used with a core module, which describes a single world");
+        }
+        let (_, bindgen) = wit_component::metadata::decode(bytes)?;
+        let w = &bindgen.resolve.worlds[bindgen.world];
+        if w.imports.is_empty() && w.exports.is_empty() {
+            bail!("core module doesn't have any `component-type` custom sections");
+        }
+        return Ok((bindgen.resolve, bindgen.world));
+    }
+
+    match wit_component::decode(bytes)? {
+        DecodedWasm::WitPackage(resolve, pkg) => {
+            let world = resolve.select_world(pkg, world)?;
+            Ok((resolve, world))
+        }
+        DecodedWasm::Component(resolve, id) => {
+            if world.is_some() {
+                bail!("`--world` can't be used with a component, which has a single world");
+            }
+            Ok((resolve, id))
+        }
+    }
 }
 
 #[test]
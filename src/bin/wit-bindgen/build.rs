@@ -0,0 +1,183 @@
+//! The `build` subcommand, which generates bindings for several targets
+//! described by a configuration file in one go.
+//!
+//! The configuration file is TOML with one `[[target]]` table per set of
+//! bindings:
+//!
+//! ```toml
+//! [[target]]
+//! generator = "rust"
+//! wit = "wit"
+//! world = "host"
+//! out-dir = "src/bindings"
+//!
+//! [target.options]
+//! std_feature = true
+//!
+//! [[target]]
+//! generator = "c"
+//! wit = "wit"
+//! world = "guest"
+//! out-dir = "c/bindings"
+//! options = { string_encoding = "utf16" }
+//! ```
+//!
+//! Paths are relative to the directory containing the configuration file.
+//! Each entry in `options` is passed to the generator as the command line
+//! option of the same name, so they're exactly the options listed by
+//! `wit-bindgen <generator> --help`: `true` passes a flag, `false` omits it,
+//! and arrays pass an option once for each element.
+
+use crate::{check, generate, report, write, CheckFormat, Opt};
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use toml::value::{Table, Value};
+
+#[derive(Debug, clap::Args)]
+pub struct Build {
+    /// The configuration file listing the targets to generate.
+    #[clap(long, value_name = "PATH", default_value = "wit-bindgen.toml")]
+    config: PathBuf,
+
+    /// Indicates that no files are written and instead the files of every
+    /// target are checked to be up-to-date with the source files.
+    #[clap(long)]
+    check: bool,
+
+    /// How `--check` reports files which aren't up to date, see the option of
+    /// the same name for individual generators.
+    #[clap(long, value_enum, default_value = "human", requires = "check")]
+    check_format: CheckFormat,
+
+    /// Fail if the lockfile of the dependencies listed in `deps.toml` is
+    /// missing or out of date, rather than updating it, see the option of
+    /// the same name for individual generators.
+    #[clap(long)]
+    locked: bool,
+}
+
+impl Build {
+    pub fn run(&self) -> Result<()> {
+        let contents = std::fs::read_to_string(&self.config)
+            .with_context(|| format!("failed to read {:?}", self.config))?;
+        let config: Value = toml::from_str(&contents)
+            .with_context(|| format!("failed to parse {:?}", self.config))?;
+        let base = self.config.parent().unwrap_or(Path::new(""));
+        let targets = match config.get("target") {
+            Some(Value::Array(targets)) => targets,
+            Some(_) => bail!("`target` in {:?} must be an array of tables", self.config),
+            None => bail!("{:?} doesn't declare any `[[target]]`s", self.config),
+        };
+
+        let mut outdated = Vec::new();
+        for (i, target) in targets.iter().enumerate() {
+            let mut args = target_args(base, target)
+                .with_context(|| format!("invalid target {} in {:?}", i + 1, self.config))?;
+            // Each target loads its WIT the way the generator would with the
+            // same flags, so `--check` leaves lockfiles alone.
+            if self.check {
+                args.push("--check".into());
+            }
+            if self.locked {
+                args.push("--locked".into());
+            }
+            let opt = Opt::try_parse_from(&args)
+                .with_context(|| format!("invalid target {} in {:?}", i + 1, self.config))?;
+            if !opt.is_generator() {
+                bail!("target {} in {:?} isn't a generator", i + 1, self.config);
+            }
+            let generated =
+                generate(opt).with_context(|| format!("failed to generate target {}", i + 1))?;
+            if self.check {
+                outdated.extend(check(&generated.files, &generated.opts)?);
+            } else {
+                write(&generated)?;
+            }
+        }
+
+        if self.check {
+            report(&outdated, self.check_format)?;
+        }
+        Ok(())
+    }
+}
+
+/// Translates a `[[target]]` table to the command line which would generate
+/// it.
+fn target_args(base: &Path, target: &Value) -> Result<Vec<OsString>> {
+    let table = match target {
+        Value::Table(table) => table,
+        _ => bail!("target must be a table"),
+    };
+    for key in table.keys() {
+        if !["generator", "wit", "world", "out-dir", "options"].contains(&key.as_str()) {
+            bail!("unknown key `{key}`");
+        }
+    }
+
+    let generator = string(table, "generator")?.context("missing `generator`")?;
+    let wit = string(table, "wit")?.context("missing `wit`")?;
+    let mut args = vec![OsString::from("wit-bindgen"), generator.into()];
+    if let Some(world) = string(table, "world")? {
+        args.push("--world".into());
+        args.push(world.into());
+    }
+    if let Some(out_dir) = string(table, "out-dir")? {
+        args.push("--out-dir".into());
+        args.push(base.join(out_dir).into());
+    }
+    match table.get("options") {
+        Some(Value::Table(options)) => {
+            for (key, value) in options {
+                option_args(&mut args, key, value)
+                    .with_context(|| format!("invalid option `{key}`"))?;
+            }
+        }
+        Some(_) => bail!("`options` must be a table"),
+        None => {}
+    }
+    args.push(base.join(wit).into());
+    Ok(args)
+}
+
+fn string<'a>(table: &'a Table, key: &str) -> Result<Option<&'a str>> {
+    match table.get(key) {
+        Some(Value::String(s)) => Ok(Some(s.as_str())),
+        Some(_) => bail!("`{key}` must be a string"),
+        None => Ok(None),
+    }
+}
+
+/// Appends the command line arguments for the generator option `key`, which
+/// may be written with either underscores or dashes.
+fn option_args(args: &mut Vec<OsString>, key: &str, value: &Value) -> Result<()> {
+    let flag = format!("--{}", key.replace('_', "-"));
+    match value {
+        Value::Boolean(true) => args.push(flag.into()),
+        Value::Boolean(false) => {}
+        Value::String(s) => {
+            args.push(flag.into());
+            args.push(s.into());
+        }
+        Value::Integer(i) => {
+            args.push(flag.into());
+            args.push(i.to_string().into());
+        }
+        Value::Float(f) => {
+            args.push(flag.into());
+            args.push(f.to_string().into());
+        }
+        Value::Array(values) => {
+            for value in values {
+                if let Value::Array(_) | Value::Table(_) = value {
+                    bail!("arrays may only contain strings, numbers and booleans");
+                }
+                option_args(args, key, value)?;
+            }
+        }
+        Value::Table(_) | Value::Datetime(_) => bail!("unsupported value `{value}`"),
+    }
+    Ok(())
+}
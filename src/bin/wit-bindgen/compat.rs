@@ -0,0 +1,41 @@
+//! The `compat` subcommand, which checks whether a new version of a world is
+//! compatible with an old one.
+
+use crate::load_world;
+use anyhow::{bail, Context, Result};
+use std::path::PathBuf;
+use wit_bindgen_deps::LockMode;
+
+#[derive(Debug, clap::Args)]
+pub struct Compat {
+    /// WIT document, or wasm binary, containing the old version of the world.
+    #[clap(value_name = "OLD")]
+    old: PathBuf,
+
+    /// WIT document, or wasm binary, containing the new version of the world.
+    #[clap(value_name = "NEW")]
+    new: PathBuf,
+
+    /// World to compare, selected in both documents as with the generators.
+    #[clap(short, long)]
+    world: Option<String>,
+}
+
+impl Compat {
+    pub fn run(&self) -> Result<()> {
+        let (old, old_world, _) = load_world(&self.old, self.world.as_deref(), LockMode::ReadOnly)
+            .with_context(|| format!("failed to load {:?}", self.old))?;
+        let (new, new_world, _) = load_world(&self.new, self.world.as_deref(), LockMode::ReadOnly)
+            .with_context(|| format!("failed to load {:?}", self.new))?;
+
+        let changes = wit_bindgen_compat::compare(&old, old_world, &new, new_world);
+        for change in changes.iter() {
+            println!("{change}");
+        }
+        let breaking = changes.iter().filter(|c| c.is_breaking()).count();
+        if breaking > 0 {
+            bail!("{breaking} breaking change(s)");
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,100 @@
+//! The `component` subcommand, which encodes a core wasm module as a
+//! component.
+
+use crate::load_world;
+use anyhow::{Context, Result};
+use std::borrow::Cow;
+use std::path::PathBuf;
+use wasm_encoder::{CustomSection, Encode, Section};
+use wit_bindgen_deps::LockMode;
+use wit_component::{ComponentEncoder, StringEncoding};
+
+#[derive(Debug, clap::Args)]
+pub struct Component {
+    /// The core wasm module to turn into a component.
+    #[clap(value_name = "MODULE")]
+    module: PathBuf,
+
+    /// Where to write the component.
+    #[clap(short, long, value_name = "PATH")]
+    output: PathBuf,
+
+    /// An adapter module which implements the core wasm imports of the module
+    /// from the module named `NAME` in terms of component imports, for example
+    /// `wasi_snapshot_preview1=adapter.wasm`. If the name is omitted it's
+    /// taken from the adapter's file name. May be specified multiple times.
+    #[clap(long = "adapt", value_name = "[NAME=]PATH")]
+    adapters: Vec<String>,
+
+    /// WIT document describing the world the module targets, for modules
+    /// which weren't built with the `component-type` custom sections emitted
+    /// by the bindings generators. It's embedded in the module alongside any
+    /// sections already present.
+    #[clap(long, value_name = "DOCUMENT")]
+    wit: Option<PathBuf>,
+
+    /// World within the `--wit` document to embed.
+    #[clap(short, long, requires = "wit")]
+    world: Option<String>,
+
+    /// The string encoding the module uses for the world given with `--wit`.
+    #[clap(long, default_value_t = StringEncoding::default(), requires = "wit")]
+    string_encoding: StringEncoding,
+
+    /// Don't validate the component after it's encoded.
+    #[clap(long)]
+    skip_validation: bool,
+}
+
+impl Component {
+    pub fn run(&self) -> Result<()> {
+        let mut module = std::fs::read(&self.module)
+            .with_context(|| format!("failed to read {:?}", self.module))?;
+
+        if let Some(wit) = &self.wit {
+            let (resolve, world, _) = load_world(wit, self.world.as_deref(), LockMode::ReadOnly)?;
+            let data =
+                wit_component::metadata::encode(&resolve, world, self.string_encoding, None)?;
+            let section = CustomSection {
+                name: Cow::Owned(format!("component-type:{}", resolve.worlds[world].name)),
+                data: Cow::Borrowed(&data),
+            };
+            module.push(section.id());
+            section.encode(&mut module);
+        }
+
+        let mut encoder = ComponentEncoder::default()
+            .module(&module)
+            .with_context(|| format!("failed to decode {:?}", self.module))?
+            .validate(!self.skip_validation);
+        for adapter in self.adapters.iter() {
+            let (name, path) = match adapter.split_once('=') {
+                Some((name, path)) => (name.to_string(), PathBuf::from(path)),
+                None => {
+                    let path = PathBuf::from(adapter);
+                    let name = path
+                        .file_stem()
+                        .and_then(|s| s.to_str())
+                        .with_context(|| format!("adapter {adapter:?} has no file name"))?
+                        .to_string();
+                    (name, path)
+                }
+            };
+            let wasm =
+                std::fs::read(&path).with_context(|| format!("failed to read {:?}", path))?;
+            encoder = encoder
+                .adapter(&name, &wasm)
+                .with_context(|| format!("failed to load adapter {:?}", path))?;
+        }
+        let component = encoder
+            .encode()
+            .with_context(|| format!("failed to encode a component from {:?}", self.module))?;
+
+        if let Some(parent) = self.output.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("failed to create {:?}", parent))?;
+        }
+        std::fs::write(&self.output, component)
+            .with_context(|| format!("failed to write {:?}", self.output))
+    }
+}
@@ -0,0 +1,95 @@
+//! The `lint` subcommand, which runs configurable checks over a WIT package.
+
+use anyhow::{bail, Context, Result};
+use std::path::PathBuf;
+use wit_bindgen_core::wit_parser::Resolve;
+use wit_bindgen_deps::LockMode;
+use wit_bindgen_lint::{Config, Level, Locations, Rule};
+
+#[derive(Debug, clap::Args)]
+pub struct Lint {
+    /// WIT document, or directory, containing the package to lint.
+    #[clap(value_name = "DOCUMENT")]
+    wit: PathBuf,
+
+    /// Don't run this rule. May be specified multiple times.
+    #[clap(short = 'A', long, value_name = "RULE")]
+    allow: Vec<String>,
+
+    /// Report findings of this rule as warnings. May be specified multiple
+    /// times.
+    #[clap(short = 'W', long, value_name = "RULE")]
+    warn: Vec<String>,
+
+    /// Report findings of this rule as errors, which fail the lint. `warnings`
+    /// turns every warning into an error. May be specified multiple times.
+    #[clap(short = 'D', long, value_name = "RULE")]
+    deny: Vec<String>,
+
+    /// The number of core wasm values a function's parameters may flatten to
+    /// before `large-signatures` reports it.
+    #[clap(long, value_name = "N", default_value_t = 16)]
+    max_flat_params: usize,
+
+    /// The number of core wasm values a function's results may flatten to
+    /// before `large-signatures` reports it.
+    #[clap(long, value_name = "N", default_value_t = 16)]
+    max_flat_results: usize,
+}
+
+impl Lint {
+    pub fn run(&self) -> Result<()> {
+        let mut config = Config::default();
+        // Levels are applied from the most to the least lenient, so the
+        // strictest wins if a rule is given more than one.
+        for (rules, level) in [
+            (&self.allow, Level::Allow),
+            (&self.warn, Level::Warn),
+            (&self.deny, Level::Deny),
+        ] {
+            for rule in rules {
+                if rule == "warnings" && level == Level::Deny {
+                    for rule in Rule::ALL {
+                        if config.level(rule) == Level::Warn {
+                            config.set_level(rule, Level::Deny);
+                        }
+                    }
+                    continue;
+                }
+                let rule = rule.parse::<Rule>().map_err(anyhow::Error::msg)?;
+                config.set_level(rule, level);
+            }
+        }
+        config.max_flat_params = self.max_flat_params;
+        config.max_flat_results = self.max_flat_results;
+
+        let mut resolve = Resolve::default();
+        let (pkg, sources) =
+            wit_bindgen_deps::push_path(&mut resolve, &self.wit, LockMode::ReadOnly)?;
+        let mut locations = Locations::default();
+        // Dependencies may also come from manifests and tarballs, which don't
+        // contain any items.
+        for source in sources
+            .iter()
+            .filter(|p| p.extension().and_then(|e| e.to_str()) == Some("wit"))
+        {
+            let contents = std::fs::read_to_string(source)
+                .with_context(|| format!("failed to read {:?}", source))?;
+            locations.add(source, &contents);
+        }
+
+        let findings = wit_bindgen_lint::lint(&resolve, pkg, &config, &locations);
+        for finding in findings.iter() {
+            println!("{finding}\n");
+        }
+        let errors = findings.iter().filter(|f| f.level == Level::Deny).count();
+        let warnings = findings.len() - errors;
+        if errors > 0 {
+            bail!("{errors} error(s) and {warnings} warning(s) found");
+        }
+        if warnings > 0 {
+            println!("{warnings} warning(s) found");
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,348 @@
+//! The `new` subcommand, which creates a project implementing a world in one
+//! of the guest languages.
+//!
+//! The project contains the bindings for the world, a stub implementation of
+//! its exports for the generator's `--generate-stub` option, a build manifest
+//! and a README describing how to build it and turn it into a component.
+
+use crate::load_world;
+use anyhow::{bail, Context, Result};
+use heck::{ToKebabCase, ToSnakeCase, ToUpperCamelCase};
+use std::path::PathBuf;
+use wit_bindgen_core::{wit_parser::Resolve, wit_parser::WorldId, Files, WorldGenerator};
+use wit_bindgen_deps::LockMode;
+
+#[derive(Debug, clap::Args)]
+pub struct New {
+    /// The language of the project.
+    #[clap(long, value_enum)]
+    lang: Lang,
+
+    /// The world the project implements.
+    #[clap(value_name = "WORLD")]
+    world: String,
+
+    /// The WIT document or directory the world is defined in.
+    #[clap(long, value_name = "PATH", default_value = "wit")]
+    wit: PathBuf,
+
+    /// The directory to create the project in, which defaults to the name of
+    /// the world. It must not exist already, or be empty.
+    #[clap(long = "out-dir")]
+    out_dir: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum Lang {
+    #[cfg(feature = "rust")]
+    Rust,
+    #[cfg(feature = "c")]
+    C,
+    #[cfg(feature = "go")]
+    Go,
+    #[cfg(feature = "teavm-java")]
+    TeavmJava,
+}
+
+/// The WASI adapter for modules without a `_start` function, which is what
+/// every language but Go produces.
+const REACTOR_ADAPTER: &str = "https://github.com/bytecodealliance/preview2-prototyping/releases/download/latest/wasi_snapshot_preview1.reactor.wasm";
+/// The WASI adapter for modules with a `_start` function.
+const COMMAND_ADAPTER: &str = "https://github.com/bytecodealliance/preview2-prototyping/releases/download/latest/wasi_snapshot_preview1.command.wasm";
+
+/// The files of a project, by path relative to its directory.
+type Project = Vec<(PathBuf, Vec<u8>)>;
+
+impl New {
+    pub fn run(&self) -> Result<()> {
+        let (resolve, world, _) = load_world(&self.wit, Some(&self.world), LockMode::ReadOnly)?;
+        let name = resolve.worlds[world].name.clone();
+        let dir = match &self.out_dir {
+            Some(dir) => dir.clone(),
+            None => PathBuf::from(name.to_kebab_case()),
+        };
+        if dir.exists() {
+            let mut entries = std::fs::read_dir(&dir)
+                .with_context(|| format!("failed to read directory {:?}", dir))?;
+            if entries.next().is_some() {
+                bail!("{:?} already exists and isn't empty", dir);
+            }
+        }
+
+        let project = match self.lang {
+            #[cfg(feature = "rust")]
+            Lang::Rust => rust(&resolve, world, &name)?,
+            #[cfg(feature = "c")]
+            Lang::C => c(&resolve, world, &name)?,
+            #[cfg(feature = "go")]
+            Lang::Go => go(&resolve, world, &name)?,
+            #[cfg(feature = "teavm-java")]
+            Lang::TeavmJava => teavm_java(&resolve, world, &name)?,
+        };
+
+        for (path, contents) in project {
+            let dst = dir.join(path);
+            println!("Creating {:?}", dst);
+            if let Some(parent) = dst.parent() {
+                std::fs::create_dir_all(parent)
+                    .with_context(|| format!("failed to create {:?}", parent))?;
+            }
+            std::fs::write(&dst, contents).with_context(|| format!("failed to write {:?}", dst))?;
+        }
+        Ok(())
+    }
+}
+
+/// Runs `generator`, returning the files it generated.
+fn generate(
+    mut generator: Box<dyn WorldGenerator>,
+    resolve: &Resolve,
+    world: WorldId,
+) -> Result<Files> {
+    let mut files = Files::default();
+    generator.generate(resolve, world, &Default::default(), &mut files)?;
+    Ok(files)
+}
+
+/// Returns the README of a project, which describes how to build it with
+/// `build` producing the core module `module`, and how to turn that into a
+/// component with `adapter`.
+fn readme(
+    name: &str,
+    regenerate: &str,
+    stub: &str,
+    build: &str,
+    module: &str,
+    adapter: &str,
+) -> Vec<u8> {
+    let kebab = name.to_kebab_case();
+    format!(
+        "# {name}
+
+This project implements the `{name}` world. It was created by `wit-bindgen new`,
+and the implementation of the world's exports in `{stub}` is a stub to be filled
+in.
+
+The bindings for the world can be regenerated after it changes with:
+
+```sh
+{regenerate}
+```
+
+where `<wit>` is the WIT package the world is defined in.
+
+## Building
+
+```sh
+{build}
+```
+
+## Creating a component
+
+The core wasm module built above is turned into a component with the
+`wit-bindgen` CLI. Modules built for WASI preview1 need an adapter which
+implements its imports in terms of the component model, which can be
+downloaded from {adapter}.
+
+```sh
+wit-bindgen component {module} \\
+    --adapt wasi_snapshot_preview1=wasi_snapshot_preview1.wasm \\
+    -o {kebab}.component.wasm
+```
+"
+    )
+    .into_bytes()
+}
+
+#[cfg(feature = "rust")]
+fn rust(resolve: &Resolve, world: WorldId, name: &str) -> Result<Project> {
+    let snake = name.to_snake_case();
+    let kebab = name.to_kebab_case();
+    let version = env!("CARGO_PKG_VERSION");
+    let mut opts = wit_bindgen_rust::Opts::default();
+    opts.generate_stub = true;
+    opts.macro_call_prefix = Some(format!("{snake}::"));
+    let files = generate(opts.build(), resolve, world)?;
+
+    let mut project = Project::new();
+    for (file, contents) in files.iter() {
+        if file == format!("{snake}_stub.rs") {
+            let mut lib = format!("#[macro_use]\nmod {snake};\n\n").into_bytes();
+            lib.extend_from_slice(contents);
+            project.push(("src/lib.rs".into(), lib));
+        } else {
+            project.push((PathBuf::from("src").join(file), contents.to_vec()));
+        }
+    }
+    let manifest = format!(
+        "[package]
+name = \"{kebab}\"
+version = \"0.1.0\"
+edition = \"2021\"
+
+[lib]
+crate-type = [\"cdylib\"]
+
+[dependencies]
+wit-bindgen = {{ version = \"{version}\", default-features = false, features = [\"realloc\"] }}
+"
+    );
+    project.push(("Cargo.toml".into(), manifest.into_bytes()));
+    project.push((
+        "README.md".into(),
+        readme(
+            name,
+            &format!(
+                "wit-bindgen rust --macro-call-prefix {snake}:: --out-dir src <wit> --world {name}"
+            ),
+            "src/lib.rs",
+            "cargo build --target wasm32-wasi --release",
+            &format!("target/wasm32-wasi/release/{snake}.wasm"),
+            REACTOR_ADAPTER,
+        ),
+    ));
+    Ok(project)
+}
+
+#[cfg(feature = "c")]
+fn c(resolve: &Resolve, world: WorldId, name: &str) -> Result<Project> {
+    let snake = name.to_snake_case();
+    let mut opts = wit_bindgen_c::Opts::default();
+    opts.generate_stub = true;
+    let files = generate(opts.build(), resolve, world)?;
+
+    let mut project = Project::new();
+    for (file, contents) in files.iter() {
+        let file = if file == format!("{snake}_stub.c") {
+            format!("{snake}_impl.c")
+        } else {
+            file.to_string()
+        };
+        project.push((file.into(), contents.to_vec()));
+    }
+    let makefile = format!(
+        "WASI_SDK_PATH ?= /opt/wasi-sdk
+CC := $(WASI_SDK_PATH)/bin/clang --sysroot=$(WASI_SDK_PATH)/share/wasi-sysroot
+CFLAGS ?= -Wall -Wextra -Wno-unused-parameter -O2
+
+{snake}.wasm: {snake}.c {snake}_impl.c {snake}_component_type.o {snake}.h
+\t$(CC) $(CFLAGS) -mexec-model=reactor -o $@ {snake}.c {snake}_impl.c {snake}_component_type.o
+
+clean:
+\trm -f {snake}.wasm
+
+.PHONY: clean
+"
+    );
+    project.push(("Makefile".into(), makefile.into_bytes()));
+    project.push((
+        "README.md".into(),
+        readme(
+            name,
+            &format!("wit-bindgen c <wit> --world {name}"),
+            &format!("{snake}_impl.c"),
+            "make WASI_SDK_PATH=/path/to/wasi-sdk",
+            &format!("{snake}.wasm"),
+            REACTOR_ADAPTER,
+        ),
+    ));
+    Ok(project)
+}
+
+#[cfg(feature = "go")]
+fn go(resolve: &Resolve, world: WorldId, name: &str) -> Result<Project> {
+    let snake = name.to_snake_case();
+    let kebab = name.to_kebab_case();
+    let mut opts = wit_bindgen_go::Opts::default();
+    opts.generate_stub = true;
+    let files = generate(opts.build(), resolve, world)?;
+
+    // The stub is the `main` package and imports the bindings from `gen`, as
+    // the module named after the world.
+    let mut project = Project::new();
+    for (file, contents) in files.iter() {
+        if file == format!("{kebab}_stub.go") {
+            project.push(("main.go".into(), contents.to_vec()));
+        } else {
+            project.push((PathBuf::from("gen").join(file), contents.to_vec()));
+        }
+    }
+    project.push((
+        "go.mod".into(),
+        format!("module {snake}\n\ngo 1.20\n").into_bytes(),
+    ));
+    project.push((
+        "README.md".into(),
+        readme(
+            name,
+            &format!("wit-bindgen tiny-go --out-dir gen <wit> --world {name}"),
+            "main.go",
+            &format!("tinygo build -target=wasi -o {snake}.wasm main.go"),
+            &format!("{snake}.wasm"),
+            COMMAND_ADAPTER,
+        ),
+    ));
+    Ok(project)
+}
+
+#[cfg(feature = "teavm-java")]
+fn teavm_java(resolve: &Resolve, world: WorldId, name: &str) -> Result<Project> {
+    let opts = wit_bindgen_teavm_java::Opts {
+        generate_stub: true,
+        ..Default::default()
+    };
+    let files = generate(opts.build(), resolve, world)?;
+
+    let java = PathBuf::from("src/main/java");
+    let mut project = Project::new();
+    for (file, contents) in files.iter() {
+        project.push((java.join(file), contents.to_vec()));
+    }
+    project.push((
+        java.join("Main.java"),
+        b"public class Main {\n    public static void main(String[] args) {}\n}\n".to_vec(),
+    ));
+
+    // TeaVM removes classes which aren't reachable from `Main`, so every class
+    // implementing exports must be preserved explicitly.
+    let makefile = "TEAVM_VERSION := 0.2.8
+MAVEN := https://repo.maven.apache.org/maven2/com/fermyon
+TEAVM_CLI := teavm-cli-$(TEAVM_VERSION).jar
+TEAVM_INTEROP := teavm-interop-$(TEAVM_VERSION).jar
+SOURCES := $(shell find src/main/java -name '*.java')
+CLASSES := $(subst /,.,$(patsubst src/main/java/%.java,%,$(SOURCES)))
+WASM := target/generated/wasm/teavm-wasm/classes.wasm
+
+$(WASM): $(SOURCES) $(TEAVM_CLI) $(TEAVM_INTEROP)
+\tjavac -cp $(TEAVM_INTEROP) -d target/classes $(SOURCES)
+\tjava -jar $(TEAVM_CLI) -p target/classes -d $(dir $(WASM)) -t wasm -O 1 \\
+\t\t$(addprefix --preserve-class ,$(CLASSES)) Main
+
+$(TEAVM_CLI):
+\tcurl -O $(MAVEN)/teavm-cli/$(TEAVM_VERSION)/$@
+
+$(TEAVM_INTEROP):
+\tcurl -O $(MAVEN)/teavm-interop/$(TEAVM_VERSION)/$@
+
+clean:
+\trm -rf target
+
+.PHONY: clean
+";
+    project.push(("Makefile".into(), makefile.as_bytes().to_vec()));
+    project.push((
+        "README.md".into(),
+        readme(
+            name,
+            &format!("wit-bindgen teavm-java --out-dir src/main/java <wit> --world {name}"),
+            &format!(
+                "src/main/java/wit/worlds/{}Impl.java",
+                name.to_upper_camel_case()
+            ),
+            "make",
+            "target/generated/wasm/teavm-wasm/classes.wasm",
+            REACTOR_ADAPTER,
+        ),
+    ));
+    Ok(project)
+}
@@ -15,6 +15,7 @@ mod lists;
 mod many_arguments;
 mod numbers;
 mod records;
+mod rust_host;
 mod smoke;
 mod strings;
 mod unions;
@@ -146,7 +147,8 @@ fn tests(name: &str) -> Result<Vec<PathBuf>> {
                 opts.string_encoding = wit_component::StringEncoding::UTF16;
             }
         }
-        opts.build().generate(&resolve, world, &mut files);
+        opts.build()
+            .generate(&resolve, world, &Default::default(), &mut files)?;
 
         for (file, contents) in files.iter() {
             let dst = out_dir.join(file);
@@ -221,9 +223,12 @@ fn tests(name: &str) -> Result<Vec<PathBuf>> {
 
         let snake = world_name.replace("-", "_");
         let mut files = Default::default();
-        wit_bindgen_go::Opts::default()
-            .build()
-            .generate(&resolve, world, &mut files);
+        wit_bindgen_go::Opts::default().build().generate(
+            &resolve,
+            world,
+            &Default::default(),
+            &mut files,
+        )?;
         let gen_dir = out_dir.join("gen");
         fs::create_dir_all(&gen_dir).unwrap();
         for (file, contents) in files.iter() {
@@ -316,9 +321,12 @@ fn tests(name: &str) -> Result<Vec<PathBuf>> {
         let java_dir = out_dir.join("src/main/java");
         let mut files = Default::default();
 
-        wit_bindgen_teavm_java::Opts::default()
-            .build()
-            .generate(&resolve, world, &mut files);
+        wit_bindgen_teavm_java::Opts::default().build().generate(
+            &resolve,
+            world,
+            &Default::default(),
+            &mut files,
+        )?;
 
         let mut dst_files = Vec::new();
 